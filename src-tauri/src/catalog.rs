@@ -0,0 +1,299 @@
+/**
+ * This module loads the app catalog from `~/.costa/catalog.json`.
+ * The catalog lists the debuggable apps with their download channels, url scheme rewrites and supported envs,
+ * so adding an app only needs an edit of the file, or of the catalog page in the webview.
+ */
+use std::{collections::HashSet, sync::Mutex};
+
+use debug_print::debug_println;
+use log::error;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    constant_local::{DCD_DOWNLOAD_URL, MCT_DOWNLOAD_URL, SIMULATOR_DOWNLOAD_URL},
+    file::check_file_if_exists,
+    path::get_catalog_path,
+};
+
+/**
+ * A download channel of the app, e.g. "latest", "beta"
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AppChannel {
+    pub name: String,
+    pub url: String,
+    /** expected sha256 of the package, checked after the download */
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+/**
+ * Rewrite the url scheme before opening it in the simulator, e.g. "sslocal" -> "snssdk36"
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SchemeRewrite {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CatalogApp {
+    /** unique id used by the commands & the tray, e.g. "mct" */
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub bundle_id: Option<String>,
+    /** the first channel is the default one */
+    #[serde(default)]
+    pub channels: Vec<AppChannel>,
+    #[serde(default)]
+    pub scheme_rewrites: Vec<SchemeRewrite>,
+    /** envs the app can switch to by the debug host, e.g. "boe", "ppe" */
+    #[serde(default)]
+    pub envs: Vec<String>,
+}
+
+impl CatalogApp {
+    /** get the channel by name, or the default channel if name is None */
+    pub fn get_channel(&self, name: Option<&str>) -> Option<&AppChannel> {
+        match name {
+            Some(name) => self.channels.iter().find(|c| c.name == name),
+            None => self.channels.first(),
+        }
+    }
+    /** rewrite the scheme of the url by the first matching rule, None if no rule matches */
+    pub fn rewrite_url(&self, url: &str) -> Option<String> {
+        let (scheme, rest) = url.split_once("://")?;
+        self.scheme_rewrites
+            .iter()
+            .find(|rule| rule.from.eq_ignore_ascii_case(scheme))
+            .map(|rule| format!("{}://{}", rule.to, rest))
+    }
+    pub fn supports_env(&self, env: &str) -> bool {
+        self.envs.iter().any(|e| env.starts_with(e.as_str()))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct AppCatalog {
+    pub apps: Vec<CatalogApp>,
+    /** page or package of the simulator runtime */
+    pub simulator_runtime_url: Option<String>,
+}
+
+impl AppCatalog {
+    pub fn get_app(&self, id: &str) -> Option<&CatalogApp> {
+        self.apps.iter().find(|app| app.id == id)
+    }
+    /**
+     * check the ids are unique & not empty, and every channel has a url
+     */
+    pub fn validate(&self) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        for app in &self.apps {
+            if app.id.trim().is_empty() {
+                return Err(format!("app id of {} is empty", app.name));
+            }
+            if !ids.insert(app.id.as_str()) {
+                return Err(format!("duplicate app id: {}", app.id));
+            }
+            if let Some(channel) = app.channels.iter().find(|c| c.url.trim().is_empty()) {
+                return Err(format!("url of {}/{} is empty", app.id, channel.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/**
+ * the catalog written on first launch, the apps & urls used to be hard-coded
+ */
+pub fn default_app_catalog() -> AppCatalog {
+    let channel = |url: &str| AppChannel {
+        name: "latest".to_string(),
+        url: url.to_string(),
+        sha256: None,
+    };
+    AppCatalog {
+        apps: vec![
+            CatalogApp {
+                id: "mct".to_string(),
+                name: "App1".to_string(),
+                bundle_id: None,
+                channels: vec![channel(MCT_DOWNLOAD_URL)],
+                scheme_rewrites: vec![],
+                envs: vec!["boe".to_string(), "ppe".to_string()],
+            },
+            CatalogApp {
+                id: "dcd".to_string(),
+                name: "App2".to_string(),
+                bundle_id: None,
+                channels: vec![channel(DCD_DOWNLOAD_URL)],
+                // opened by snssdk36 for now
+                scheme_rewrites: vec![SchemeRewrite {
+                    from: "sslocal".to_string(),
+                    to: "snssdk36".to_string(),
+                }],
+                envs: vec!["boe".to_string(), "ppe".to_string()],
+            },
+        ],
+        simulator_runtime_url: Some(SIMULATOR_DOWNLOAD_URL.to_string()),
+    }
+}
+
+/**
+ * The catalog loaded at startup, managed by tauri
+ */
+#[derive(Default)]
+pub struct CatalogState(Mutex<AppCatalog>);
+
+/**
+ * read the catalog file, the default catalog is written if the file does not exist
+ */
+pub fn read_app_catalog() -> Result<AppCatalog, String> {
+    let path = get_catalog_path();
+    if !check_file_if_exists(&path) {
+        debug_println!("catalog not found, write the default one: {:?}", path);
+        let catalog = default_app_catalog();
+        write_app_catalog(&catalog)?;
+        return Ok(catalog);
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let catalog: AppCatalog = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    catalog.validate()?;
+    Ok(catalog)
+}
+
+fn write_app_catalog(catalog: &AppCatalog) -> Result<(), String> {
+    let path = get_catalog_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(catalog).map_err(|e| e.to_string())?;
+    std::fs::write(&path, content).map_err(|e| e.to_string())
+}
+
+/**
+ * (re)load the catalog file into the state, the last loaded catalog is kept if the file is invalid
+ */
+pub fn load_app_catalog(handle: &AppHandle) -> Result<AppCatalog, String> {
+    let catalog = read_app_catalog().map_err(|e| {
+        error!("load app catalog error: {}", e);
+        e
+    })?;
+    *handle.state::<CatalogState>().0.lock().unwrap() = catalog.clone();
+    Ok(catalog)
+}
+
+pub fn get_app_catalog(handle: &AppHandle) -> AppCatalog {
+    handle.state::<CatalogState>().0.lock().unwrap().clone()
+}
+
+pub fn get_catalog_app(handle: &AppHandle, id: &str) -> Result<CatalogApp, String> {
+    get_app_catalog(handle)
+        .get_app(id)
+        .cloned()
+        .ok_or_else(|| format!("app not found in the catalog: {}", id))
+}
+
+/**
+ * validate & save the catalog edited in the webview
+ */
+pub fn save_app_catalog(handle: &AppHandle, catalog: AppCatalog) -> Result<(), String> {
+    catalog.validate()?;
+    write_app_catalog(&catalog)?;
+    *handle.state::<CatalogState>().0.lock().unwrap() = catalog;
+    Ok(())
+}
+
+/**
+ * rewrite the url by the rules of the app, or of the first app with a matching rule if app_id is None
+ */
+pub fn rewrite_app_url(handle: &AppHandle, app_id: Option<&str>, url: &str) -> String {
+    let catalog = get_app_catalog(handle);
+    let rewritten = match app_id {
+        Some(id) => catalog.get_app(id).and_then(|app| app.rewrite_url(url)),
+        None => catalog.apps.iter().find_map(|app| app.rewrite_url(url)),
+    };
+    rewritten.unwrap_or_else(|| url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(id: &str) -> CatalogApp {
+        CatalogApp {
+            id: id.to_string(),
+            name: id.to_uppercase(),
+            bundle_id: None,
+            channels: vec![AppChannel {
+                name: "latest".to_string(),
+                url: format!("https://example.com/{}.zip", id),
+                sha256: None,
+            }],
+            scheme_rewrites: vec![SchemeRewrite {
+                from: "sslocal".to_string(),
+                to: "snssdk36".to_string(),
+            }],
+            envs: vec!["boe".to_string(), "ppe".to_string()],
+        }
+    }
+
+    fn catalog(apps: Vec<CatalogApp>) -> AppCatalog {
+        AppCatalog {
+            apps,
+            simulator_runtime_url: None,
+        }
+    }
+
+    #[test]
+    fn rewrites_the_url_scheme() {
+        let app = app("dcd");
+        assert_eq!(
+            app.rewrite_url("sslocal://webview?url=https://example.com")
+                .as_deref(),
+            Some("snssdk36://webview?url=https://example.com")
+        );
+        // the scheme matches case-insensitively
+        assert_eq!(
+            app.rewrite_url("SSLocal://home").as_deref(),
+            Some("snssdk36://home")
+        );
+        assert_eq!(app.rewrite_url("https://example.com"), None);
+        // not a url
+        assert_eq!(app.rewrite_url("sslocal"), None);
+    }
+
+    #[test]
+    fn supports_the_envs_by_prefix() {
+        let app = app("mct");
+        assert!(app.supports_env("boe"));
+        assert!(app.supports_env("ppe_feature_a"));
+        assert!(!app.supports_env("prod"));
+    }
+
+    #[test]
+    fn validates_the_catalog() {
+        assert_eq!(catalog(vec![app("mct"), app("dcd")]).validate(), Ok(()));
+        assert_eq!(default_app_catalog().validate(), Ok(()));
+        assert_eq!(
+            catalog(vec![app("mct"), app("mct")]).validate(),
+            Err("duplicate app id: mct".to_string())
+        );
+        let mut unnamed = app(" ");
+        unnamed.name = "App3".to_string();
+        assert_eq!(
+            catalog(vec![unnamed]).validate(),
+            Err("app id of App3 is empty".to_string())
+        );
+        let mut no_url = app("mct");
+        no_url.channels[0].url = "".to_string();
+        assert_eq!(
+            catalog(vec![no_url]).validate(),
+            Err("url of mct/latest is empty".to_string())
+        );
+    }
+}
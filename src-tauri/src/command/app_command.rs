@@ -0,0 +1,64 @@
+use debug_print::debug_println;
+
+use crate::simulator::{
+    app::{get_installed_apps as get_app_list, refresh_installed_apps, InstalledApp},
+    backend::get_simctl_backend,
+};
+
+use super::util::get_target_device;
+
+/**
+ * get the apps installed on the device, or the first booted device if udid is None
+ * the cached list is returned unless `refresh` is true
+ */
+#[tauri::command]
+pub async fn get_installed_apps(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    refresh: Option<bool>,
+) -> Result<Vec<InstalledApp>, String> {
+    let device = get_target_device(&app, udid)?;
+    get_app_list(&app, &device, refresh.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn launch_installed_app(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    bundle_id: String,
+) -> Result<(), String> {
+    debug_println!("launch app: {}", bundle_id);
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    device.launch_app(backend.as_ref(), &bundle_id).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn terminate_installed_app(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    bundle_id: String,
+) -> Result<(), String> {
+    debug_println!("terminate app: {}", bundle_id);
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    device.terminate_app(backend.as_ref(), &bundle_id).await?;
+    Ok(())
+}
+
+/**
+ * uninstall the app, return the refreshed list
+ */
+#[tauri::command]
+pub async fn uninstall_installed_app(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    bundle_id: String,
+) -> Result<Vec<InstalledApp>, String> {
+    debug_println!("uninstall app: {}", bundle_id);
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    device.uninstall_app(backend.as_ref(), &bundle_id).await?;
+    refresh_installed_apps(&app, &device).await
+}
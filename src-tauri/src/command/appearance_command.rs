@@ -0,0 +1,38 @@
+use tauri::Manager;
+
+use crate::simulator::{
+    appearance::{self, DisplaySettings, DisplaySettingsCache},
+    backend::get_simctl_backend,
+};
+
+use super::util::get_target_device;
+
+/**
+ * read the appearance settings from the device, or the first booted device if udid is None
+ */
+#[tauri::command]
+pub async fn get_display_settings(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+) -> Result<DisplaySettings, String> {
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    let settings = appearance::read_display_settings(backend.as_ref(), &device.udid).await?;
+    app.state::<DisplaySettingsCache>()
+        .insert(&device.udid, settings.clone());
+    Ok(settings)
+}
+
+/**
+ * apply the fields which are set and save them for the next boot
+ * return the settings read back from the device
+ */
+#[tauri::command]
+pub async fn set_display_settings(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    settings: DisplaySettings,
+) -> Result<DisplaySettings, String> {
+    let device = get_target_device(&app, udid)?;
+    appearance::update_display_settings(&app, &device, &settings).await
+}
@@ -0,0 +1,51 @@
+use debug_print::debug_println;
+
+use crate::simulator::capture::{self, CaptureRecord, MaskPolicy, ScreenshotFormat};
+
+use super::util::get_target_device;
+
+/**
+ * take a screenshot of the device, or the first booted device if udid is None
+ */
+#[tauri::command]
+pub async fn take_screenshot(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    format: Option<ScreenshotFormat>,
+    mask: Option<MaskPolicy>,
+) -> Result<CaptureRecord, String> {
+    debug_println!("take screenshot: {:?}", udid);
+    let device = get_target_device(&app, udid)?;
+    capture::take_screenshot(&app, &device, format.unwrap_or_default(), mask).await
+}
+
+#[tauri::command]
+pub async fn start_recording(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    mask: Option<MaskPolicy>,
+) -> Result<(), String> {
+    debug_println!("start recording: {:?}", udid);
+    let device = get_target_device(&app, udid)?;
+    capture::start_recording(&app, &device, mask).await
+}
+
+#[tauri::command]
+pub async fn stop_recording(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+) -> Result<CaptureRecord, String> {
+    debug_println!("stop recording: {:?}", udid);
+    let device = get_target_device(&app, udid)?;
+    capture::stop_recording(&app, &device.udid).await
+}
+
+#[tauri::command]
+pub async fn get_capture_list(app: tauri::AppHandle) -> Result<Vec<CaptureRecord>, String> {
+    Ok(capture::get_capture_list(&app))
+}
+
+#[tauri::command]
+pub async fn delete_capture(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    capture::delete_capture(&app, &id)
+}
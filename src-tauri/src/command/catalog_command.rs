@@ -0,0 +1,22 @@
+use crate::catalog::{self, AppCatalog};
+
+#[tauri::command]
+pub fn get_app_catalog(app: tauri::AppHandle) -> Result<AppCatalog, String> {
+    Ok(catalog::get_app_catalog(&app))
+}
+
+/**
+ * save the catalog edited in the webview, the tray lists the new apps on the next tick
+ */
+#[tauri::command]
+pub fn save_app_catalog(app: tauri::AppHandle, catalog: AppCatalog) -> Result<(), String> {
+    catalog::save_app_catalog(&app, catalog)
+}
+
+/**
+ * load the catalog file again, e.g. after it is edited by hand
+ */
+#[tauri::command]
+pub fn reload_app_catalog(app: tauri::AppHandle) -> Result<AppCatalog, String> {
+    catalog::load_app_catalog(&app)
+}
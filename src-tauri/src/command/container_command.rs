@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use debug_print::debug_println;
+
+use crate::simulator::{
+    backend::get_simctl_backend,
+    container::{self, ContainerEntry, ContainerKind, FileContent},
+};
+
+use super::util::get_target_device;
+
+/**
+ * get the root path of the container on the device, or the first booted device if udid is None
+ */
+async fn get_root(
+    app: &tauri::AppHandle,
+    udid: Option<String>,
+    bundle_id: &str,
+    kind: &ContainerKind,
+) -> Result<std::path::PathBuf, String> {
+    let device = get_target_device(app, udid)?;
+    let backend = get_simctl_backend(app);
+    Ok(container::get_container_root(backend.as_ref(), &device.udid, bundle_id, kind).await?)
+}
+
+#[tauri::command]
+pub async fn get_app_container(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    bundle_id: String,
+    kind: ContainerKind,
+) -> Result<String, String> {
+    let root = get_root(&app, udid, &bundle_id, &kind).await?;
+    Ok(root.to_string_lossy().to_string())
+}
+
+/**
+ * key: app group id, value: container path
+ */
+#[tauri::command]
+pub async fn get_app_group_containers(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    bundle_id: String,
+) -> Result<HashMap<String, String>, String> {
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    Ok(container::get_group_containers(backend.as_ref(), &device.udid, &bundle_id).await?)
+}
+
+/**
+ * list a directory of the container, `path` is relative to the container root
+ */
+#[tauri::command]
+pub async fn list_container_dir(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    bundle_id: String,
+    kind: ContainerKind,
+    path: String,
+) -> Result<Vec<ContainerEntry>, String> {
+    let root = get_root(&app, udid, &bundle_id, &kind).await?;
+    Ok(container::list_dir(&root, &path)?)
+}
+
+/**
+ * read a file of the container, plists are converted to JSON
+ */
+#[tauri::command]
+pub async fn read_container_file(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    bundle_id: String,
+    kind: ContainerKind,
+    path: String,
+) -> Result<FileContent, String> {
+    let root = get_root(&app, udid, &bundle_id, &kind).await?;
+    Ok(container::read_file(&root, &path)?)
+}
+
+/**
+ * copy a file of the container to the host, return the host path
+ */
+#[tauri::command]
+pub async fn copy_from_container(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    bundle_id: String,
+    kind: ContainerKind,
+    path: String,
+    destination: String,
+) -> Result<String, String> {
+    debug_println!("copy {} out of {} to {}", path, bundle_id, destination);
+    let root = get_root(&app, udid, &bundle_id, &kind).await?;
+    Ok(container::copy_out(&root, &path, &destination)?)
+}
+
+/**
+ * copy a host file into a directory of the container, return the path relative to the container root
+ */
+#[tauri::command]
+pub async fn copy_to_container(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    bundle_id: String,
+    kind: ContainerKind,
+    source: String,
+    path: String,
+) -> Result<String, String> {
+    debug_println!("copy {} into {} at {}", source, bundle_id, path);
+    let root = get_root(&app, udid, &bundle_id, &kind).await?;
+    Ok(container::copy_in(&root, &source, &path)?)
+}
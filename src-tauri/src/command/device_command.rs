@@ -0,0 +1,112 @@
+use debug_print::debug_println;
+
+use crate::{
+    preference::{get_preferences, set_preferences},
+    simulator::{
+        appearance,
+        backend::get_simctl_backend,
+        command,
+        device_type::{DeviceSpec, DeviceType, ProvisionResult},
+    },
+};
+
+#[tauri::command]
+pub async fn get_device_type_list(app: tauri::AppHandle) -> Result<Vec<DeviceType>, String> {
+    Ok(command::get_all_device_types(get_simctl_backend(&app).as_ref()).await?)
+}
+
+/**
+ * create a device, return the udid of the new device
+ */
+#[tauri::command]
+pub async fn create_device(
+    app: tauri::AppHandle,
+    name: String,
+    device_type: String,
+    runtime: Option<String>,
+) -> Result<String, String> {
+    debug_println!("create device: {} {} {:?}", name, device_type, runtime);
+    Ok(command::create_device(
+        get_simctl_backend(&app).as_ref(),
+        &name,
+        &device_type,
+        runtime.as_deref(),
+    )
+    .await?)
+}
+
+/**
+ * clone a configured device, return the udid of the new device
+ */
+#[tauri::command]
+pub async fn clone_device(
+    app: tauri::AppHandle,
+    udid: String,
+    name: String,
+) -> Result<String, String> {
+    debug_println!("clone device: {} {}", udid, name);
+    Ok(command::clone_device(get_simctl_backend(&app).as_ref(), &udid, &name).await?)
+}
+
+#[tauri::command]
+pub async fn rename_device(app: tauri::AppHandle, udid: String, name: String) -> Result<(), String> {
+    debug_println!("rename device: {} {}", udid, name);
+    Ok(command::rename_device(get_simctl_backend(&app).as_ref(), &udid, &name).await?)
+}
+
+#[tauri::command]
+pub async fn delete_device(app: tauri::AppHandle, udid: String) -> Result<(), String> {
+    debug_println!("delete device: {}", udid);
+    Ok(command::delete_device(get_simctl_backend(&app).as_ref(), &udid).await?)
+}
+
+/**
+ * create the devices in the matrix, use the saved device matrix preference if specs is None
+ */
+#[tauri::command]
+pub async fn provision_device_matrix(
+    app: tauri::AppHandle,
+    specs: Option<Vec<DeviceSpec>>,
+) -> Result<Vec<ProvisionResult>, String> {
+    let specs = specs.unwrap_or_else(|| get_preferences(&app).device_matrix);
+    debug_println!("provision device matrix: {:?}", specs);
+    Ok(command::provision_devices(get_simctl_backend(&app).as_ref(), specs).await?)
+}
+
+#[tauri::command]
+pub async fn set_device_matrix(app: tauri::AppHandle, specs: Vec<DeviceSpec>) -> Result<(), String> {
+    let mut preferences = get_preferences(&app);
+    preferences.device_matrix = specs;
+    set_preferences(&app, preferences)
+}
+
+#[tauri::command]
+pub async fn get_default_device_name(app: tauri::AppHandle) -> Result<String, String> {
+    Ok(get_preferences(&app).default_device_name())
+}
+
+#[tauri::command]
+pub async fn set_default_device_name(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    let mut preferences = get_preferences(&app);
+    preferences.default_device_name = Some(name);
+    set_preferences(&app, preferences)
+}
+
+/**
+ * boot the default device and open the simulator app
+ */
+#[tauri::command]
+pub async fn boot_default_device(app: tauri::AppHandle) -> Result<String, String> {
+    let backend = get_simctl_backend(&app);
+    let name = get_preferences(&app).default_device_name();
+    let devices = command::get_all_devices(backend.as_ref()).await?;
+    let device = devices
+        .get_default_device(&name)
+        .ok_or_else(|| format!("default device not found: {}", name))?;
+    if device.state != "Booted" {
+        command::boot_device(backend.as_ref(), &device.udid).await?;
+        appearance::apply_saved_display_settings(&app, &device.udid).await;
+    }
+    command::open_simulator_app(backend.as_ref()).await?;
+    Ok(device.udid.clone())
+}
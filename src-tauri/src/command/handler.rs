@@ -1,4 +1,9 @@
-use super::webview_command;
+use super::{
+    app_command, appearance_command, capture_command, catalog_command, container_command,
+    device_command, host_command, library_command, location_command, log_command, media_command,
+    pasteboard_command, privacy_command, push_command, runtime_command, status_bar_command,
+    webview_command,
+};
 
 pub fn assamble_handler(builder: tauri::Builder<tauri::Wry>) -> tauri::Builder<tauri::Wry> {
     builder.invoke_handler(tauri::generate_handler![
@@ -18,5 +23,106 @@ pub fn assamble_handler(builder: tauri::Builder<tauri::Wry>) -> tauri::Builder<t
       webview_command::get_installed_simulator_runtime_list,
       webview_command::get_available_simulator_runtime_list,
       webview_command::delete_simulator_runtime,
+      webview_command::cancel_simulator_operation,
+      capture_command::take_screenshot,
+      capture_command::start_recording,
+      capture_command::stop_recording,
+      capture_command::get_capture_list,
+      capture_command::delete_capture,
+      device_command::get_device_type_list,
+      device_command::create_device,
+      device_command::clone_device,
+      device_command::rename_device,
+      device_command::delete_device,
+      device_command::provision_device_matrix,
+      device_command::set_device_matrix,
+      device_command::get_default_device_name,
+      device_command::set_default_device_name,
+      device_command::boot_default_device,
+      log_command::start_log_stream,
+      log_command::stop_log_stream,
+      log_command::get_log_file_path,
+      push_command::get_push_templates,
+      push_command::save_push_template,
+      push_command::delete_push_template,
+      push_command::send_push,
+      push_command::resend_last_push,
+      location_command::get_location_store,
+      location_command::save_location_place,
+      location_command::delete_location_place,
+      location_command::save_location_route,
+      location_command::delete_location_route,
+      location_command::import_gpx_route,
+      location_command::set_location,
+      location_command::set_location_by_place,
+      location_command::clear_location,
+      location_command::get_location_scenarios,
+      location_command::run_location_scenario,
+      location_command::start_location_route,
+      location_command::stop_location_route,
+      privacy_command::set_app_privacy,
+      privacy_command::reset_all_privacy,
+      privacy_command::get_permission_profiles,
+      privacy_command::save_permission_profile,
+      privacy_command::delete_permission_profile,
+      privacy_command::apply_permission_profile,
+      status_bar_command::get_status_bar_presets,
+      status_bar_command::save_status_bar_preset,
+      status_bar_command::delete_status_bar_preset,
+      status_bar_command::apply_status_bar_preset,
+      status_bar_command::get_applied_status_bar_preset,
+      status_bar_command::clear_status_bar,
+      status_bar_command::set_screenshot_status_bar_preset,
+      appearance_command::get_display_settings,
+      appearance_command::set_display_settings,
+      media_command::add_media,
+      media_command::get_fixture_sets,
+      media_command::import_fixtures,
+      media_command::delete_fixture_set,
+      media_command::seed_fixture_set,
+      pasteboard_command::get_device_pasteboard,
+      pasteboard_command::set_device_pasteboard,
+      pasteboard_command::get_pasteboard_sync,
+      pasteboard_command::start_pasteboard_sync,
+      pasteboard_command::stop_pasteboard_sync,
+      app_command::get_installed_apps,
+      app_command::launch_installed_app,
+      app_command::terminate_installed_app,
+      app_command::uninstall_installed_app,
+      container_command::get_app_container,
+      container_command::get_app_group_containers,
+      container_command::list_container_dir,
+      container_command::read_container_file,
+      container_command::copy_from_container,
+      container_command::copy_to_container,
+      library_command::get_package_library,
+      library_command::import_package,
+      library_command::remove_package,
+      library_command::clean_package_library,
+      library_command::get_library_settings,
+      library_command::set_library_settings,
+      library_command::start_download,
+      library_command::cancel_download,
+      library_command::get_downloads,
+      library_command::clear_finished_downloads,
+      catalog_command::get_app_catalog,
+      catalog_command::save_app_catalog,
+      catalog_command::reload_app_catalog,
+      runtime_command::get_runtime_summary,
+      runtime_command::get_runtime_prune_plan,
+      runtime_command::prune_simulator_runtimes,
+      runtime_command::get_runtime_matches,
+      runtime_command::update_runtime_dyld_cache,
+      host_command::route_vc_operation,
+      host_command::open_debug_menu,
+      host_command::get_current_route,
+      host_command::get_selected_host_info,
+      host_command::get_host_discovery_settings,
+      host_command::set_host_discovery_settings,
+      host_command::login_account,
+      host_command::logout_account,
+      host_command::get_accounts,
+      host_command::save_account,
+      host_command::remove_account,
     ])
 }
@@ -0,0 +1,122 @@
+use debug_print::debug_println;
+
+use crate::{
+    host::{
+        account::{self, Account, LoginParams},
+        client::{get_host_client, HostResponse, RouteOperation},
+        info::{HostCapability, HostInfo},
+        scanner::HostDiscoverySettings,
+    },
+    preference::{get_preferences, set_preferences},
+    sotre::get_tauri_store,
+};
+
+/**
+ * the app & device behind the selected debug host, None if no host is selected or it stopped answering
+ */
+#[tauri::command]
+pub async fn get_selected_host_info(app: tauri::AppHandle) -> Result<Option<HostInfo>, String> {
+    Ok(get_tauri_store(app)
+        .and_then(|store| store.debug_hosts)
+        .and_then(|host| host.get_selected_host_info().cloned()))
+}
+
+/**
+ * operate the current view controller of the app, e.g. "op_route_back"
+ */
+#[tauri::command]
+pub async fn route_vc_operation(
+    app: tauri::AppHandle,
+    operation: RouteOperation,
+) -> Result<HostResponse, String> {
+    debug_println!("route vc operation: {:?}", operation);
+    let capability = match operation {
+        RouteOperation::Back => HostCapability::RouteBack,
+        RouteOperation::Forward => HostCapability::RouteForward,
+        RouteOperation::Refresh => HostCapability::RouteRefresh,
+    };
+    Ok(get_host_client(&app, capability)?
+        .route_vc_operation(operation)
+        .await?)
+}
+
+#[tauri::command]
+pub async fn open_debug_menu(app: tauri::AppHandle) -> Result<HostResponse, String> {
+    Ok(get_host_client(&app, HostCapability::DebugMenu)?
+        .open_debug_menu()
+        .await?)
+}
+
+/**
+ * the route of the current page in the app
+ */
+#[tauri::command]
+pub async fn get_current_route(app: tauri::AppHandle) -> Result<String, String> {
+    Ok(get_host_client(&app, HostCapability::CurrentRoute)?
+        .current_route()
+        .await?)
+}
+
+/**
+ * login the app with the saved account by name
+ */
+#[tauri::command]
+pub async fn login_account(app: tauri::AppHandle, name: String) -> Result<HostResponse, String> {
+    debug_println!("login account: {}", name);
+    let account = account::get_account(&app, &name)?;
+    Ok(get_host_client(&app, HostCapability::Login)?
+        .login(&LoginParams::from(&account))
+        .await?)
+}
+
+#[tauri::command]
+pub async fn logout_account(app: tauri::AppHandle) -> Result<HostResponse, String> {
+    Ok(get_host_client(&app, HostCapability::Logout)?
+        .logout()
+        .await?)
+}
+
+/**
+ * the passwords are masked, `login_account` reads the saved one
+ */
+fn mask_accounts(accounts: Vec<Account>) -> Vec<Account> {
+    accounts.iter().map(Account::masked).collect()
+}
+
+#[tauri::command]
+pub async fn get_accounts(app: tauri::AppHandle) -> Result<Vec<Account>, String> {
+    Ok(mask_accounts(account::get_accounts(&app)))
+}
+
+/**
+ * add or replace the account by name, the login submenu of the tray lists it
+ * an empty password keeps the saved one
+ */
+#[tauri::command]
+pub async fn save_account(app: tauri::AppHandle, account: Account) -> Result<Vec<Account>, String> {
+    account::save_account(&app, account).map(mask_accounts)
+}
+
+#[tauri::command]
+pub async fn remove_account(app: tauri::AppHandle, name: String) -> Result<Vec<Account>, String> {
+    account::remove_account(&app, &name).map(mask_accounts)
+}
+
+#[tauri::command]
+pub fn get_host_discovery_settings(app: tauri::AppHandle) -> Result<HostDiscoverySettings, String> {
+    Ok(get_preferences(&app).host_discovery)
+}
+
+/**
+ * the hosts & port ranges scanned for debug hosts, applied from the next scan
+ */
+#[tauri::command]
+pub fn set_host_discovery_settings(
+    app: tauri::AppHandle,
+    settings: HostDiscoverySettings,
+) -> Result<(), String> {
+    settings.validate()?;
+    let mut preferences = get_preferences(&app);
+    preferences.host_discovery = settings;
+    set_preferences(&app, preferences)
+}
@@ -0,0 +1,101 @@
+use debug_print::debug_println;
+use tauri::Manager;
+
+use crate::{
+    library::{
+        download::{self, DownloadManager, DownloadProgress, DownloadRequest},
+        package::{self, LibrarySettings, PackageKind, PackageRecord},
+    },
+    preference::{get_preferences, set_preferences},
+};
+
+/**
+ * get the packages of the library, the watch folders are scanned first
+ */
+#[tauri::command]
+pub async fn get_package_library(
+    app: tauri::AppHandle,
+    kind: Option<PackageKind>,
+) -> Result<Vec<PackageRecord>, String> {
+    package::scan_watch_folders(&app).await?;
+    let mut packages = package::get_package_index(&app).packages;
+    packages.retain(|p| kind.map(|kind| p.kind == kind).unwrap_or(true));
+    packages.sort_by(|a, b| b.added_at.cmp(&a.added_at));
+    Ok(packages)
+}
+
+/**
+ * import a package from the host, a package with the same sha256 is returned as is
+ */
+#[tauri::command]
+pub async fn import_package(
+    app: tauri::AppHandle,
+    path: String,
+    source_url: Option<String>,
+) -> Result<PackageRecord, String> {
+    debug_println!("import package: {}", path);
+    package::import_package(&app, std::path::Path::new(&path), source_url).await
+}
+
+/**
+ * remove a package from the library, its source is not imported again by the watch folders
+ */
+#[tauri::command]
+pub async fn remove_package(
+    app: tauri::AppHandle,
+    sha256: String,
+) -> Result<PackageRecord, String> {
+    debug_println!("remove package: {}", sha256);
+    package::remove_package(&app, &sha256).await
+}
+
+/**
+ * remove the packages expired by the retention settings
+ */
+#[tauri::command]
+pub async fn clean_package_library(app: tauri::AppHandle) -> Result<Vec<PackageRecord>, String> {
+    package::clean_library(&app).await
+}
+
+#[tauri::command]
+pub fn get_library_settings(app: tauri::AppHandle) -> Result<LibrarySettings, String> {
+    Ok(get_preferences(&app).library)
+}
+
+#[tauri::command]
+pub fn set_library_settings(
+    app: tauri::AppHandle,
+    settings: LibrarySettings,
+) -> Result<(), String> {
+    let mut preferences = get_preferences(&app);
+    preferences.library = settings;
+    set_preferences(&app, preferences)
+}
+
+/**
+ * queue a download and return its id, the progress is emitted by the `download_progress` event
+ */
+#[tauri::command]
+pub fn start_download(app: tauri::AppHandle, request: DownloadRequest) -> Result<u64, String> {
+    debug_println!("start download: {}", request.url);
+    Ok(download::start_download(&app, request)?)
+}
+
+/**
+ * cancel the download, starting the same url again resumes it
+ */
+#[tauri::command]
+pub fn cancel_download(app: tauri::AppHandle, id: u64) -> Result<bool, String> {
+    Ok(app.state::<DownloadManager>().cancel(id))
+}
+
+#[tauri::command]
+pub fn get_downloads(app: tauri::AppHandle) -> Result<Vec<DownloadProgress>, String> {
+    Ok(app.state::<DownloadManager>().list())
+}
+
+#[tauri::command]
+pub fn clear_finished_downloads(app: tauri::AppHandle) -> Result<(), String> {
+    app.state::<DownloadManager>().clear_finished();
+    Ok(())
+}
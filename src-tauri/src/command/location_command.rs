@@ -0,0 +1,153 @@
+use debug_print::debug_println;
+
+use crate::simulator::{
+    backend::get_simctl_backend,
+    command,
+    location::{self, Coordinate, LocationStore, Place, Route},
+};
+
+use super::util::get_target_device;
+
+#[tauri::command]
+pub async fn get_location_store(app: tauri::AppHandle) -> Result<LocationStore, String> {
+    Ok(location::get_location_store(&app))
+}
+
+#[tauri::command]
+pub async fn save_location_place(app: tauri::AppHandle, place: Place) -> Result<(), String> {
+    location::save_place(&app, place)
+}
+
+#[tauri::command]
+pub async fn delete_location_place(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    let mut store = location::get_location_store(&app);
+    store.places.retain(|p| p.name != name);
+    location::set_location_store(&app, store)
+}
+
+#[tauri::command]
+pub async fn save_location_route(app: tauri::AppHandle, route: Route) -> Result<(), String> {
+    location::save_route(&app, route)
+}
+
+#[tauri::command]
+pub async fn delete_location_route(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    let mut store = location::get_location_store(&app);
+    store.routes.retain(|r| r.name != name);
+    location::set_location_store(&app, store)
+}
+
+/**
+ * import a GPX file as a route, the track name is used if name is None
+ */
+#[tauri::command]
+pub async fn import_gpx_route(
+    app: tauri::AppHandle,
+    path: String,
+    name: Option<String>,
+) -> Result<Route, String> {
+    debug_println!("import gpx: {}", path);
+    location::import_gpx_file(&app, &path, name)
+}
+
+/**
+ * set the location of the device, or the first booted device if udid is None
+ */
+#[tauri::command]
+pub async fn set_location(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    latitude: f64,
+    longitude: f64,
+) -> Result<(), String> {
+    let coordinate = Coordinate::new(latitude, longitude)?;
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    device.set_location(backend.as_ref(), &coordinate).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_location_by_place(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    name: String,
+) -> Result<(), String> {
+    let place = location::get_location_store(&app)
+        .places
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("place not found: {}", name))?;
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    device.set_location(backend.as_ref(), &place.coordinate).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn clear_location(app: tauri::AppHandle, udid: Option<String>) -> Result<(), String> {
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    device.clear_location(backend.as_ref()).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_location_scenarios(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+) -> Result<Vec<String>, String> {
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    Ok(command::list_location_scenarios(backend.as_ref(), &device.udid).await?)
+}
+
+#[tauri::command]
+pub async fn run_location_scenario(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    scenario: String,
+) -> Result<(), String> {
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    command::run_location_scenario(backend.as_ref(), &device.udid, &scenario).await?;
+    Ok(())
+}
+
+/**
+ * move the device along the saved route, speed is in meters per second
+ */
+#[tauri::command]
+pub async fn start_location_route(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    name: String,
+    speed: Option<f64>,
+) -> Result<(), String> {
+    if let Some(speed) = speed {
+        if speed.is_nan() || speed <= 0.0 {
+            return Err(format!("invalid speed: {}", speed));
+        }
+    }
+    let route = location::get_location_store(&app)
+        .routes
+        .into_iter()
+        .find(|r| r.name == name)
+        .ok_or_else(|| format!("route not found: {}", name))?;
+    let device = get_target_device(&app, udid)?;
+    debug_println!("start route {} on {} at {:?} m/s", name, device.name, speed);
+    let backend = get_simctl_backend(&app);
+    device.start_route(backend.as_ref(), &route.points, speed).await?;
+    Ok(())
+}
+
+/**
+ * stop the running route, the device keeps no simulated location afterwards
+ */
+#[tauri::command]
+pub async fn stop_location_route(app: tauri::AppHandle, udid: Option<String>) -> Result<(), String> {
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    device.clear_location(backend.as_ref()).await?;
+    Ok(())
+}
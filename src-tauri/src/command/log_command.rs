@@ -0,0 +1,53 @@
+use debug_print::debug_println;
+use tauri::{Manager, WindowEvent};
+
+use crate::simulator::log_stream::{self, LogFilter, LogStreams};
+
+use super::util::get_target_device;
+
+/**
+ * start streaming the device logs to the `simulator_log` event
+ * the stream is stopped when the window is closed
+ */
+#[tauri::command]
+pub async fn start_log_stream(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    udid: Option<String>,
+    filter: Option<LogFilter>,
+    write_to_file: Option<bool>,
+) -> Result<String, String> {
+    let device = get_target_device(&app, udid)?;
+    debug_println!("start log stream: {} {:?}", device.udid, filter);
+    let cancel = log_stream::start_log_stream(
+        &app,
+        &device.udid,
+        &filter.unwrap_or_default(),
+        write_to_file.unwrap_or(false),
+    )
+    .await?;
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            cancel.cancel();
+        }
+    });
+    Ok(device.udid)
+}
+
+#[tauri::command]
+pub async fn stop_log_stream(app: tauri::AppHandle, udid: Option<String>) -> Result<bool, String> {
+    let device = get_target_device(&app, udid)?;
+    debug_println!("stop log stream: {}", device.udid);
+    Ok(app.state::<LogStreams>().stop(&device.udid))
+}
+
+/**
+ * path of the log file written by the stream
+ */
+#[tauri::command]
+pub async fn get_log_file_path(app: tauri::AppHandle, udid: Option<String>) -> Result<String, String> {
+    let device = get_target_device(&app, udid)?;
+    Ok(log_stream::get_log_file_path(&device.udid)
+        .to_string_lossy()
+        .to_string())
+}
@@ -0,0 +1,54 @@
+use debug_print::debug_println;
+
+use crate::simulator::{
+    backend::get_simctl_backend,
+    media::{self, FixtureSet, MediaResult},
+};
+
+use super::util::get_target_device;
+
+/**
+ * add photos, videos or contacts to the device, or the first booted device if udid is None
+ */
+#[tauri::command]
+pub async fn add_media(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    paths: Vec<String>,
+) -> Result<Vec<MediaResult>, String> {
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    Ok(device.add_media(backend.as_ref(), &paths).await)
+}
+
+#[tauri::command]
+pub async fn get_fixture_sets() -> Result<Vec<FixtureSet>, String> {
+    Ok(media::get_fixture_sets())
+}
+
+/**
+ * copy the files into the fixture set, the set is created if it does not exist
+ */
+#[tauri::command]
+pub async fn import_fixtures(name: String, paths: Vec<String>) -> Result<Vec<MediaResult>, String> {
+    debug_println!("import fixtures into {}: {:?}", name, paths);
+    media::import_fixtures(&name, &paths)
+}
+
+#[tauri::command]
+pub async fn delete_fixture_set(name: String) -> Result<(), String> {
+    media::delete_fixture_set(&name)
+}
+
+/**
+ * add every file of the fixture set to the device
+ */
+#[tauri::command]
+pub async fn seed_fixture_set(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    name: String,
+) -> Result<Vec<MediaResult>, String> {
+    let device = get_target_device(&app, udid)?;
+    media::seed_device(&app, &device, &name).await
+}
@@ -1,3 +1,19 @@
 pub mod webview_command;
 pub mod handler;
-pub mod util;
\ No newline at end of file
+pub mod util;
+pub mod capture_command;
+pub mod device_command;
+pub mod log_command;
+pub mod push_command;
+pub mod location_command;
+pub mod privacy_command;
+pub mod status_bar_command;
+pub mod appearance_command;
+pub mod media_command;
+pub mod pasteboard_command;
+pub mod app_command;
+pub mod container_command;
+pub mod library_command;
+pub mod catalog_command;
+pub mod runtime_command;
+pub mod host_command;
\ No newline at end of file
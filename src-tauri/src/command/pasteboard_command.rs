@@ -0,0 +1,52 @@
+use tauri::Manager;
+
+use crate::simulator::{
+    backend::get_simctl_backend,
+    command,
+    pasteboard::{self, PasteboardSync, SyncDirection},
+};
+
+use super::util::get_target_device;
+
+/**
+ * read the pasteboard of the device, or the first booted device if udid is None
+ */
+#[tauri::command]
+pub async fn get_device_pasteboard(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+) -> Result<String, String> {
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    Ok(command::pbpaste(backend.as_ref(), &device.udid).await?)
+}
+
+/**
+ * write the text into the pasteboard of the device, e.g. a schema to paste in the app
+ */
+#[tauri::command]
+pub async fn set_device_pasteboard(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    content: String,
+) -> Result<(), String> {
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    command::pbcopy(backend.as_ref(), &device.udid, &content).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_pasteboard_sync(app: tauri::AppHandle) -> Option<SyncDirection> {
+    app.state::<PasteboardSync>().direction()
+}
+
+#[tauri::command]
+pub fn start_pasteboard_sync(app: tauri::AppHandle, direction: SyncDirection) {
+    pasteboard::start_pasteboard_sync(&app, direction);
+}
+
+#[tauri::command]
+pub fn stop_pasteboard_sync(app: tauri::AppHandle) -> bool {
+    app.state::<PasteboardSync>().stop()
+}
@@ -0,0 +1,104 @@
+use debug_print::debug_println;
+
+use crate::simulator::{
+    backend::get_simctl_backend,
+    command,
+    privacy::{self, PermissionProfile, PrivacyAction, PrivacyService},
+};
+
+use super::util::get_target_device;
+
+/**
+ * grant, revoke or reset the permission of the app on the device, or the first booted device if udid is None
+ */
+#[tauri::command]
+pub async fn set_app_privacy(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    bundle_id: String,
+    service: PrivacyService,
+    action: PrivacyAction,
+) -> Result<(), String> {
+    debug_println!("set privacy: {} {} {}", bundle_id, action, service);
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    device
+        .set_privacy(backend.as_ref(), &bundle_id, service, action)
+        .await?;
+    Ok(())
+}
+
+/**
+ * reset the permissions of all apps on the device
+ */
+#[tauri::command]
+pub async fn reset_all_privacy(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    service: PrivacyService,
+) -> Result<(), String> {
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    command::set_privacy(
+        backend.as_ref(),
+        &device.udid,
+        PrivacyAction::Reset,
+        service,
+        None,
+    )
+    .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_permission_profiles(
+    app: tauri::AppHandle,
+) -> Result<Vec<PermissionProfile>, String> {
+    Ok(privacy::get_permission_profiles(&app))
+}
+
+/**
+ * add or replace the profile of the bundle id
+ */
+#[tauri::command]
+pub async fn save_permission_profile(
+    app: tauri::AppHandle,
+    profile: PermissionProfile,
+) -> Result<(), String> {
+    if profile.rules.iter().any(|r| {
+        r.service == PrivacyService::All && r.action != PrivacyAction::Reset
+    }) {
+        return Err("`all` can only be reset".to_string());
+    }
+    let mut profiles = privacy::get_permission_profiles(&app);
+    profiles.retain(|p| p.bundle_id != profile.bundle_id);
+    profiles.push(profile);
+    privacy::set_permission_profiles(&app, profiles)
+}
+
+#[tauri::command]
+pub async fn delete_permission_profile(
+    app: tauri::AppHandle,
+    bundle_id: String,
+) -> Result<(), String> {
+    let mut profiles = privacy::get_permission_profiles(&app);
+    profiles.retain(|p| p.bundle_id != bundle_id);
+    privacy::set_permission_profiles(&app, profiles)
+}
+
+/**
+ * apply the saved profile now, e.g. after the permissions were changed in the Settings app
+ */
+#[tauri::command]
+pub async fn apply_permission_profile(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    bundle_id: String,
+) -> Result<(), String> {
+    let profile = privacy::get_permission_profile(&app, &bundle_id)
+        .ok_or_else(|| format!("no permission profile for {}", bundle_id))?;
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    profile.apply(backend.as_ref(), &device).await?;
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use debug_print::debug_println;
+
+use crate::simulator::push::{self, PushTemplate};
+
+use super::util::get_target_device;
+
+#[tauri::command]
+pub async fn get_push_templates(app: tauri::AppHandle) -> Result<Vec<PushTemplate>, String> {
+    Ok(push::get_push_store(&app).templates)
+}
+
+/**
+ * add or replace the template with the same name
+ */
+#[tauri::command]
+pub async fn save_push_template(app: tauri::AppHandle, template: PushTemplate) -> Result<(), String> {
+    // a template may contain placeholders, validate it with a sample schema
+    let sample = push::fill_template(&template.payload, Some("sslocal://costa"))?;
+    push::validate_payload(&sample)?;
+    let mut store = push::get_push_store(&app);
+    store.templates.retain(|t| t.name != template.name);
+    store.templates.push(template);
+    push::set_push_store(&app, store)
+}
+
+#[tauri::command]
+pub async fn delete_push_template(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    let mut store = push::get_push_store(&app);
+    store.templates.retain(|t| t.name != name);
+    push::set_push_store(&app, store)
+}
+
+/**
+ * send a push to the device, or the first booted device if udid is None
+ * the payload may contain `{{schema}}`, which is filled from the clipboard
+ */
+#[tauri::command]
+pub async fn send_push(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    bundle_id: String,
+    payload: String,
+) -> Result<(), String> {
+    debug_println!("send push: {} {}", bundle_id, payload);
+    let device = get_target_device(&app, udid)?;
+    push::send_push(&app, &device, &bundle_id, &payload).await
+}
+
+#[tauri::command]
+pub async fn resend_last_push(app: tauri::AppHandle) -> Result<(), String> {
+    push::resend_last_push(&app).await
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use debug_print::debug_println;
+use tauri::Manager;
+
+use crate::simulator::{
+    backend::get_simctl_backend,
+    command,
+    runner::CancelRegistry,
+    runtime::RuntimeMatch,
+    runtime_manager::{self, PrunePlan, PruneResult, RuntimeSummary, DEFAULT_RUNTIME_UNUSED_DAYS},
+};
+
+/**
+ * disk usage & status of the installed runtimes
+ * runtimes without devices not used for `unused_days` (default 90) are flagged as unused
+ */
+#[tauri::command]
+pub async fn get_runtime_summary(
+    app: tauri::AppHandle,
+    unused_days: Option<i64>,
+) -> Result<RuntimeSummary, String> {
+    let unused_days = unused_days.unwrap_or(DEFAULT_RUNTIME_UNUSED_DAYS);
+    Ok(
+        runtime_manager::get_runtime_summary(get_simctl_backend(&app).as_ref(), unused_days)
+            .await?,
+    )
+}
+
+/**
+ * the runtimes `prune_simulator_runtimes` would delete, nothing is deleted
+ */
+#[tauri::command]
+pub async fn get_runtime_prune_plan(
+    app: tauri::AppHandle,
+    unused_days: Option<i64>,
+) -> Result<PrunePlan, String> {
+    let unused_days = unused_days.unwrap_or(DEFAULT_RUNTIME_UNUSED_DAYS);
+    Ok(runtime_manager::get_prune_plan(get_simctl_backend(&app).as_ref(), unused_days).await?)
+}
+
+/**
+ * delete the runtimes confirmed from the prune plan
+ * the webview can cancel it by `cancel_simulator_operation` with the same id
+ */
+#[tauri::command]
+pub async fn prune_simulator_runtimes(
+    app: tauri::AppHandle,
+    identifiers: Vec<String>,
+    unused_days: Option<i64>,
+    operation_id: Option<String>,
+) -> Result<Vec<PruneResult>, String> {
+    debug_println!("prune simulator runtimes: {:?}", identifiers);
+    let unused_days = unused_days.unwrap_or(DEFAULT_RUNTIME_UNUSED_DAYS);
+    let cancel = operation_id
+        .as_ref()
+        .map(|id| app.state::<CancelRegistry>().register(id));
+    let res = runtime_manager::prune_runtimes(
+        get_simctl_backend(&app).as_ref(),
+        &identifiers,
+        unused_days,
+        cancel,
+    )
+    .await;
+    if let Some(id) = operation_id {
+        app.state::<CancelRegistry>().remove(&id);
+    }
+    Ok(res?)
+}
+
+/**
+ * the runtimes chosen for the SDKs of the selected Xcode, key: sdk name
+ */
+#[tauri::command]
+pub async fn get_runtime_matches(
+    app: tauri::AppHandle,
+) -> Result<HashMap<String, RuntimeMatch>, String> {
+    Ok(command::get_runtime_matches(get_simctl_backend(&app).as_ref()).await?)
+}
+
+/**
+ * build the dyld shared cache of the runtime, or of all runtimes if identifier is None
+ */
+#[tauri::command]
+pub async fn update_runtime_dyld_cache(
+    app: tauri::AppHandle,
+    identifier: Option<String>,
+    operation_id: Option<String>,
+) -> Result<String, String> {
+    debug_println!("update dyld shared cache: {:?}", identifier);
+    let cancel = operation_id
+        .as_ref()
+        .map(|id| app.state::<CancelRegistry>().register(id));
+    let res = command::update_dyld_shared_cache(
+        get_simctl_backend(&app).as_ref(),
+        identifier.as_deref(),
+        cancel,
+    )
+    .await;
+    if let Some(id) = operation_id {
+        app.state::<CancelRegistry>().remove(&id);
+    }
+    Ok(res?)
+}
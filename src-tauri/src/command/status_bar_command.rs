@@ -0,0 +1,99 @@
+use debug_print::debug_println;
+use tauri::Manager;
+
+use crate::{
+    preference::{get_preferences, set_preferences},
+    simulator::{
+        backend::get_simctl_backend,
+        status_bar::{self, StatusBarOverrides, StatusBarPreset},
+    },
+};
+
+use super::util::get_target_device;
+
+#[tauri::command]
+pub async fn get_status_bar_presets(app: tauri::AppHandle) -> Result<Vec<StatusBarPreset>, String> {
+    Ok(status_bar::get_status_bar_presets(&app))
+}
+
+/**
+ * add or replace the preset with the same name
+ */
+#[tauri::command]
+pub async fn save_status_bar_preset(
+    app: tauri::AppHandle,
+    preset: StatusBarPreset,
+) -> Result<(), String> {
+    preset.status_bar.validate()?;
+    let mut presets = status_bar::get_status_bar_presets(&app);
+    presets.retain(|p| p.name != preset.name);
+    presets.push(preset);
+    status_bar::set_status_bar_presets(&app, presets)
+}
+
+#[tauri::command]
+pub async fn delete_status_bar_preset(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    let mut presets = status_bar::get_status_bar_presets(&app);
+    presets.retain(|p| p.name != name);
+    status_bar::set_status_bar_presets(&app, presets)
+}
+
+/**
+ * apply the preset to the device, or the first booted device if udid is None
+ * screenshots keep this preset until it is cleared
+ */
+#[tauri::command]
+pub async fn apply_status_bar_preset(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+    name: String,
+) -> Result<(), String> {
+    let preset = status_bar::get_status_bar_preset(&app, &name)
+        .ok_or_else(|| format!("status bar preset not found: {}", name))?;
+    let device = get_target_device(&app, udid)?;
+    debug_println!("apply status bar preset {} to {}", name, device.name);
+    let backend = get_simctl_backend(&app);
+    device
+        .override_status_bar(backend.as_ref(), &preset.status_bar)
+        .await?;
+    app.state::<StatusBarOverrides>().insert(&device.udid, &name);
+    Ok(())
+}
+
+/**
+ * get the preset applied to the device from the webview
+ */
+#[tauri::command]
+pub async fn get_applied_status_bar_preset(
+    app: tauri::AppHandle,
+    udid: Option<String>,
+) -> Result<Option<String>, String> {
+    let device = get_target_device(&app, udid)?;
+    Ok(app.state::<StatusBarOverrides>().get(&device.udid))
+}
+
+#[tauri::command]
+pub async fn clear_status_bar(app: tauri::AppHandle, udid: Option<String>) -> Result<(), String> {
+    let device = get_target_device(&app, udid)?;
+    let backend = get_simctl_backend(&app);
+    device.clear_status_bar(backend.as_ref()).await?;
+    app.state::<StatusBarOverrides>().remove(&device.udid);
+    Ok(())
+}
+
+/**
+ * set the preset applied before screenshots, None to keep the real status bar
+ */
+#[tauri::command]
+pub async fn set_screenshot_status_bar_preset(
+    app: tauri::AppHandle,
+    name: Option<String>,
+) -> Result<(), String> {
+    if let Some(name) = name.as_ref() {
+        status_bar::get_status_bar_preset(&app, name)
+            .ok_or_else(|| format!("status bar preset not found: {}", name))?;
+    }
+    let mut preferences = get_preferences(&app);
+    preferences.screenshot_status_bar_preset = name;
+    set_preferences(&app, preferences)
+}
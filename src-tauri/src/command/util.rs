@@ -1,3 +1,8 @@
+use crate::{
+    simulator::device::Device,
+    sotre::{self, get_tauri_store},
+    tray::menu::TrayMenu,
+};
 
 pub async fn check_xcode_tools() -> Result<(), String> {
     Ok(())
@@ -6,3 +11,18 @@ pub async fn check_xcode_tools() -> Result<(), String> {
 pub async fn check_simulator_runtime() -> Result<(), String> {
     Ok(())
 }
+
+/**
+ * get the device by udid from the store, or the first booted device if udid is None
+ */
+pub fn get_target_device(app: &tauri::AppHandle, udid: Option<String>) -> Result<Device, String> {
+    let store = get_tauri_store(app.clone()).ok_or_else(|| "store not found".to_string())?;
+    let device_map = TrayMenu::from(store.get(sotre::StoreKey::Tray)).simulator;
+    let device = match udid {
+        Some(udid) => device_map.get_device_by_udid(&udid),
+        None => device_map.get_first_booted_device(),
+    };
+    device
+        .cloned()
+        .ok_or_else(|| "no booted device found".to_string())
+}
@@ -3,17 +3,31 @@ use std::collections::HashMap;
 use debug_print::debug_println;
 use log::info;
 use log4rs::config::runtime;
-use regex::Regex;
-use serde_json::json;
 use tauri::Manager;
 
 use crate::{
     clipboard::ClipboardContent,
-    constant_local::{DCD_DOWNLOAD_URL, MCT_DOWNLOAD_URL, SIMULATOR_DOWNLOAD_URL},
-    host::host::{Host, HostOperation, SetEnvParams},
-    simulator::{self, device::DeviceMap, runtime::Runtime},
+    catalog::{self, get_catalog_app},
+    host::{
+        client::{get_host_client, HostResponse},
+        host::SetEnvParams,
+        info::HostCapability,
+    },
+    library::{
+        download::{self, DownloadRequest},
+        package::{self, PackageKind, PackageRecord},
+    },
+    command::util::get_target_device,
+    simulator::{
+        self,
+        backend::get_simctl_backend,
+        device::DeviceMap,
+        install::{self, AppBundleInfo},
+        runner::CancelRegistry,
+        runtime::Runtime,
+    },
     sotre::{self, get_tauri_store, CostaStoreWrapper},
-    tray::{menu::TrayMenu, operation::OperationId},
+    tray::menu::TrayMenu,
     window::costa_window,
 };
 
@@ -54,24 +68,26 @@ pub async fn get_clipborad_value(app: tauri::AppHandle) -> Result<String, String
     }
 }
 
+/**
+ * open the schema in the app by the selected debug host
+ */
 #[tauri::command]
-pub async fn goto_schema(app: tauri::AppHandle, schema: String) -> Result<(), String> {
-    let store = get_tauri_store(app.clone());
-    if let Some(store) = store {
-        let host = store.get(sotre::StoreKey::DebugHosts);
-        if let Some(host) = host {
-            let host: Host = serde_json::from_value(host).map_err(|e| e.to_string())?;
-            host.exec_operation(OperationId::ClipboardSchema, json!({ "value": schema }));
-        }
-    }
-    Ok(())
+pub async fn goto_schema(app: tauri::AppHandle, schema: String) -> Result<HostResponse, String> {
+    Ok(get_host_client(&app, HostCapability::RouteSwitch)?
+        .route_switch(&schema)
+        .await?)
 }
 
 /**
- * 懂车帝暂时用 snssdk36 跳转
+ * open the url in the booted device, the scheme is rewritten by the catalog rules of the app
+ * e.g. 懂车帝暂时用 snssdk36 跳转
  */
 #[tauri::command]
-pub async fn goto_schema_by_sslocal(app: tauri::AppHandle, url: String) -> Result<(), String> {
+pub async fn goto_schema_by_sslocal(
+    app: tauri::AppHandle,
+    url: String,
+    app_id: Option<String>,
+) -> Result<(), String> {
     debug_println!("goto schema by sslocal: {}", url);
     let store = get_tauri_store(app.clone());
     if let Some(store) = store {
@@ -81,45 +97,61 @@ pub async fn goto_schema_by_sslocal(app: tauri::AppHandle, url: String) -> Resul
         let device = device_map.get_first_booted_device();
         debug_println!("booted device: {:?}", device);
         if let Some(device) = device {
-            device.open_url(&url.replace("sslocal", "snssdk36"));
+            let url = catalog::rewrite_app_url(&app, app_id.as_deref(), &url);
+            device
+                .open_url(get_simctl_backend(&app).as_ref(), &url)
+                .await?;
         }
     }
     Ok(())
 }
 
-#[tauri::command]
-pub async fn set_boe(app: tauri::AppHandle, params: SetEnvParams) -> Result<(), String> {
-    debug_println!("set boe: {:?}", params);
-    let store = get_tauri_store(app.clone());
-    if let Some(store) = store {
-        let host = store.get(sotre::StoreKey::DebugHosts);
-        if let Some(host) = host {
-            let host: Host = serde_json::from_value(host).map_err(|e| e.to_string())?;
-            if params.env_type.starts_with("boe") {
-                host.exec_operation(OperationId::SetEnv, json!({ "value": params }));
-            } else if params.env_type.starts_with("ppe") {
-                host.exec_operation(OperationId::SetEnv, json!({ "value": params }));
-            }
+/**
+ * check the app of the catalog supports the env, any env is allowed if app_id is None
+ */
+fn check_env_capability(
+    app: &tauri::AppHandle,
+    app_id: Option<&str>,
+    env_type: &str,
+) -> Result<(), String> {
+    match app_id {
+        Some(id) if !get_catalog_app(app, id)?.supports_env(env_type) => {
+            Err(format!("{} does not support the env {}", id, env_type))
         }
+        _ => Ok(()),
     }
-    Ok(())
 }
 
+/**
+ * switch the app to a boe env by the selected debug host
+ */
 #[tauri::command]
-pub async fn set_ppe(app: tauri::AppHandle, params: SetEnvParams) -> Result<(), String> {
-    let store = get_tauri_store(app.clone());
-    if let Some(store) = store {
-        let host = store.get(sotre::StoreKey::DebugHosts);
-        if let Some(host) = host {
-            let host: Host = serde_json::from_value(host).map_err(|e| e.to_string())?;
-            if params.env_type.starts_with("boe") {
-                host.exec_operation(OperationId::SetEnv, json!({ "value": params }));
-            } else if params.env_type.starts_with("ppe") {
-                host.exec_operation(OperationId::SetEnv, json!({ "value": params }));
-            }
-        }
-    }
-    Ok(())
+pub async fn set_boe(
+    app: tauri::AppHandle,
+    params: SetEnvParams,
+    app_id: Option<String>,
+) -> Result<HostResponse, String> {
+    debug_println!("set boe: {:?}", params);
+    check_env_capability(&app, app_id.as_deref(), &params.env_type)?;
+    Ok(get_host_client(&app, HostCapability::SetEnv)?
+        .set_env(&params)
+        .await?)
+}
+
+/**
+ * switch the app to a ppe env by the selected debug host
+ */
+#[tauri::command]
+pub async fn set_ppe(
+    app: tauri::AppHandle,
+    params: SetEnvParams,
+    app_id: Option<String>,
+) -> Result<HostResponse, String> {
+    debug_println!("set ppe: {:?}", params);
+    check_env_capability(&app, app_id.as_deref(), &params.env_type)?;
+    Ok(get_host_client(&app, HostCapability::SetEnv)?
+        .set_env(&params)
+        .await?)
 }
 
 #[tauri::command]
@@ -141,166 +173,164 @@ pub async fn xcode_install(app: tauri::AppHandle) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub async fn install_simulator(app: tauri::AppHandle, path: String) -> Result<(), String> {
+pub async fn install_simulator(
+    app: tauri::AppHandle,
+    path: String,
+    operation_id: Option<String>,
+) -> Result<(), String> {
     debug_println!("install simulator: {}", path);
+    let backend = get_simctl_backend(&app);
+    // the webview can cancel the install by `cancel_simulator_operation` with the same id
+    let cancel = operation_id
+        .as_ref()
+        .map(|id| app.state::<CancelRegistry>().register(id));
+    // find the runtime package in the library by its path or name
+    let runtime = package::get_packages(&app, PackageKind::Runtime)
+        .into_iter()
+        .find(|p| p.path == path || p.name.contains(&path));
     let res = tauri::async_runtime::spawn(async move {
-        // find file with name include "Simulator_Runtime" in ~/Downloads
-        let file_path = std::path::Path::new(&std::env::var("HOME").unwrap()).join("Downloads");
-        let mut files = std::fs::read_dir(file_path).unwrap();
-        let file = files.find(|f| {
-            f.as_ref()
-                .unwrap()
-                .path()
-                .to_str()
-                .unwrap()
-                .contains(&path)
-        });
-
-        if let Some(file) = file {
-            info!(
-                "simulator pkg found: {}",
-                file.as_ref().unwrap().path().to_str().unwrap()
-            );
-            let output = std::process::Command::new("xcrun")
-                .arg("simctl")
-                .arg("runtime")
-                .arg("add")
-                .arg(file.unwrap().path().to_str().unwrap())
-                .output()
-                .expect("failed to exec install simulator");
-            let output = String::from_utf8(output.stdout).unwrap();
-            println!("xcode install output: {}", output);
+        if let Some(runtime) = runtime {
+            info!("simulator pkg found: {}", runtime.path);
+            let output =
+                simulator::command::add_runtime(backend.as_ref(), &runtime.path, cancel).await?;
             info!("simulator installed: {}", output);
             Ok(())
         } else {
-            Err("simulator package not found in ~/Downloads, plz check")
+            Err("simulator package not found in the package library, plz check".to_string())
         }
     })
     .await
     .map_err(|e| e.to_string());
-    res.unwrap().map_err(|e| e.to_string())
+    if let Some(id) = operation_id {
+        app.state::<CancelRegistry>().remove(&id);
+    }
+    res?
 }
 
+/**
+ * download the simulator runtime of the catalog in-app and add it once finished, return the download id
+ */
 #[tauri::command]
-pub async fn download_simulator(app: tauri::AppHandle) -> Result<(), String> {
-    debug_println!("open simulator download url");
-    let output = std::process::Command::new("open")
-        .arg(SIMULATOR_DOWNLOAD_URL)
-        .output()
-        .expect("failed to exec download simulator");
-    let output = String::from_utf8(output.stdout).unwrap();
-    info!("download simulator output: {}", output);
-    Ok(())
+pub async fn download_simulator(app: tauri::AppHandle) -> Result<u64, String> {
+    let url = catalog::get_app_catalog(&app)
+        .simulator_runtime_url
+        .ok_or_else(|| "simulator runtime url not found in the catalog".to_string())?;
+    debug_println!("download simulator: {}", url);
+    Ok(download::start_download(
+        &app,
+        DownloadRequest {
+            url,
+            install: true,
+            ..Default::default()
+        },
+    )?)
 }
 
+/**
+ * download the catalog app in-app and install it into the booted device once finished
+ * `params` is the app id, `channel` defaults to the first channel, return the download id
+ */
 #[tauri::command]
-pub async fn download_app(app: tauri::AppHandle, params: String) -> Result<(), String> {
-    debug_println!("download app: {}", params);
-    let url = if params == "mct" {
-        MCT_DOWNLOAD_URL
-    } else {
-        DCD_DOWNLOAD_URL
-    };
-    let output = std::process::Command::new("open")
-        .arg(url)
-        .output()
-        .expect("failed to exec download app");
-    let output = String::from_utf8(output.stdout).unwrap();
-    info!("download app output: {}", output);
-    Ok(())
+pub async fn download_app(
+    app: tauri::AppHandle,
+    params: String,
+    channel: Option<String>,
+) -> Result<u64, String> {
+    debug_println!("download app: {} {:?}", params, channel);
+    let catalog_app = get_catalog_app(&app, &params)?;
+    let channel = catalog_app
+        .get_channel(channel.as_deref())
+        .ok_or_else(|| format!("channel not found for {}", params))?;
+    Ok(download::start_download(
+        &app,
+        DownloadRequest {
+            url: channel.url.clone(),
+            sha256: channel.sha256.clone(),
+            install: true,
+            ..Default::default()
+        },
+    )?)
 }
 
+/**
+ * get the app packages of the library, the watch folders are scanned first
+ */
 #[tauri::command]
-pub async fn get_app_pkg_list(app: tauri::AppHandle) -> Result<Vec<String>, String> {
-    // debug_println!("get app pkg list");
-    // find file with name include "Simulator_Runtime" in ~/Downloads
-    let file_path = std::path::Path::new(&std::env::var("HOME").unwrap()).join("Downloads");
-    let files = std::fs::read_dir(file_path).unwrap();
-    // find all files with name include ".app"
-    let files = files
-        .filter(|f| {
-            let re = Regex::new(r"\.(app|app\.zip)$").unwrap();
-            re.is_match(f.as_ref().unwrap().path().to_str().unwrap())
-        })
-        .map(|f| f.unwrap().path().to_str().unwrap().to_string())
-        .collect::<Vec<_>>();
-    // info!("app pkg list: {:?}", files);
-    Ok(files)
+pub async fn get_app_pkg_list(app: tauri::AppHandle) -> Result<Vec<PackageRecord>, String> {
+    package::scan_watch_folders(&app).await?;
+    Ok(package::get_packages(&app, PackageKind::App))
 }
 
+/**
+ * install a .app, .app.zip or .ipa into the device, the first booted device if udid is None
+ * the stages are emitted by the `install_progress` event, `id` defaults to the package path
+ */
 #[tauri::command]
-pub async fn install_app(app: tauri::AppHandle, params: String) -> Result<(), String> {
+pub async fn install_app(
+    app: tauri::AppHandle,
+    params: String,
+    udid: Option<String>,
+    launch: Option<bool>,
+    id: Option<String>,
+) -> Result<AppBundleInfo, String> {
     debug_println!("install app: {}", params);
-    let is_zip = params.ends_with(".zip");
-    let app_path = if is_zip {
-        // remove the zip extension
-        params.clone().replace(".zip", "")
-    } else {
-        params.clone()
-    };
-
-    let store = get_tauri_store(app.clone());
-    if let Some(store) = store {
-        let tray_menu = TrayMenu::from(store.get(sotre::StoreKey::Tray));
-        let device_map = tray_menu.simulator;
-        // get selected device
-        let device = device_map.get_first_booted_device();
-        debug_println!("booted device: {:?}", device);
-        if let Some(device) = device {
-            if is_zip {
-                // unzip the file
-                let output = std::process::Command::new("unzip")
-                    .arg(&params)
-                    .arg("-d")
-                    // assign to Downloads dir
-                    .arg(
-                        std::path::Path::new(&std::env::var("HOME").unwrap())
-                            .join("Downloads")
-                            .to_str()
-                            .unwrap(),
-                    )
-                    .output()
-                    .expect("failed to unzip app");
-                let output = String::from_utf8(output.stdout).unwrap();
-                info!("unzip app output: {}", output);
-            }
-
-            debug_println!("unzip done, installing app path: {}", app_path);
-            device.install_app(&app_path);
-        } else {
-            return Err("no booted device found".to_string());
-        }
-    }
-
-    Ok(())
+    let device = get_target_device(&app, udid)?;
+    debug_println!("target device: {:?}", device);
+    let id = id.unwrap_or_else(|| params.clone());
+    Ok(install::install_package(
+        &app,
+        &device,
+        std::path::Path::new(&params),
+        launch.unwrap_or(false),
+        &id,
+    )
+    .await?)
 }
 
 #[tauri::command]
-pub fn get_installed_simulator_runtime_list() -> Result<HashMap<String, Runtime>, String> {
-    Ok(simulator::command::get_all_runtimes())
+pub async fn get_installed_simulator_runtime_list(
+    app: tauri::AppHandle,
+) -> Result<HashMap<String, Runtime>, String> {
+    Ok(simulator::command::get_all_runtimes(get_simctl_backend(&app).as_ref()).await?)
 }
 
+/**
+ * get the simulator runtime packages of the library, the watch folders are scanned first
+ */
 #[tauri::command]
-pub fn get_available_simulator_runtime_list() -> Result<Vec<String>, String> {
-    // debug_println!("get app pkg list");
-    // find file with name include "Simulator_Runtime" in ~/Downloads
-    let file_path = std::path::Path::new(&std::env::var("HOME").unwrap()).join("Downloads");
-    let files = std::fs::read_dir(file_path).unwrap();
-    // find all files with name include ".app"
-    let files = files
-        .filter(|f| {
-            let re = Regex::new(r"Simulator_Runtime.dmg").unwrap();
-            re.is_match(f.as_ref().unwrap().path().to_str().unwrap())
-        })
-        .map(|f| f.unwrap().path().to_str().unwrap().to_string())
-        .collect::<Vec<_>>();
-    // info!("app pkg list: {:?}", files);
-    Ok(files)
+pub async fn get_available_simulator_runtime_list(
+    app: tauri::AppHandle,
+) -> Result<Vec<PackageRecord>, String> {
+    package::scan_watch_folders(&app).await?;
+    Ok(package::get_packages(&app, PackageKind::Runtime))
 }
 
 #[tauri::command]
-pub async fn delete_simulator_runtime(app: tauri::AppHandle, id: String) -> Result<(), String> {
+pub async fn delete_simulator_runtime(
+    app: tauri::AppHandle,
+    id: String,
+    operation_id: Option<String>,
+) -> Result<(), String> {
     debug_println!("delete simulator runtime: {}", id);
-    simulator::command::delete_runtime(app, id);
-    Ok(())
+    let cancel = operation_id
+        .as_ref()
+        .map(|op| app.state::<CancelRegistry>().register(op));
+    let res =
+        simulator::command::delete_runtime(get_simctl_backend(&app).as_ref(), id, cancel).await;
+    if let Some(op) = operation_id {
+        app.state::<CancelRegistry>().remove(&op);
+    }
+    Ok(res?)
+}
+
+/**
+ * cancel a running runtime add/delete, the simctl process will be killed
+ */
+#[tauri::command]
+pub async fn cancel_simulator_operation(
+    app: tauri::AppHandle,
+    operation_id: String,
+) -> Result<bool, String> {
+    debug_println!("cancel simulator operation: {}", operation_id);
+    Ok(app.state::<CancelRegistry>().cancel(&operation_id))
 }
@@ -1,9 +1,17 @@
 pub const APP_NAME: &str = "costa";
 pub const STORE_PATH: &str = "store";
+pub const CAPTURES_PATH: &str = "captures";
+pub const LOGS_PATH: &str = "logs";
+pub const FIXTURES_PATH: &str = "fixtures";
+pub const INSTALL_CACHE_PATH: &str = "install_cache";
+pub const LIBRARY_PATH: &str = "library";
+pub const DOWNLOADS_PATH: &str = "downloads";
+pub const CATALOG_FILE: &str = "catalog.json";
 pub const DEFAULT_HOST: &str = "http://127.0.0.1:9081";
 pub const DEFAULT_HOSTNAME: &str = "127.0.0.1";
 pub const DEFAULT_PORT: &str = "9081";
 pub const DEFAULT_PATH: &str = "/costa";
+pub const DEFAULT_DEVICE_NAME: &str = "iPhone 15 Pro";
 #[cfg(debug_assertions)]
 pub const IS_DEBUG: bool = true;
 #[cfg(not(debug_assertions))]
@@ -0,0 +1,98 @@
+/**
+ * This module keeps the test accounts the app is logged in with by the debug host.
+ * The accounts are saved in the store and listed in the login submenu of the tray.
+ */
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::AppHandle;
+
+use crate::sotre::{get_tauri_store, update_tauri_store, StoreKey};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Account {
+    /** unique name shown in the tray, e.g. "boe test user" */
+    pub name: String,
+    pub username: String,
+    pub password: String,
+    /** how the app logs in, e.g. "password", "sms", the app decides if None */
+    #[serde(default)]
+    pub login_type: Option<String>,
+}
+
+/**
+ * The body of `POST /costa/login`
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LoginParams {
+    pub username: String,
+    pub password: String,
+    #[serde(rename = "loginType", skip_serializing_if = "Option::is_none")]
+    pub login_type: Option<String>,
+}
+
+impl Account {
+    /** the account without its password, the password is only read in the backend */
+    pub fn masked(&self) -> Self {
+        Self {
+            password: String::new(),
+            ..self.clone()
+        }
+    }
+}
+
+impl From<&Account> for LoginParams {
+    fn from(account: &Account) -> Self {
+        Self {
+            username: account.username.clone(),
+            password: account.password.clone(),
+            login_type: account.login_type.clone(),
+        }
+    }
+}
+
+pub fn get_accounts(handle: &AppHandle) -> Vec<Account> {
+    get_tauri_store(handle.clone())
+        .and_then(|store| store.accounts)
+        .unwrap_or_default()
+}
+
+pub fn get_account(handle: &AppHandle, name: &str) -> Result<Account, String> {
+    get_accounts(handle)
+        .into_iter()
+        .find(|account| account.name == name)
+        .ok_or_else(|| format!("account not found: {}", name))
+}
+
+fn set_accounts(handle: &AppHandle, accounts: Vec<Account>) -> Result<(), String> {
+    update_tauri_store(handle.clone(), StoreKey::Accounts, json!(accounts))
+        .map_err(|e| e.to_string())
+}
+
+/**
+ * add the account, or replace the account with the same name
+ * an empty password keeps the saved one, the webview only gets the masked accounts
+ */
+pub fn save_account(handle: &AppHandle, mut account: Account) -> Result<Vec<Account>, String> {
+    if account.name.trim().is_empty() || account.username.trim().is_empty() {
+        return Err("account name & username are required".to_string());
+    }
+    let mut accounts = get_accounts(handle);
+    match accounts.iter_mut().find(|a| a.name == account.name) {
+        Some(saved) => {
+            if account.password.is_empty() {
+                account.password = std::mem::take(&mut saved.password);
+            }
+            *saved = account;
+        }
+        None => accounts.push(account),
+    }
+    set_accounts(handle, accounts.clone())?;
+    Ok(accounts)
+}
+
+pub fn remove_account(handle: &AppHandle, name: &str) -> Result<Vec<Account>, String> {
+    let mut accounts = get_accounts(handle);
+    accounts.retain(|account| account.name != name);
+    set_accounts(handle, accounts.clone())?;
+    Ok(accounts)
+}
@@ -0,0 +1,362 @@
+/**
+ * This module talks to the debug host running in the app, `<host>/costa/<endpoint>`.
+ * Every endpoint has an async method returning the response or a `HostError`,
+ * so the webview & the tray can tell the user whether the operation worked.
+ */
+use std::{fmt, time::Duration};
+
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{constant::DEFAULT_PATH, sotre::get_tauri_store};
+
+use super::{
+    account::LoginParams,
+    host::{Host, SetEnvParams},
+    info::HostCapability,
+};
+
+/** the debug host answers locally, a slow request means the app is paused or gone */
+pub const HOST_REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HostError {
+    /** no debug host is selected in the tray */
+    NoSelectedHost,
+    /** the host is not listening, e.g. the app was killed */
+    ConnectionRefused(String),
+    Timeout(String),
+    /** the host answered with a non-2xx status */
+    Status {
+        status: u16,
+        body: String,
+    },
+    /** the body cannot be read or is not what the endpoint returns */
+    BadBody(String),
+    Request(String),
+    /** the params of the operation cannot be parsed */
+    InvalidParams(String),
+    /** the operation is handled by costa itself, not by the debug host */
+    UnsupportedOperation(String),
+    /** the selected host does not declare the operation in its heartbeat */
+    NotSupportedByHost(String),
+}
+
+impl fmt::Display for HostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostError::NoSelectedHost => write!(f, "no debug host selected"),
+            HostError::ConnectionRefused(url) => {
+                write!(f, "debug host refused the connection: {}", url)
+            }
+            HostError::Timeout(url) => write!(f, "debug host timed out: {}", url),
+            HostError::Status { status, body } if body.is_empty() => {
+                write!(f, "debug host responded with status {}", status)
+            }
+            HostError::Status { status, body } => {
+                write!(f, "debug host responded with status {}: {}", status, body)
+            }
+            HostError::BadBody(e) => write!(f, "bad response of the debug host: {}", e),
+            HostError::Request(e) => write!(f, "debug host request failed: {}", e),
+            HostError::InvalidParams(e) => write!(f, "invalid operation params: {}", e),
+            HostError::UnsupportedOperation(operation) => {
+                write!(f, "{} is not a debug host operation", operation)
+            }
+            HostError::NotSupportedByHost(operation) => {
+                write!(f, "the selected debug host does not support {}", operation)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HostError {}
+
+impl From<reqwest::Error> for HostError {
+    fn from(e: reqwest::Error) -> Self {
+        let url = e.url().map(|url| url.to_string()).unwrap_or_default();
+        if e.is_timeout() {
+            HostError::Timeout(url)
+        } else if e.is_connect() {
+            HostError::ConnectionRefused(url)
+        } else if e.is_body() || e.is_decode() {
+            HostError::BadBody(e.to_string())
+        } else {
+            HostError::Request(e.to_string())
+        }
+    }
+}
+
+impl From<HostError> for String {
+    fn from(e: HostError) -> Self {
+        e.to_string()
+    }
+}
+
+/**
+ * A successful (2xx) response of the debug host
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HostResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+/**
+ * The body of `POST /costa/routeVCOperation`
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum RouteOperation {
+    #[serde(rename = "op_route_back")]
+    Back,
+    #[serde(rename = "op_route_forward")]
+    Forward,
+    /** reload the current page */
+    #[serde(rename = "op_route_refresh")]
+    Refresh,
+}
+
+impl RouteOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RouteOperation::Back => "op_route_back",
+            RouteOperation::Forward => "op_route_forward",
+            RouteOperation::Refresh => "op_route_refresh",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DebugHostClient {
+    base_url: String,
+    client: Client,
+}
+
+impl DebugHostClient {
+    /** base_url of the host, e.g. "http://127.0.0.1:9081" */
+    pub fn new(base_url: &str) -> Self {
+        let client = Client::builder()
+            .timeout(HOST_REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client,
+        }
+    }
+    /** the client of the selected host */
+    pub fn from_host(host: &Host) -> Result<Self, HostError> {
+        host.selected_host
+            .as_deref()
+            .map(Self::new)
+            .ok_or(HostError::NoSelectedHost)
+    }
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+    fn endpoint(&self, name: &str) -> String {
+        format!("{}{}/{}", self.base_url, DEFAULT_PATH, name)
+    }
+    async fn send(&self, request: RequestBuilder) -> Result<HostResponse, HostError> {
+        let resp = request.send().await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(HostError::Status {
+                status: status.as_u16(),
+                body,
+            });
+        }
+        Ok(HostResponse {
+            status: status.as_u16(),
+            body,
+        })
+    }
+    /**
+     * open the schema in the app by `POST /costa/routeSwitch`
+     */
+    pub async fn route_switch(&self, schema: &str) -> Result<HostResponse, HostError> {
+        let request = self
+            .client
+            .post(self.endpoint("routeSwitch"))
+            .body(schema.to_string());
+        self.send(request).await
+    }
+    /**
+     * operate the current view controller by `POST /costa/routeVCOperation`
+     */
+    pub async fn route_vc_operation(
+        &self,
+        operation: RouteOperation,
+    ) -> Result<HostResponse, HostError> {
+        let request = self
+            .client
+            .post(self.endpoint("routeVCOperation"))
+            .body(operation.as_str());
+        self.send(request).await
+    }
+    /**
+     * switch the env of the app by `POST /costa/setEnv`
+     */
+    pub async fn set_env(&self, params: &SetEnvParams) -> Result<HostResponse, HostError> {
+        let request = self.client.post(self.endpoint("setEnv")).json(params);
+        self.send(request).await
+    }
+    /**
+     * get the route of the current page by `GET /costa/currentRoute`
+     */
+    pub async fn current_route(&self) -> Result<String, HostError> {
+        let resp = self
+            .send(self.client.get(self.endpoint("currentRoute")))
+            .await?;
+        if resp.body.is_empty() {
+            return Err(HostError::BadBody("empty route".to_string()));
+        }
+        Ok(resp.body)
+    }
+    /**
+     * login the app with the account by `POST /costa/login`
+     */
+    pub async fn login(&self, params: &LoginParams) -> Result<HostResponse, HostError> {
+        let request = self.client.post(self.endpoint("login")).json(params);
+        self.send(request).await
+    }
+    /**
+     * logout the current account by `POST /costa/logout`
+     */
+    pub async fn logout(&self) -> Result<HostResponse, HostError> {
+        self.send(self.client.post(self.endpoint("logout"))).await
+    }
+    /** the debug menu is a route of the app */
+    pub async fn open_debug_menu(&self) -> Result<HostResponse, HostError> {
+        self.route_switch("sslocal://debug").await
+    }
+}
+
+/**
+ * the client of the host selected in the tray, the host must declare the capability in its heartbeat
+ */
+pub fn get_host_client(
+    handle: &AppHandle,
+    capability: HostCapability,
+) -> Result<DebugHostClient, HostError> {
+    let host = get_tauri_store(handle.clone())
+        .and_then(|store| store.debug_hosts)
+        .ok_or(HostError::NoSelectedHost)?;
+    if let Some(info) = host.get_selected_host_info() {
+        if !info.supports(capability) {
+            return Err(HostError::NotSupportedByHost(format!("{:?}", capability)));
+        }
+    }
+    DebugHostClient::from_host(&host)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::test_util::{TestRequest, TestResponse, TestServer};
+
+    /** a debug host answering every request with the status & the request body */
+    async fn echo_host(status: u16) -> TestServer {
+        TestServer::start(move |request: &TestRequest| {
+            Some(TestResponse::new(status, request.body.as_bytes()))
+        })
+        .await
+    }
+
+    fn client(host: &TestServer) -> DebugHostClient {
+        DebugHostClient::new(&format!("{}/", host.url()))
+    }
+
+    /** the method, path & body of every request */
+    fn requests(host: &TestServer) -> Vec<(String, String, String)> {
+        host.requests()
+            .into_iter()
+            .map(|request| (request.method, request.path, request.body))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn refused_when_nothing_listens() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let res = DebugHostClient::new(&url).logout().await;
+        assert!(
+            matches!(res, Err(HostError::ConnectionRefused(ref refused)) if refused.starts_with(&url)),
+            "{:?}",
+            res
+        );
+    }
+
+    #[tokio::test]
+    async fn times_out_when_the_host_does_not_answer() {
+        let host = TestServer::start(|_: &TestRequest| None).await;
+        let res = client(&host).current_route().await;
+        assert!(matches!(res, Err(HostError::Timeout(_))), "{:?}", res);
+    }
+
+    #[tokio::test]
+    async fn non_2xx_status_keeps_the_body() {
+        let host = echo_host(500).await;
+        let res = client(&host).route_switch("sslocal://home").await;
+        assert_eq!(
+            res,
+            Err(HostError::Status {
+                status: 500,
+                body: "sslocal://home".to_string(),
+            })
+        );
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "debug host responded with status 500: sslocal://home"
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_current_route_is_a_bad_body() {
+        let host = echo_host(200).await;
+        let res = client(&host).current_route().await;
+        assert_eq!(res, Err(HostError::BadBody("empty route".to_string())));
+        assert_eq!(
+            requests(&host),
+            vec![(
+                "GET".to_string(),
+                format!("{}/currentRoute", DEFAULT_PATH),
+                String::new()
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn operation_returns_the_host_response() {
+        let host = echo_host(202).await;
+        let res = client(&host).route_vc_operation(RouteOperation::Back).await;
+        assert_eq!(
+            res,
+            Ok(HostResponse {
+                status: 202,
+                body: "op_route_back".to_string(),
+            })
+        );
+        let res = client(&host).open_debug_menu().await;
+        assert_eq!(res.unwrap().body, "sslocal://debug");
+        assert_eq!(
+            requests(&host),
+            vec![
+                (
+                    "POST".to_string(),
+                    format!("{}/routeVCOperation", DEFAULT_PATH),
+                    "op_route_back".to_string()
+                ),
+                (
+                    "POST".to_string(),
+                    format!("{}/routeSwitch", DEFAULT_PATH),
+                    "sslocal://debug".to_string()
+                ),
+            ]
+        );
+    }
+}
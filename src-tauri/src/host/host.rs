@@ -1,25 +1,29 @@
+use async_trait::async_trait;
 use debug_print::debug_println;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
 
-use crate::{
-    constant::{DEFAULT_HOST, DEFAULT_PATH},
-    tray::operation::OperationId,
+use crate::tray::operation::OperationId;
+use std::collections::HashMap;
+
+use super::{
+    account::{Account, LoginParams},
+    client::{DebugHostClient, HostError, HostResponse, RouteOperation},
+    info::{HostCapability, HostInfo},
 };
-use reqwest::Client;
-use std::{collections::HashMap, time::Duration};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Host {
     pub selected_host: Option<String>,
-    pub host_map: HashMap<String, String>,
+    /** hosts answering the heartbeat, key: url of the host */
+    #[serde(default)]
+    pub hosts: HashMap<String, HostInfo>,
 }
 
 impl Host {
     pub fn default() -> Self {
         Self {
             selected_host: None,
-            host_map: HashMap::new(),
+            hosts: HashMap::new(),
         }
     }
     pub fn set_selected_host(&mut self, host: String) {
@@ -28,14 +32,38 @@ impl Host {
     pub fn get_selected_host(&self) -> Option<String> {
         self.selected_host.clone()
     }
-    pub fn get_host_by_name(&self, name: &str) -> Option<&String> {
-        self.host_map.get(name)
+    pub fn get_host_info(&self, url: &str) -> Option<&HostInfo> {
+        self.hosts.get(url)
+    }
+    /** info of the selected host, None if it is not selected or no longer answers */
+    pub fn get_selected_host_info(&self) -> Option<&HostInfo> {
+        self.get_host_info(self.selected_host.as_ref()?)
+    }
+    pub fn get_hosts(&self) -> HashMap<String, HostInfo> {
+        self.hosts.clone()
+    }
+    pub fn set_hosts(&mut self, hosts: HashMap<String, HostInfo>) {
+        self.hosts = hosts;
     }
-    pub fn get_host_map(&self) -> HashMap<String, String> {
-        self.host_map.clone()
+    /**
+     * true if the selected host declares the operation, operations of costa itself are always supported
+     */
+    pub fn supports_operation(&self, operation: &OperationId) -> bool {
+        match self.get_selected_host_info() {
+            Some(info) => info.supports_operation(operation),
+            None => HostCapability::for_operation(operation).is_none(),
+        }
     }
-    pub fn set_host_map(&mut self, map: HashMap<String, String>) {
-        self.host_map = map;
+    /**
+     * get the route of the current page from the selected host by `GET /costa/currentRoute`
+     * return None if no host is selected or the host does not respond
+     */
+    pub async fn get_current_route(&self) -> Option<String> {
+        DebugHostClient::from_host(self)
+            .ok()?
+            .current_route()
+            .await
+            .ok()
     }
 }
 
@@ -45,84 +73,60 @@ impl From<Host> for serde_json::Value {
     }
 }
 
+#[async_trait]
 pub trait HostOperation {
-    fn exec_operation(&self, operation: OperationId, params: serde_json::Value);
+    async fn exec_operation(
+        &self,
+        operation: OperationId,
+        params: serde_json::Value,
+    ) -> Result<HostResponse, HostError>;
 }
 
+#[async_trait]
 impl HostOperation for Host {
-    fn exec_operation(&self, operation: OperationId, params: serde_json::Value) {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(1))
-            .build()
-            .unwrap();
+    /**
+     * send the operation to the selected host, `params["value"]` is the payload of the operation
+     * operations handled by costa itself are rejected with `UnsupportedOperation`
+     */
+    async fn exec_operation(
+        &self,
+        operation: OperationId,
+        params: serde_json::Value,
+    ) -> Result<HostResponse, HostError> {
         debug_println!("exec operation: {:?}", operation);
+        let client = DebugHostClient::from_host(self)?;
+        if let Some(info) = self.get_selected_host_info() {
+            if !info.supports_operation(&operation) {
+                return Err(HostError::NotSupportedByHost(operation.to_string()));
+            }
+        }
+        let value = params["value"].clone();
         match operation {
-            OperationId::None => todo!(),
             OperationId::ClipboardSchema => {
-                debug_println!("selected host: {:?}", self.selected_host);
-                let client = client.clone();
-                let schema_str = String::from(params["value"].as_str().unwrap());
-                println!("schema: {:?}", schema_str);
-                let url = format!(
-                    "{}{}/routeSwitch",
-                    self.selected_host.as_ref().unwrap(),
-                    DEFAULT_PATH
-                );
-                tauri::async_runtime::spawn(async move {
-                    let res = client.post(url).body(schema_str).send().await;
-                    debug_println!("{:?}", res);
-                });
-            }
-            OperationId::Safari => todo!(),
-            OperationId::InstallApp => todo!(),
-            OperationId::Quit => todo!(),
-            OperationId::OpenSimulator => todo!(),
-            OperationId::SelectHost => todo!(),
-            OperationId::RouteBack => {
-                debug_println!("route back operation: {:?}", params);
-                let client = client.clone();
-                let url = format!(
-                    "{}{}/routeVCOperation",
-                    self.selected_host.as_ref().unwrap(),
-                    DEFAULT_PATH
-                );
-                let operation = String::from(params["value"].as_str().unwrap());
-                tauri::async_runtime::spawn(async move {
-                    let res = client.post(url).body(operation).send().await;
-                    debug_println!("{:?}", res);
-                });
+                let schema = value
+                    .as_str()
+                    .ok_or_else(|| HostError::InvalidParams("schema is required".to_string()))?;
+                debug_println!("schema: {:?}", schema);
+                client.route_switch(schema).await
             }
-            OperationId::RouteForward => todo!(),
-            OperationId::RouteRefresh => todo!(),
+            OperationId::RouteBack => client.route_vc_operation(RouteOperation::Back).await,
+            OperationId::RouteForward => client.route_vc_operation(RouteOperation::Forward).await,
+            OperationId::RouteRefresh => client.route_vc_operation(RouteOperation::Refresh).await,
             OperationId::SetEnv => {
-                let client = client.clone();
-                let url = format!(
-                    "{}{}/setEnv",
-                    self.selected_host.as_ref().unwrap(),
-                    DEFAULT_PATH
-                );
-                let set_env_params: SetEnvParams = serde_json::from_value(params["value"].clone()).unwrap();
+                let set_env_params: SetEnvParams = serde_json::from_value(value)
+                    .map_err(|e| HostError::InvalidParams(e.to_string()))?;
                 debug_println!("{:?}", set_env_params);
-                tauri::async_runtime::spawn(async move {
-                    let res = client.post(url).json(&set_env_params).send().await;
-                    debug_println!("{:?}", res);
-                });
+                client.set_env(&set_env_params).await
             }
-            OperationId::Login => todo!(),
-            OperationId::Logout => todo!(),
-            OperationId::DebugMenu => {
-                let client = client.clone();
-                let url = format!(
-                    "{}{}/routeSwitch",
-                    self.selected_host.as_ref().unwrap(),
-                    DEFAULT_PATH
-                );
-                let body_json = json!({});
-                tauri::async_runtime::spawn(async move {
-                    let res = client.post(url).body("sslocal://debug").send().await;
-                    debug_println!("{:?}", res);
-                });
+            OperationId::Login => {
+                let account: Account = serde_json::from_value(value)
+                    .map_err(|e| HostError::InvalidParams(e.to_string()))?;
+                debug_println!("login: {}", account.name);
+                client.login(&LoginParams::from(&account)).await
             }
+            OperationId::Logout => client.logout().await,
+            OperationId::DebugMenu => client.open_debug_menu().await,
+            _ => Err(HostError::UnsupportedOperation(operation.to_string())),
         }
     }
 }
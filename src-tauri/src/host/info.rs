@@ -0,0 +1,221 @@
+/**
+ * This module parses the heartbeat of the debug host.
+ * Since protocol version 1 the heartbeat is JSON describing the app, the device & the supported operations,
+ * older hosts answer `costa_heartbeat_success_<name>` and are given the operations they always had.
+ */
+use debug_print::debug_println;
+use serde::{Deserialize, Serialize};
+
+use crate::tray::operation::OperationId;
+
+/** the heartbeat protocol version costa speaks, sent as `?version=` so the host can answer in kind */
+pub const HOST_PROTOCOL_VERSION: u32 = 1;
+/** prefix of the plain text heartbeat of the hosts before protocol version 1 */
+pub const LEGACY_HEARTBEAT_PREFIX: &str = "costa_heartbeat_success_";
+
+/**
+ * An operation the debug host can handle, named as in the `operations` of the heartbeat
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum HostCapability {
+    RouteSwitch,
+    RouteBack,
+    RouteForward,
+    RouteRefresh,
+    SetEnv,
+    Login,
+    Logout,
+    DebugMenu,
+    CurrentRoute,
+    /** declared by a newer host, ignored */
+    #[serde(other)]
+    Unknown,
+}
+
+/** the operations costa sent to the hosts before they declared them */
+pub const LEGACY_CAPABILITIES: [HostCapability; 4] = [
+    HostCapability::RouteSwitch,
+    HostCapability::RouteBack,
+    HostCapability::SetEnv,
+    HostCapability::DebugMenu,
+];
+
+impl HostCapability {
+    /** the capability the tray operation needs, None if costa handles the operation itself */
+    pub fn for_operation(operation: &OperationId) -> Option<Self> {
+        match operation {
+            OperationId::ClipboardSchema => Some(HostCapability::RouteSwitch),
+            OperationId::RouteBack => Some(HostCapability::RouteBack),
+            OperationId::RouteForward => Some(HostCapability::RouteForward),
+            OperationId::RouteRefresh => Some(HostCapability::RouteRefresh),
+            OperationId::SetEnv => Some(HostCapability::SetEnv),
+            OperationId::Login => Some(HostCapability::Login),
+            OperationId::Logout => Some(HostCapability::Logout),
+            OperationId::DebugMenu => Some(HostCapability::DebugMenu),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * The app & device behind a debug host, from the heartbeat
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HostInfo {
+    /** 0 for the hosts answering the plain text heartbeat */
+    pub protocol_version: u32,
+    pub app_name: String,
+    pub bundle_id: Option<String>,
+    pub app_version: Option<String>,
+    pub device_name: Option<String>,
+    pub device_udid: Option<String>,
+    pub operations: Vec<HostCapability>,
+}
+
+impl HostInfo {
+    /** a host answering `costa_heartbeat_success_<name>` */
+    pub fn legacy(name: &str) -> Self {
+        Self {
+            protocol_version: 0,
+            app_name: name.to_string(),
+            operations: LEGACY_CAPABILITIES.to_vec(),
+            ..Default::default()
+        }
+    }
+    pub fn is_legacy(&self) -> bool {
+        self.protocol_version == 0
+    }
+    pub fn supports(&self, capability: HostCapability) -> bool {
+        self.operations.contains(&capability)
+    }
+    /** true if the host can handle the operation, operations of costa itself are always supported */
+    pub fn supports_operation(&self, operation: &OperationId) -> bool {
+        HostCapability::for_operation(operation)
+            .map(|capability| self.supports(capability))
+            .unwrap_or(true)
+    }
+    /** title of the host in the tray, e.g. "App1 1.2.0 - iPhone 15" */
+    pub fn display_name(&self) -> String {
+        let mut name = self.app_name.clone();
+        if let Some(version) = &self.app_version {
+            name = format!("{} {}", name, version);
+        }
+        match &self.device_name {
+            Some(device) => format!("{} - {}", name, device),
+            None => name,
+        }
+    }
+}
+
+/**
+ * parse the heartbeat body, None if it is not a costa heartbeat
+ */
+pub fn parse_heartbeat(body: &str) -> Option<HostInfo> {
+    let body = body.trim();
+    if body.starts_with('{') {
+        return match serde_json::from_str::<HostInfo>(body) {
+            Ok(info) if info.protocol_version >= 1 && !info.app_name.is_empty() => {
+                if info.protocol_version > HOST_PROTOCOL_VERSION {
+                    debug_println!("newer host protocol: {}", info.protocol_version);
+                }
+                Some(info)
+            }
+            Ok(info) => {
+                debug_println!("invalid heartbeat: {:?}", info);
+                None
+            }
+            Err(e) => {
+                debug_println!("heartbeat parse error: {}", e);
+                None
+            }
+        };
+    }
+    body.split_once(LEGACY_HEARTBEAT_PREFIX)
+        .map(|(_, name)| name.trim())
+        .filter(|name| !name.is_empty())
+        .map(HostInfo::legacy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_legacy_heartbeat() {
+        let info = parse_heartbeat("  costa_heartbeat_success_App1 \n").unwrap();
+        assert_eq!(info, HostInfo::legacy("App1"));
+        assert!(info.is_legacy());
+        assert_eq!(info.display_name(), "App1");
+        assert_eq!(
+            info.operations,
+            vec![
+                HostCapability::RouteSwitch,
+                HostCapability::RouteBack,
+                HostCapability::SetEnv,
+                HostCapability::DebugMenu,
+            ]
+        );
+        assert!(info.supports_operation(&OperationId::ClipboardSchema));
+        assert!(!info.supports_operation(&OperationId::RouteForward));
+        assert!(!info.supports_operation(&OperationId::Login));
+        // handled by costa itself
+        assert!(info.supports_operation(&OperationId::Screenshot));
+
+        assert_eq!(parse_heartbeat("costa_heartbeat_success_"), None);
+        assert_eq!(parse_heartbeat(" costa_heartbeat_success_  \n"), None);
+    }
+
+    #[test]
+    fn parses_the_json_heartbeat() {
+        let info = parse_heartbeat(
+            r#"{
+                "protocolVersion": 1,
+                "appName": "App1",
+                "appVersion": "1.2.0",
+                "deviceName": "iPhone 15",
+                "operations": ["routeSwitch", "login", "teleport"]
+            }"#,
+        )
+        .unwrap();
+        assert!(!info.is_legacy());
+        assert_eq!(info.display_name(), "App1 1.2.0 - iPhone 15");
+        assert_eq!(info.bundle_id, None);
+        assert_eq!(
+            info.operations,
+            vec![
+                HostCapability::RouteSwitch,
+                HostCapability::Login,
+                HostCapability::Unknown,
+            ]
+        );
+        assert!(info.supports_operation(&OperationId::Login));
+        assert!(!info.supports_operation(&OperationId::SetEnv));
+
+        // a newer host is still understood
+        let info = parse_heartbeat(r#"{"protocolVersion": 2, "appName": "App1", "extra": true}"#);
+        assert_eq!(info.map(|info| info.protocol_version), Some(2));
+        // version 0 is only the plain text heartbeat
+        assert_eq!(
+            parse_heartbeat(r#"{"protocolVersion": 0, "appName": "App1"}"#),
+            None
+        );
+        assert_eq!(parse_heartbeat(r#"{"protocolVersion": 1}"#), None);
+    }
+
+    #[test]
+    fn ignores_other_bodies() {
+        for body in [
+            "",
+            "  ",
+            "ok",
+            "<html><body>Not Found</body></html>",
+            "{",
+            r#"{"status": "ok"}"#,
+            r#"{"protocolVersion": "1", "appName": "App1"}"#,
+        ] {
+            assert_eq!(parse_heartbeat(body), None, "{}", body);
+        }
+    }
+}
@@ -0,0 +1,313 @@
+/**
+ * This module browses the `_costa._tcp` mDNS service, announced by the debug SDK on physical devices.
+ * The resolved services are described by their TXT records (same keys as the JSON heartbeat)
+ * and merged with the hosts found by the port scan.
+ */
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+};
+
+use debug_print::debug_println;
+use log::error;
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+
+use super::info::{HostCapability, HostInfo, LEGACY_CAPABILITIES};
+
+pub const MDNS_SERVICE_TYPE: &str = "_costa._tcp.local.";
+
+/**
+ * the url & info of a resolved service, None if it has no address or app name
+ * TXT keys: protocolVersion, appName, bundleId, appVersion, deviceName, deviceUdid,
+ * operations (comma separated, the legacy operations if missing)
+ */
+pub fn parse_service_info(service: &ServiceInfo) -> Option<(String, HostInfo)> {
+    let txt = |key: &str| {
+        service
+            .get_property_val_str(key)
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    // prefer ipv4, the sort keeps the url stable between announcements
+    let mut addresses = service.get_addresses().iter().collect::<Vec<_>>();
+    addresses.sort_by_key(|ip| (!ip.is_ipv4(), **ip));
+    let url = match addresses.first()? {
+        IpAddr::V4(ip) => format!("http://{}:{}", ip, service.get_port()),
+        IpAddr::V6(ip) => format!("http://[{}]:{}", ip, service.get_port()),
+    };
+    let protocol_version = txt("protocolVersion")
+        .and_then(|version| version.parse::<u32>().ok())
+        .unwrap_or(0);
+    let operations = match txt("operations") {
+        Some(operations) => operations
+            .split(',')
+            .filter_map(|operation| {
+                serde_json::from_value::<HostCapability>(operation.trim().into()).ok()
+            })
+            .collect(),
+        None => LEGACY_CAPABILITIES.to_vec(),
+    };
+    // the instance name, e.g. "App1._costa._tcp.local."
+    let instance = service
+        .get_fullname()
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let app_name = txt("appName").unwrap_or(instance);
+    if app_name.is_empty() {
+        return None;
+    }
+    let info = HostInfo {
+        protocol_version,
+        app_name,
+        bundle_id: txt("bundleId"),
+        app_version: txt("appVersion"),
+        device_name: txt("deviceName"),
+        device_udid: txt("deviceUdid"),
+        operations,
+    };
+    Some((url, info))
+}
+
+#[derive(Default)]
+struct BrowserState {
+    daemon: Option<ServiceDaemon>,
+    /** the daemon cannot start, e.g. no multicast interface, retried when enabled again */
+    failed: bool,
+}
+
+/**
+ * The mDNS browser managed by tauri, key of the hosts: full name of the service
+ */
+#[derive(Default)]
+pub struct MdnsBrowser {
+    state: Mutex<BrowserState>,
+    hosts: Arc<Mutex<HashMap<String, (String, HostInfo)>>>,
+}
+
+impl MdnsBrowser {
+    /**
+     * browse the service type with the daemon, the events are handled in a thread until the daemon shuts down
+     */
+    pub fn start(&self, daemon: ServiceDaemon, service_type: &str) -> Result<(), String> {
+        let receiver = daemon.browse(service_type).map_err(|e| e.to_string())?;
+        let hosts = self.hosts.clone();
+        std::thread::spawn(move || {
+            while let Ok(event) = receiver.recv() {
+                match event {
+                    ServiceEvent::ServiceResolved(service) => {
+                        if let Some((url, info)) = parse_service_info(&service) {
+                            debug_println!("mdns host resolved: {} {:?}", url, info);
+                            let fullname = service.get_fullname().to_string();
+                            hosts.lock().unwrap().insert(fullname, (url, info));
+                        }
+                    }
+                    ServiceEvent::ServiceRemoved(_, fullname) => {
+                        debug_println!("mdns host removed: {}", fullname);
+                        hosts.lock().unwrap().remove(&fullname);
+                    }
+                    _ => {}
+                }
+            }
+        });
+        self.state.lock().unwrap().daemon = Some(daemon);
+        Ok(())
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state.lock().unwrap().daemon.is_some()
+    }
+
+    /** shut the daemon down & forget the hosts */
+    pub fn stop(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(daemon) = state.daemon.take() {
+            let _ = daemon.shutdown();
+        }
+        state.failed = false;
+        self.hosts.lock().unwrap().clear();
+    }
+
+    /**
+     * start browsing `_costa._tcp` if enabled and not running yet, stop it if disabled
+     */
+    pub fn set_enabled(&self, enabled: bool) {
+        if !enabled {
+            self.stop();
+            return;
+        }
+        if self.is_running() || self.state.lock().unwrap().failed {
+            return;
+        }
+        let res = ServiceDaemon::new()
+            .map_err(|e| e.to_string())
+            .and_then(|daemon| self.start(daemon, MDNS_SERVICE_TYPE));
+        if let Err(e) = res {
+            error!("start mdns browser error: {}", e);
+            self.state.lock().unwrap().failed = true;
+        }
+    }
+
+    /** the resolved hosts, key: url of the host */
+    pub fn hosts(&self) -> HashMap<String, HostInfo> {
+        self.hosts.lock().unwrap().values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn service(instance: &str, properties: &[(&str, &str)]) -> ServiceInfo {
+        ServiceInfo::new(
+            MDNS_SERVICE_TYPE,
+            instance,
+            "costa-test.local.",
+            "127.0.0.1",
+            9081,
+            properties,
+        )
+        .unwrap()
+    }
+
+    /** poll the hosts of the browser until the condition holds */
+    fn wait_hosts(
+        browser: &MdnsBrowser,
+        condition: impl Fn(&HashMap<String, HostInfo>) -> bool,
+    ) -> HashMap<String, HostInfo> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let hosts = browser.hosts();
+            if condition(&hosts) || Instant::now() > deadline {
+                return hosts;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    #[test]
+    fn parses_the_txt_records() {
+        let info = service(
+            "App1",
+            &[
+                ("protocolVersion", "1"),
+                ("appName", "Demo"),
+                ("bundleId", "com.example.demo"),
+                ("appVersion", " 1.2.0 "),
+                ("deviceName", "iPhone"),
+                ("deviceUdid", ""),
+                ("operations", "routeBack, newOperation,login"),
+            ],
+        );
+        let (url, host) = parse_service_info(&info).unwrap();
+        assert_eq!(url, "http://127.0.0.1:9081");
+        assert_eq!(
+            host,
+            HostInfo {
+                protocol_version: 1,
+                app_name: "Demo".to_string(),
+                bundle_id: Some("com.example.demo".to_string()),
+                app_version: Some("1.2.0".to_string()),
+                device_name: Some("iPhone".to_string()),
+                device_udid: None,
+                operations: vec![
+                    HostCapability::RouteBack,
+                    HostCapability::Unknown,
+                    HostCapability::Login,
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_instance_name_and_legacy_operations() {
+        let (_, host) = parse_service_info(&service("App1", &[("appName", " ")])).unwrap();
+        assert_eq!(host.protocol_version, 0);
+        assert_eq!(host.app_name, "App1");
+        assert_eq!(host.operations, LEGACY_CAPABILITIES.to_vec());
+    }
+
+    #[test]
+    fn prefers_ipv4_addresses() {
+        let info = ServiceInfo::new(
+            MDNS_SERVICE_TYPE,
+            "App1",
+            "costa-test.local.",
+            &["fe80::1", "192.168.1.20", "10.0.0.2"][..],
+            9082,
+            None,
+        )
+        .unwrap();
+        let (url, _) = parse_service_info(&info).unwrap();
+        assert_eq!(url, "http://10.0.0.2:9082");
+        let info = ServiceInfo::new(
+            MDNS_SERVICE_TYPE,
+            "App1",
+            "costa-test.local.",
+            "fe80::1",
+            9082,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            parse_service_info(&info).unwrap().0,
+            "http://[fe80::1]:9082"
+        );
+    }
+
+    /**
+     * needs a non-loopback interface with multicast, which CI runners & sandboxes often lack
+     * run with `cargo test -- --ignored` on a machine in a LAN
+     */
+    #[test]
+    #[ignore = "needs a LAN interface with multicast"]
+    fn browses_the_registered_hosts() {
+        let publisher = ServiceDaemon::new().unwrap();
+        // mdns-sd skips the loopback, the service is announced with the addresses of the LAN interfaces
+        let properties = [
+            ("protocolVersion", "1"),
+            ("appName", "Demo"),
+            ("operations", "routeSwitch"),
+        ];
+        let info = ServiceInfo::new(
+            MDNS_SERVICE_TYPE,
+            "App1",
+            "costa-test.local.",
+            "",
+            9083,
+            &properties[..],
+        )
+        .unwrap()
+        .enable_addr_auto();
+        let fullname = info.get_fullname().to_string();
+        let browser = MdnsBrowser::default();
+        browser
+            .start(ServiceDaemon::new().unwrap(), MDNS_SERVICE_TYPE)
+            .unwrap();
+        assert!(browser.is_running());
+        publisher.register(info).unwrap();
+
+        let hosts = wait_hosts(&browser, |hosts| !hosts.is_empty());
+        assert_eq!(hosts.len(), 1, "{:?}", hosts);
+        let (url, host) = hosts.iter().next().unwrap();
+        assert!(
+            url.starts_with("http://") && url.ends_with(":9083"),
+            "{}",
+            url
+        );
+        assert_eq!(host.app_name, "Demo");
+        assert_eq!(host.protocol_version, 1);
+        assert_eq!(host.operations, vec![HostCapability::RouteSwitch]);
+
+        publisher.unregister(&fullname).unwrap();
+        assert!(wait_hosts(&browser, |hosts| hosts.is_empty()).is_empty());
+
+        browser.stop();
+        assert!(!browser.is_running());
+        let _ = publisher.shutdown();
+    }
+}
@@ -1,2 +1,6 @@
 pub mod host;
-pub mod scanner;
\ No newline at end of file
+pub mod scanner;
+pub mod client;
+pub mod account;
+pub mod info;
+pub mod mdns;
\ No newline at end of file
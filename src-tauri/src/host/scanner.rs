@@ -1,35 +1,156 @@
 // This module is used to send the heartbeat to the dev host to indicate that the debugger is running.
 
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use debug_print::debug_println;
+use serde::{Deserialize, Serialize};
+use tokio::{sync::Semaphore, task::JoinSet};
 
 use crate::constant::{DEFAULT_HOSTNAME, DEFAULT_PATH};
 
+use super::info::{parse_heartbeat, HostInfo, HOST_PROTOCOL_VERSION};
+
+/** heartbeats sent at the same time */
+pub const MAX_CONCURRENT_PROBES: usize = 32;
+/** ports probed by one scan, larger ranges are cut */
+pub const MAX_SCAN_PORTS: usize = 1024;
+
+/**
+ * A host & the ports its debug hosts may listen on, e.g. the LAN ip of a physical device
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScanTarget {
+    /** ip or hostname, `http://` is added if there is no scheme */
+    pub host: String,
+    pub port_start: u16,
+    /** inclusive */
+    pub port_end: u16,
+}
+
+impl ScanTarget {
+    pub fn urls(&self) -> Vec<String> {
+        let host = if self.host.contains("://") {
+            self.host.trim_end_matches('/').to_string()
+        } else {
+            format!("http://{}", self.host)
+        };
+        (self.port_start..=self.port_end)
+            .map(|port| format!("{}:{}", host, port))
+            .collect()
+    }
+}
+
+/**
+ * Settings of the debug host discovery, saved in the preferences
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct HostDiscoverySettings {
+    pub targets: Vec<ScanTarget>,
+    pub probe_timeout_ms: u64,
+    /** a host missing heartbeats is kept for n seconds before it is removed */
+    pub stale_grace_secs: u64,
+    /** a port failing n heartbeats in a row is probed less often */
+    pub backoff_after_failures: u32,
+    /** the longest wait between two heartbeats of a failing port */
+    pub max_backoff_secs: u64,
+    /** browse the `_costa._tcp` mDNS service for hosts on the LAN */
+    pub mdns: bool,
+}
+
+impl Default for HostDiscoverySettings {
+    fn default() -> Self {
+        Self {
+            // the ports the simulator apps used to be scanned on
+            targets: vec![ScanTarget {
+                host: DEFAULT_HOSTNAME.to_string(),
+                port_start: 9081,
+                port_end: 9091,
+            }],
+            probe_timeout_ms: 1000,
+            stale_grace_secs: 10,
+            backoff_after_failures: 3,
+            max_backoff_secs: 30,
+            mdns: true,
+        }
+    }
+}
+
+impl HostDiscoverySettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(target) = self.targets.iter().find(|t| t.host.trim().is_empty()) {
+            return Err(format!(
+                "host of {}-{} is empty",
+                target.port_start, target.port_end
+            ));
+        }
+        if let Some(target) = self.targets.iter().find(|t| t.port_start > t.port_end) {
+            return Err(format!(
+                "invalid port range of {}: {}-{}",
+                target.host, target.port_start, target.port_end
+            ));
+        }
+        if self.probe_timeout_ms == 0 {
+            return Err("probe timeout must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+    /** urls of all targets without duplicates, at most `MAX_SCAN_PORTS` */
+    pub fn urls(&self) -> Vec<String> {
+        let mut urls = vec![];
+        for url in self.targets.iter().flat_map(|t| t.urls()) {
+            if urls.len() >= MAX_SCAN_PORTS {
+                debug_println!("too many ports to scan, the rest is skipped");
+                break;
+            }
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        urls
+    }
+    /** a host seen at `seen` is kept until the grace period is over */
+    fn within_grace(&self, seen: Instant, now: Instant) -> bool {
+        now.saturating_duration_since(seen) <= Duration::from_secs(self.stale_grace_secs)
+    }
+    /**
+     * the wait before the next heartbeat of a port failing `failures` times in a row, doubled each time
+     */
+    pub fn backoff(&self, failures: u32) -> Option<Duration> {
+        if failures < self.backoff_after_failures.max(1) {
+            return None;
+        }
+        let exponent = failures - self.backoff_after_failures.max(1);
+        let secs = 1u64
+            .checked_shl(exponent)
+            .unwrap_or(u64::MAX)
+            .min(self.max_backoff_secs);
+        Some(Duration::from_secs(secs))
+    }
+}
+
 pub async fn heartbeat(
     client: &reqwest::Client,
     url: &str,
-) -> Result<Option<String>, Box<dyn std::error::Error>> {
-    let url = format!("{}{}/heartbeat", url, DEFAULT_PATH);
-    // TODO: send heartbeat to the dev host
-    let resp = client.get(url).timeout(Duration::from_secs(1)).send().await;
-    // log::info!("heartbeat resp: {}", resp);
+) -> Result<Option<HostInfo>, Box<dyn std::error::Error + Send + Sync>> {
+    // hosts before protocol version 1 ignore the query
+    let url = format!(
+        "{}{}/heartbeat?version={}",
+        url, DEFAULT_PATH, HOST_PROTOCOL_VERSION
+    );
+    let resp = client.get(url).send().await;
     let resp = match resp {
         Ok(resp) => resp.text().await?,
         Err(e) => {
             return Err(Box::new(e));
         }
     };
-    if resp.len() > 0 {
-        if resp.contains("costa_heartbeat_success_") {
-            // heartbeat success, resp is host device info
-            return Ok(Some(
-                resp.split("costa_heartbeat_success_")
-                    .last()
-                    .unwrap()
-                    .to_string(),
-            ));
-        }
+    if let Some(info) = parse_heartbeat(&resp) {
+        return Ok(Some(info));
     }
     // heartbeat failed
     Err(Box::new(std::io::Error::new(
@@ -38,22 +159,282 @@ pub async fn heartbeat(
     )))
 }
 
-pub async fn scan_local_debug_host() -> Result<HashMap<String, String>, Box<dyn std::error::Error>>
-{
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(1))
-        .build()
-        .unwrap();
-    let local_host = format!("http://{}", DEFAULT_HOSTNAME);
-    // from port 9081 to 9091, scan all ports
-    let mut hosts = HashMap::new();
-    for port in 9081..9092 {
-        let url = format!("{}:{}", local_host, port);
-        let resp = heartbeat(&client, &url).await;
-        if let Ok(Some(resp)) = resp {
-            hosts.insert(resp, url.to_string());
-        }
-    }
-    // log::info!("scan_local_debug_host: {:?}", hosts);
-    Ok(hosts)
+#[derive(Debug, Default)]
+struct ProbeState {
+    /** heartbeats failed in a row */
+    failures: u32,
+    /** the port is skipped until then */
+    next_probe: Option<Instant>,
+    last_seen: Option<Instant>,
+    info: Option<HostInfo>,
+}
+
+/**
+ * The probe history of each url, managed by tauri
+ */
+#[derive(Default)]
+pub struct HostScanner(Mutex<HashMap<String, ProbeState>>);
+
+impl HostScanner {
+    /**
+     * send the heartbeat to the due urls concurrently, return the hosts alive or within the grace period
+     * key: url of the host
+     */
+    pub async fn scan(&self, settings: &HostDiscoverySettings) -> HashMap<String, HostInfo> {
+        let urls = settings.urls();
+        let now = Instant::now();
+        let due = {
+            let mut probes = self.0.lock().unwrap();
+            // forget the urls removed from the settings
+            probes.retain(|url, _| urls.contains(url));
+            urls.into_iter()
+                .filter(
+                    |url| match probes.get(url).and_then(|probe| probe.next_probe) {
+                        Some(next) => next <= now,
+                        None => true,
+                    },
+                )
+                .collect::<Vec<_>>()
+        };
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(settings.probe_timeout_ms))
+            .build()
+            .unwrap_or_default();
+        let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_PROBES));
+        let mut probes = JoinSet::new();
+        for url in due {
+            let client = client.clone();
+            let semaphore = semaphore.clone();
+            probes.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let info = heartbeat(&client, &url).await.ok().flatten();
+                (url, info)
+            });
+        }
+        while let Some(probe) = probes.join_next().await {
+            if let Ok((url, info)) = probe {
+                self.record(settings, url, info, Instant::now());
+            }
+        }
+        self.hosts(settings, Instant::now())
+    }
+
+    fn record(
+        &self,
+        settings: &HostDiscoverySettings,
+        url: String,
+        info: Option<HostInfo>,
+        now: Instant,
+    ) {
+        let mut probes = self.0.lock().unwrap();
+        let probe = probes.entry(url).or_default();
+        match info {
+            Some(info) => {
+                probe.failures = 0;
+                probe.next_probe = None;
+                probe.last_seen = Some(now);
+                probe.info = Some(info);
+            }
+            None => {
+                probe.failures = probe.failures.saturating_add(1);
+                // a host within the grace period is probed every time to catch it coming back
+                let recently_seen = probe
+                    .last_seen
+                    .is_some_and(|seen| settings.within_grace(seen, now));
+                probe.next_probe = match settings.backoff(probe.failures) {
+                    Some(delay) if !recently_seen => Some(now + delay),
+                    _ => None,
+                };
+            }
+        }
+    }
+
+    fn hosts(&self, settings: &HostDiscoverySettings, now: Instant) -> HashMap<String, HostInfo> {
+        let mut probes = self.0.lock().unwrap();
+        let mut hosts = HashMap::new();
+        for (url, probe) in probes.iter_mut() {
+            let alive = probe
+                .last_seen
+                .is_some_and(|seen| settings.within_grace(seen, now));
+            match (alive, probe.info.as_ref()) {
+                (true, Some(info)) => {
+                    hosts.insert(url.clone(), info.clone());
+                }
+                // stale for longer than the grace period
+                _ => probe.info = None,
+            }
+        }
+        hosts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(host: &str, port_start: u16, port_end: u16) -> ScanTarget {
+        ScanTarget {
+            host: host.to_string(),
+            port_start,
+            port_end,
+        }
+    }
+
+    fn info(app_name: &str) -> HostInfo {
+        HostInfo {
+            app_name: app_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn next_probe(scanner: &HostScanner, url: &str) -> Option<Instant> {
+        scanner
+            .0
+            .lock()
+            .unwrap()
+            .get(url)
+            .and_then(|p| p.next_probe)
+    }
+
+    #[test]
+    fn validates_the_targets() {
+        assert_eq!(HostDiscoverySettings::default().validate(), Ok(()));
+        let settings = |targets, probe_timeout_ms| HostDiscoverySettings {
+            targets,
+            probe_timeout_ms,
+            ..Default::default()
+        };
+        assert_eq!(
+            settings(vec![target(" ", 9081, 9091)], 1000).validate(),
+            Err("host of 9081-9091 is empty".to_string())
+        );
+        assert_eq!(
+            settings(vec![target("192.168.1.20", 9091, 9081)], 1000).validate(),
+            Err("invalid port range of 192.168.1.20: 9091-9081".to_string())
+        );
+        assert_eq!(
+            settings(vec![target("192.168.1.20", 9081, 9081)], 0).validate(),
+            Err("probe timeout must be greater than 0".to_string())
+        );
+    }
+
+    #[test]
+    fn urls_without_duplicates_and_at_most_max_scan_ports() {
+        assert_eq!(
+            target("https://device.local/", 8080, 8081).urls(),
+            vec!["https://device.local:8080", "https://device.local:8081"]
+        );
+        let settings = HostDiscoverySettings {
+            targets: vec![
+                target("127.0.0.1", 9081, 9083),
+                target("127.0.0.1", 9082, 9084),
+                target("http://127.0.0.1", 9084, 9084),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            settings.urls(),
+            vec![
+                "http://127.0.0.1:9081",
+                "http://127.0.0.1:9082",
+                "http://127.0.0.1:9083",
+                "http://127.0.0.1:9084",
+            ]
+        );
+        let settings = HostDiscoverySettings {
+            targets: vec![target("127.0.0.1", 1, 2000), target("10.0.0.2", 1, 1)],
+            ..Default::default()
+        };
+        let urls = settings.urls();
+        assert_eq!(urls.len(), MAX_SCAN_PORTS);
+        assert_eq!(
+            urls.last().map(|url| url.as_str()),
+            Some("http://127.0.0.1:1024")
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_max() {
+        let settings = HostDiscoverySettings {
+            backoff_after_failures: 3,
+            max_backoff_secs: 30,
+            ..Default::default()
+        };
+        let secs = |failures| settings.backoff(failures).map(|d| d.as_secs());
+        assert_eq!(secs(0), None);
+        assert_eq!(secs(2), None);
+        assert_eq!(secs(3), Some(1));
+        assert_eq!(secs(4), Some(2));
+        assert_eq!(secs(7), Some(16));
+        assert_eq!(secs(8), Some(30));
+        assert_eq!(secs(u32::MAX), Some(30));
+        // 0 backs off from the first failure
+        let settings = HostDiscoverySettings {
+            backoff_after_failures: 0,
+            ..settings
+        };
+        assert_eq!(settings.backoff(1), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn no_backoff_within_the_grace_period() {
+        let settings = HostDiscoverySettings {
+            stale_grace_secs: 10,
+            backoff_after_failures: 1,
+            ..Default::default()
+        };
+        let url = "http://127.0.0.1:9081";
+        let scanner = HostScanner::default();
+        let seen = Instant::now();
+        scanner.record(&settings, url.to_string(), Some(info("Demo")), seen);
+        for secs in 1..=10 {
+            let now = seen + Duration::from_secs(secs);
+            scanner.record(&settings, url.to_string(), None, now);
+            assert_eq!(next_probe(&scanner, url), None, "{}s after", secs);
+        }
+        // the 11th failure is past the grace period, the 1024s backoff is capped
+        let now = seen + Duration::from_secs(11);
+        scanner.record(&settings, url.to_string(), None, now);
+        assert_eq!(
+            next_probe(&scanner, url),
+            Some(now + Duration::from_secs(settings.max_backoff_secs))
+        );
+        // a port never seen backs off at once
+        let never_seen = "http://127.0.0.1:9082";
+        scanner.record(&settings, never_seen.to_string(), None, seen);
+        assert_eq!(
+            next_probe(&scanner, never_seen),
+            Some(seen + Duration::from_secs(1))
+        );
+        // a heartbeat resets the failures
+        scanner.record(&settings, url.to_string(), Some(info("Demo")), now);
+        assert_eq!(next_probe(&scanner, url), None);
+        assert_eq!(scanner.0.lock().unwrap()[url].failures, 0);
+    }
+
+    #[test]
+    fn hosts_are_removed_after_the_grace_period() {
+        let settings = HostDiscoverySettings {
+            stale_grace_secs: 10,
+            ..Default::default()
+        };
+        let url = "http://127.0.0.1:9081";
+        let scanner = HostScanner::default();
+        let seen = Instant::now();
+        scanner.record(&settings, url.to_string(), Some(info("Demo")), seen);
+        scanner.record(
+            &settings,
+            url.to_string(),
+            None,
+            seen + Duration::from_secs(5),
+        );
+        let hosts = scanner.hosts(&settings, seen + Duration::from_secs(10));
+        assert_eq!(hosts.get(url), Some(&info("Demo")));
+
+        assert!(scanner
+            .hosts(&settings, seen + Duration::from_secs(11))
+            .is_empty());
+        // the stale info is dropped, not only hidden
+        assert!(scanner.hosts(&settings, seen).is_empty());
+    }
 }
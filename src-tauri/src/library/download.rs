@@ -0,0 +1,890 @@
+/**
+ * This module downloads app builds & simulator runtimes into `~/.costa/downloads`.
+ * A download is streamed into `<name>.part` and resumed by an HTTP range request validated by `If-Range`,
+ * the finished file is verified by sha256, imported into the package library and handed to the install pipeline.
+ */
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use debug_print::debug_println;
+use log::error;
+use reqwest::{header, StatusCode};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tokio::{io::AsyncWriteExt, sync::Semaphore};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    command::util::get_target_device,
+    path::get_downloads_dir,
+    simulator::{backend::get_simctl_backend, command, install},
+};
+
+use super::package::{self, hash_package, PackageKind, PackageRecord};
+
+/** downloads running at the same time, the others wait in the queue */
+pub const MAX_CONCURRENT_DOWNLOADS: usize = 2;
+/** minimum interval of the `download_progress` events */
+pub const DOWNLOAD_PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadError {
+    Http(String),
+    /** the server answered with an error status */
+    Status(u16),
+    Io(String),
+    ChecksumMismatch {
+        expected: String,
+        actual: String,
+    },
+    Cancelled,
+    /** the file name is empty, `..` or has a path separator */
+    InvalidFileName(String),
+    /** another running download writes the same file */
+    AlreadyDownloading(String),
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownloadError::Http(e) => write!(f, "download failed: {}", e),
+            DownloadError::Status(status) => write!(f, "download failed with status {}", status),
+            DownloadError::Io(e) => write!(f, "{}", e),
+            DownloadError::ChecksumMismatch { expected, actual } => {
+                write!(f, "sha256 mismatch, expected {}, got {}", expected, actual)
+            }
+            DownloadError::Cancelled => write!(f, "download cancelled"),
+            DownloadError::InvalidFileName(name) => write!(f, "invalid file name: {}", name),
+            DownloadError::AlreadyDownloading(name) => {
+                write!(f, "{} is already downloading", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DownloadError {}
+
+impl From<reqwest::Error> for DownloadError {
+    fn from(e: reqwest::Error) -> Self {
+        DownloadError::Http(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for DownloadError {
+    fn from(e: std::io::Error) -> Self {
+        DownloadError::Io(e.to_string())
+    }
+}
+
+impl From<DownloadError> for String {
+    fn from(e: DownloadError) -> Self {
+        e.to_string()
+    }
+}
+
+/**
+ * A download started from the webview or the tray
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct DownloadRequest {
+    pub url: String,
+    /** name of the file, the last segment of the url by default */
+    pub file_name: Option<String>,
+    /** expected sha256 in hex, the download fails on mismatch */
+    pub sha256: Option<String>,
+    /** install the app or add the runtime after the download */
+    pub install: bool,
+    /** device of the app install, the first booted device by default */
+    pub udid: Option<String>,
+    /** launch the app after the install */
+    pub launch: bool,
+}
+
+impl DownloadRequest {
+    /**
+     * the name of the file in the downloads folder, it must not point to another folder
+     */
+    pub fn file_name(&self) -> Result<String, DownloadError> {
+        let name = self.file_name.clone().unwrap_or_else(|| {
+            let path = self.url.split(['?', '#']).next().unwrap_or_default();
+            path.rsplit('/')
+                .find(|s| !s.is_empty() && !s.contains(':'))
+                .unwrap_or("download")
+                .to_string()
+        });
+        if name.trim().is_empty() || name == "." || name == ".." || name.contains(['/', '\\', '\0'])
+        {
+            return Err(DownloadError::InvalidFileName(name));
+        }
+        Ok(name)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadState {
+    Queued,
+    Downloading,
+    Verifying,
+    Installing,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/**
+ * The payload of the `download_progress` event
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DownloadProgress {
+    pub id: u64,
+    pub url: String,
+    pub file_name: String,
+    pub state: DownloadState,
+    pub downloaded: u64,
+    /** None if the server does not send the length */
+    pub total: Option<u64>,
+    /** the package imported into the library */
+    pub package: Option<PackageRecord>,
+    pub error: Option<String>,
+}
+
+/**
+ * The downloads managed by tauri
+ */
+pub struct DownloadManager {
+    permits: Arc<Semaphore>,
+    next_id: AtomicU64,
+    /** key: download id, value: the last progress & the token which cancels the download */
+    downloads: Mutex<HashMap<u64, (DownloadProgress, CancellationToken)>>,
+}
+
+impl Default for DownloadManager {
+    fn default() -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(MAX_CONCURRENT_DOWNLOADS)),
+            next_id: AtomicU64::new(1),
+            downloads: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl DownloadManager {
+    pub fn list(&self) -> Vec<DownloadProgress> {
+        let mut downloads = self
+            .downloads
+            .lock()
+            .unwrap()
+            .values()
+            .map(|(progress, _)| progress.clone())
+            .collect::<Vec<_>>();
+        downloads.sort_by_key(|p| p.id);
+        downloads
+    }
+    /** cancel the download, the partial file is kept for resuming, return false if it is not running */
+    pub fn cancel(&self, id: u64) -> bool {
+        match self.downloads.lock().unwrap().get(&id) {
+            Some((progress, cancel)) if !is_finished(progress.state) => {
+                cancel.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+    /** remove the finished downloads from the list */
+    pub fn clear_finished(&self) {
+        self.downloads
+            .lock()
+            .unwrap()
+            .retain(|_, (progress, _)| !is_finished(progress.state));
+    }
+    /**
+     * add the download, two running downloads of the same file would write the same partial file
+     * the check & the insert share the lock
+     */
+    fn insert(
+        &self,
+        progress: DownloadProgress,
+        cancel: CancellationToken,
+    ) -> Result<(), DownloadError> {
+        let mut downloads = self.downloads.lock().unwrap();
+        if downloads.values().any(|(running, _)| {
+            running.file_name == progress.file_name && !is_finished(running.state)
+        }) {
+            return Err(DownloadError::AlreadyDownloading(progress.file_name));
+        }
+        downloads.insert(progress.id, (progress, cancel));
+        Ok(())
+    }
+    fn update(&self, progress: &DownloadProgress) {
+        if let Some((last, _)) = self.downloads.lock().unwrap().get_mut(&progress.id) {
+            *last = progress.clone();
+        }
+    }
+}
+
+fn is_finished(state: DownloadState) -> bool {
+    matches!(
+        state,
+        DownloadState::Completed | DownloadState::Failed | DownloadState::Cancelled
+    )
+}
+
+fn partial_path(destination: &Path) -> PathBuf {
+    let mut name = destination.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    destination.with_file_name(name)
+}
+
+/** `<destination>.part.validator`, the ETag or Last-Modified of the partial file */
+fn validator_path(destination: &Path) -> PathBuf {
+    let mut name = destination.file_name().unwrap_or_default().to_os_string();
+    name.push(".part.validator");
+    destination.with_file_name(name)
+}
+
+/** a strong ETag, or the Last-Modified date, a weak ETag cannot validate a range */
+fn response_validator(response: &reqwest::Response) -> Option<String> {
+    let value = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    value(header::ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| value(header::LAST_MODIFIED))
+}
+
+/** start of `Content-Range: bytes <start>-<end>/<total>` */
+fn content_range_start(response: &reqwest::Response) -> Option<u64> {
+    response
+        .headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .trim()
+        .strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/**
+ * stream the url into `destination`, resuming `<destination>.part` if the server supports ranges
+ * a partial file is only resumed with its validator as `If-Range`, a "latest" url may serve another build since
+ * `on_progress` gets (downloaded, total), the partial file is kept on errors & cancellation
+ * independent of tauri, so it works against any http server, e.g. a local stand-in
+ */
+pub async fn download_to_file(
+    client: &reqwest::Client,
+    url: &str,
+    destination: &Path,
+    expected_sha256: Option<&str>,
+    cancel: &CancellationToken,
+    mut on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<(), DownloadError> {
+    if let Some(parent) = destination.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let partial = partial_path(destination);
+    let validator_file = validator_path(destination);
+    let validator = tokio::fs::read_to_string(&validator_file)
+        .await
+        .ok()
+        .filter(|validator| !validator.trim().is_empty());
+    // without a validator the partial file cannot be told apart from another build, it is downloaded again
+    let mut offset = match validator {
+        Some(_) => tokio::fs::metadata(&partial)
+            .await
+            .map(|m| m.len())
+            .unwrap_or(0),
+        None => 0,
+    };
+    let mut request = client.get(url);
+    if let (true, Some(validator)) = (offset > 0, validator.as_deref()) {
+        debug_println!("resume download {} from {}", url, offset);
+        request = request
+            .header(header::RANGE, format!("bytes={}-", offset))
+            .header(header::IF_RANGE, validator);
+    }
+    let mut response = tokio::select! {
+        response = request.send() => response?,
+        _ = cancel.cancelled() => return Err(DownloadError::Cancelled),
+    };
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        response = client.get(url).send().await?;
+        // the partial file is already complete
+        if response.content_length() == Some(offset) && response_validator(&response) == validator {
+            drop(response);
+            on_progress(offset, Some(offset));
+            return finish_download(&partial, destination, expected_sha256).await;
+        }
+        // not the same file, the response is the whole file
+        offset = 0;
+    }
+    if response.status() == StatusCode::PARTIAL_CONTENT
+        && content_range_start(&response) != Some(offset)
+    {
+        debug_println!("unexpected range of {}, download from the start", url);
+        response = client.get(url).send().await?;
+        offset = 0;
+    }
+    if !response.status().is_success() {
+        return Err(DownloadError::Status(response.status().as_u16()));
+    }
+    // a server without range support or a changed file sends the whole file again
+    let resumed = response.status() == StatusCode::PARTIAL_CONTENT && offset > 0;
+    let mut downloaded = if resumed { offset } else { 0 };
+    let total = response.content_length().map(|length| length + downloaded);
+    if !resumed {
+        match response_validator(&response) {
+            Some(validator) => tokio::fs::write(&validator_file, validator).await?,
+            None => {
+                let _ = tokio::fs::remove_file(&validator_file).await;
+            }
+        }
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&partial)
+        .await?;
+    on_progress(downloaded, total);
+    loop {
+        let chunk = tokio::select! {
+            chunk = response.chunk() => chunk?,
+            _ = cancel.cancelled() => {
+                file.flush().await?;
+                return Err(DownloadError::Cancelled);
+            }
+        };
+        match chunk {
+            Some(chunk) => {
+                file.write_all(&chunk).await?;
+                downloaded += chunk.len() as u64;
+                on_progress(downloaded, total);
+            }
+            None => break,
+        }
+    }
+    file.flush().await?;
+    drop(file);
+    finish_download(&partial, destination, expected_sha256).await
+}
+
+/**
+ * verify the partial file and move it to the destination, a mismatched file is removed
+ */
+async fn finish_download(
+    partial: &Path,
+    destination: &Path,
+    expected_sha256: Option<&str>,
+) -> Result<(), DownloadError> {
+    // the partial file is either moved or removed
+    let _ = tokio::fs::remove_file(validator_path(destination)).await;
+    if let Some(expected) = expected_sha256 {
+        let path = partial.to_path_buf();
+        let (actual, _) = tokio::task::spawn_blocking(move || hash_package(&path))
+            .await
+            .map_err(|e| DownloadError::Io(e.to_string()))??;
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            tokio::fs::remove_file(partial).await?;
+            return Err(DownloadError::ChecksumMismatch {
+                expected: expected.to_string(),
+                actual,
+            });
+        }
+    }
+    tokio::fs::rename(partial, destination).await?;
+    Ok(())
+}
+
+/**
+ * hand the package to the install pipeline, apps are installed into the device, runtimes are added
+ */
+async fn install_downloaded_package(
+    handle: &AppHandle,
+    request: &DownloadRequest,
+    record: &PackageRecord,
+    id: u64,
+) -> Result<(), String> {
+    match record.kind {
+        PackageKind::App => {
+            let device = get_target_device(handle, request.udid.clone())?;
+            // the install progress has the same id as the download
+            install::install_package(
+                handle,
+                &device,
+                Path::new(&record.path),
+                request.launch,
+                &id.to_string(),
+            )
+            .await?;
+        }
+        PackageKind::Runtime => {
+            let backend = get_simctl_backend(handle);
+            command::add_runtime(backend.as_ref(), &record.path, None).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn run_download(
+    handle: &AppHandle,
+    request: &DownloadRequest,
+    progress: &mut DownloadProgress,
+    cancel: &CancellationToken,
+) -> Result<(), String> {
+    let manager = handle.state::<DownloadManager>();
+    let _permit = tokio::select! {
+        permit = manager.permits.acquire() => permit.map_err(|e| e.to_string())?,
+        _ = cancel.cancelled() => return Err(DownloadError::Cancelled.into()),
+    };
+    let destination = get_downloads_dir().join(&progress.file_name);
+    let client = reqwest::Client::new();
+    progress.state = DownloadState::Downloading;
+    emit_progress(handle, progress);
+    let mut last_emit = Instant::now();
+    download_to_file(
+        &client,
+        &request.url,
+        &destination,
+        request.sha256.as_deref(),
+        cancel,
+        |downloaded, total| {
+            progress.downloaded = downloaded;
+            progress.total = total;
+            if last_emit.elapsed() >= DOWNLOAD_PROGRESS_INTERVAL {
+                last_emit = Instant::now();
+                emit_progress(handle, progress);
+            }
+        },
+    )
+    .await?;
+
+    progress.state = DownloadState::Verifying;
+    emit_progress(handle, progress);
+    let record = package::import_package(handle, &destination, Some(request.url.clone())).await?;
+    // the library keeps its own copy
+    if let Err(e) = tokio::fs::remove_file(&destination).await {
+        error!("remove download {:?} error: {}", destination, e);
+    }
+    progress.package = Some(record.clone());
+
+    if request.install {
+        progress.state = DownloadState::Installing;
+        emit_progress(handle, progress);
+        install_downloaded_package(handle, request, &record, progress.id).await?;
+    }
+    Ok(())
+}
+
+fn emit_progress(handle: &AppHandle, progress: &DownloadProgress) {
+    handle.state::<DownloadManager>().update(progress);
+    let _ = handle.emit_all("download_progress", progress);
+}
+
+/**
+ * queue the download and return its id, the progress is emitted by the `download_progress` event
+ */
+pub fn start_download(handle: &AppHandle, request: DownloadRequest) -> Result<u64, DownloadError> {
+    let file_name = request.file_name()?;
+    let manager = handle.state::<DownloadManager>();
+    let id = manager.next_id.fetch_add(1, Ordering::Relaxed);
+    let cancel = CancellationToken::new();
+    let mut progress = DownloadProgress {
+        id,
+        url: request.url.clone(),
+        file_name,
+        state: DownloadState::Queued,
+        downloaded: 0,
+        total: None,
+        package: None,
+        error: None,
+    };
+    manager.insert(progress.clone(), cancel.clone())?;
+    let _ = handle.emit_all("download_progress", &progress);
+    let app_handle = handle.clone();
+    tauri::async_runtime::spawn(async move {
+        debug_println!("download {} started: {}", id, request.url);
+        match run_download(&app_handle, &request, &mut progress, &cancel).await {
+            Ok(_) => progress.state = DownloadState::Completed,
+            Err(_) if cancel.is_cancelled() => progress.state = DownloadState::Cancelled,
+            Err(e) => {
+                error!("download {} error: {}", request.url, e);
+                progress.state = DownloadState::Failed;
+                progress.error = Some(e);
+            }
+        }
+        debug_println!("download {} finished: {:?}", id, progress.state);
+        emit_progress(&app_handle, &progress);
+    });
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::test_util::{TempDir, TestRequest, TestResponse, TestServer};
+
+    const BODY: &[u8] = b"costa simulator runtime, pretend this is a few gigabytes";
+    const ETAG: &str = "\"runtime-1\"";
+
+    /**
+     * serve the body with its ETag, a range is answered by 206 or 416 while `If-Range` matches the ETag
+     * `ranges`: false for a server which always sends the whole file
+     */
+    async fn file_server(body: &'static [u8], etag: &'static str, ranges: bool) -> TestServer {
+        TestServer::start(move |request: &TestRequest| {
+            let offset = request
+                .header("range")
+                .and_then(|range| range.strip_prefix("bytes="))
+                .and_then(|range| range.trim_end_matches('-').parse::<usize>().ok())
+                .filter(|_| ranges)
+                .filter(|_| request.header("if-range").unwrap_or(etag) == etag);
+            let response = match offset {
+                Some(offset) if offset >= body.len() => TestResponse::new(416, "")
+                    .with_header("content-range", format!("bytes */{}", body.len())),
+                Some(offset) => TestResponse::new(206, &body[offset..]).with_header(
+                    "content-range",
+                    format!("bytes {}-{}/{}", offset, body.len() - 1, body.len()),
+                ),
+                None => TestResponse::new(200, body),
+            };
+            Some(response.with_header("etag", etag))
+        })
+        .await
+    }
+
+    /** the range & If-Range headers of every request */
+    fn range_headers(server: &TestServer) -> Vec<(Option<String>, Option<String>)> {
+        server
+            .requests()
+            .iter()
+            .map(|request| {
+                let header = |name| request.header(name).map(|value| value.to_string());
+                (header("range"), header("if-range"))
+            })
+            .collect()
+    }
+
+    /** the destination in a fresh folder */
+    struct Downloads {
+        dir: TempDir,
+    }
+
+    impl Downloads {
+        fn new(name: &str) -> Self {
+            Self {
+                dir: TempDir::new(name),
+            }
+        }
+        fn destination(&self) -> PathBuf {
+            self.dir.join("Simulator_Runtime.dmg")
+        }
+        fn partial(&self) -> PathBuf {
+            partial_path(&self.destination())
+        }
+        fn validator(&self) -> PathBuf {
+            validator_path(&self.destination())
+        }
+        fn write_partial(&self, content: &[u8], validator: Option<&str>) {
+            std::fs::write(self.partial(), content).unwrap();
+            if let Some(validator) = validator {
+                std::fs::write(self.validator(), validator).unwrap();
+            }
+        }
+        async fn download(
+            &self,
+            server: &TestServer,
+            sha256: Option<&str>,
+        ) -> (Result<(), DownloadError>, Vec<(u64, Option<u64>)>) {
+            let mut progress = vec![];
+            let res = download_to_file(
+                &reqwest::Client::new(),
+                server.url(),
+                &self.destination(),
+                sha256,
+                &CancellationToken::new(),
+                |downloaded, total| progress.push((downloaded, total)),
+            )
+            .await;
+            (res, progress)
+        }
+    }
+
+    fn sha256(content: &[u8]) -> String {
+        Sha256::digest(content)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn downloads_the_whole_file() {
+        let server = file_server(BODY, ETAG, true).await;
+        let downloads = Downloads::new("download-full");
+        let (res, progress) = downloads
+            .download(&server, Some(&sha256(BODY).to_uppercase()))
+            .await;
+        res.unwrap();
+        assert_eq!(std::fs::read(downloads.destination()).unwrap(), BODY);
+        assert!(!downloads.partial().exists());
+        assert!(!downloads.validator().exists());
+        assert_eq!(range_headers(&server), vec![(None, None)]);
+        let total = BODY.len() as u64;
+        assert_eq!(progress.first(), Some(&(0, Some(total))));
+        assert_eq!(progress.last(), Some(&(total, Some(total))));
+    }
+
+    #[tokio::test]
+    async fn saves_the_validator_before_the_body() {
+        let server = file_server(BODY, ETAG, true).await;
+        let downloads = Downloads::new("download-validator");
+        let mut validators = vec![];
+        download_to_file(
+            &reqwest::Client::new(),
+            server.url(),
+            &downloads.destination(),
+            None,
+            &CancellationToken::new(),
+            |_, _| validators.push(std::fs::read_to_string(downloads.validator()).ok()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(validators.first(), Some(&Some(ETAG.to_string())));
+    }
+
+    #[tokio::test]
+    async fn resumes_the_partial_file() {
+        let server = file_server(BODY, ETAG, true).await;
+        let downloads = Downloads::new("download-resume");
+        downloads.write_partial(&BODY[..10], Some(ETAG));
+        let (res, progress) = downloads.download(&server, Some(&sha256(BODY))).await;
+        res.unwrap();
+        assert_eq!(std::fs::read(downloads.destination()).unwrap(), BODY);
+        assert!(!downloads.validator().exists());
+        assert_eq!(
+            range_headers(&server),
+            vec![(Some("bytes=10-".to_string()), Some(ETAG.to_string()))]
+        );
+        let total = BODY.len() as u64;
+        assert_eq!(progress.first(), Some(&(10, Some(total))));
+        assert_eq!(progress.last(), Some(&(total, Some(total))));
+    }
+
+    #[tokio::test]
+    async fn restarts_when_the_file_changed() {
+        // a "latest" url serves the next build, the partial file has the first bytes of the previous one
+        let server = file_server(
+            b"costa simulator runtime, the next build",
+            "\"runtime-2\"",
+            true,
+        )
+        .await;
+        let downloads = Downloads::new("download-changed");
+        downloads.write_partial(&BODY[..30], Some(ETAG));
+        let (res, progress) = downloads.download(&server, None).await;
+        res.unwrap();
+        assert_eq!(
+            std::fs::read(downloads.destination()).unwrap(),
+            b"costa simulator runtime, the next build"
+        );
+        assert_eq!(
+            range_headers(&server),
+            vec![(Some("bytes=30-".to_string()), Some(ETAG.to_string()))]
+        );
+        assert_eq!(progress.first(), Some(&(0, Some(39))));
+    }
+
+    #[tokio::test]
+    async fn restarts_when_the_range_starts_elsewhere() {
+        // a server answering every range from byte 5
+        let server = TestServer::start(|request: &TestRequest| {
+            let response = match request.header("range") {
+                Some(_) => TestResponse::new(206, &BODY[5..]).with_header(
+                    "content-range",
+                    format!("bytes 5-{}/{}", BODY.len() - 1, BODY.len()),
+                ),
+                None => TestResponse::new(200, BODY),
+            };
+            Some(response.with_header("etag", ETAG))
+        })
+        .await;
+        let downloads = Downloads::new("download-wrong-range");
+        downloads.write_partial(&BODY[..10], Some(ETAG));
+        let (res, _) = downloads.download(&server, Some(&sha256(BODY))).await;
+        res.unwrap();
+        assert_eq!(std::fs::read(downloads.destination()).unwrap(), BODY);
+        assert_eq!(
+            range_headers(&server),
+            vec![
+                (Some("bytes=10-".to_string()), Some(ETAG.to_string())),
+                (None, None)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn restarts_a_partial_file_without_validator() {
+        let server = file_server(BODY, ETAG, true).await;
+        let downloads = Downloads::new("download-no-validator");
+        downloads.write_partial(b"stale bytes", None);
+        let (res, _) = downloads.download(&server, None).await;
+        res.unwrap();
+        assert_eq!(std::fs::read(downloads.destination()).unwrap(), BODY);
+        assert_eq!(range_headers(&server), vec![(None, None)]);
+    }
+
+    #[tokio::test]
+    async fn finishes_a_complete_partial_file() {
+        let server = file_server(BODY, ETAG, true).await;
+        let downloads = Downloads::new("download-complete");
+        downloads.write_partial(BODY, Some(ETAG));
+        let (res, progress) = downloads.download(&server, Some(&sha256(BODY))).await;
+        res.unwrap();
+        assert_eq!(std::fs::read(downloads.destination()).unwrap(), BODY);
+        assert!(!downloads.partial().exists());
+        // 416 for the range, then the length & the ETag are compared with the partial file
+        let offset = format!("bytes={}-", BODY.len());
+        assert_eq!(
+            range_headers(&server),
+            vec![(Some(offset), Some(ETAG.to_string())), (None, None)]
+        );
+        let total = BODY.len() as u64;
+        assert_eq!(progress, vec![(total, Some(total))]);
+    }
+
+    #[tokio::test]
+    async fn restarts_when_the_server_ignores_the_range() {
+        let server = file_server(BODY, ETAG, false).await;
+        let downloads = Downloads::new("download-no-range");
+        downloads.write_partial(b"stale bytes of another build", Some(ETAG));
+        let (res, progress) = downloads.download(&server, None).await;
+        res.unwrap();
+        // the partial file is truncated instead of appended to
+        assert_eq!(std::fs::read(downloads.destination()).unwrap(), BODY);
+        assert_eq!(
+            range_headers(&server),
+            vec![(Some("bytes=28-".to_string()), Some(ETAG.to_string()))]
+        );
+        assert_eq!(progress.first(), Some(&(0, Some(BODY.len() as u64))));
+    }
+
+    #[tokio::test]
+    async fn removes_the_partial_file_on_checksum_mismatch() {
+        let server = file_server(BODY, ETAG, true).await;
+        let downloads = Downloads::new("download-mismatch");
+        let expected = sha256(b"another file");
+        let (res, _) = downloads.download(&server, Some(&expected)).await;
+        assert_eq!(
+            res,
+            Err(DownloadError::ChecksumMismatch {
+                expected,
+                actual: sha256(BODY),
+            })
+        );
+        assert!(!downloads.partial().exists());
+        assert!(!downloads.validator().exists());
+        assert!(!downloads.destination().exists());
+    }
+
+    #[tokio::test]
+    async fn keeps_the_partial_file_when_cancelled() {
+        let server = file_server(BODY, ETAG, true).await;
+        let downloads = Downloads::new("download-cancel");
+        downloads.write_partial(&BODY[..10], Some(ETAG));
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let res = download_to_file(
+            &reqwest::Client::new(),
+            server.url(),
+            &downloads.destination(),
+            None,
+            &cancel,
+            |_, _| {},
+        )
+        .await;
+        assert_eq!(res, Err(DownloadError::Cancelled));
+        assert_eq!(std::fs::read(downloads.partial()).unwrap(), &BODY[..10]);
+        assert_eq!(
+            std::fs::read_to_string(downloads.validator()).unwrap(),
+            ETAG
+        );
+    }
+
+    #[test]
+    fn file_names_stay_in_the_downloads_folder() {
+        let request = |url: &str, file_name: Option<&str>| DownloadRequest {
+            url: url.to_string(),
+            file_name: file_name.map(|name| name.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            request("https://example.com/builds/Demo.ipa?token=1", None).file_name(),
+            Ok("Demo.ipa".to_string())
+        );
+        assert_eq!(
+            request("https://example.com/", None).file_name(),
+            Ok("example.com".to_string())
+        );
+        assert_eq!(
+            request("https://example.com/a", Some("Demo 1.2.ipa")).file_name(),
+            Ok("Demo 1.2.ipa".to_string())
+        );
+        for name in ["../.zshrc", "a/b.ipa", "..", ".", "", " ", "a\\b.ipa"] {
+            assert_eq!(
+                request("https://example.com/a", Some(name)).file_name(),
+                Err(DownloadError::InvalidFileName(name.to_string()))
+            );
+        }
+        assert!(request("https://example.com/a/..", None)
+            .file_name()
+            .is_err());
+    }
+
+    #[test]
+    fn refuses_a_running_download_of_the_same_file() {
+        let manager = DownloadManager::default();
+        let progress = |id: u64, file_name: &str| DownloadProgress {
+            id,
+            url: format!("https://example.com/{}", file_name),
+            file_name: file_name.to_string(),
+            state: DownloadState::Queued,
+            downloaded: 0,
+            total: None,
+            package: None,
+            error: None,
+        };
+        let token = CancellationToken::new;
+        manager.insert(progress(1, "Demo.ipa"), token()).unwrap();
+        manager.insert(progress(2, "Other.ipa"), token()).unwrap();
+        assert_eq!(
+            manager.insert(progress(3, "Demo.ipa"), token()),
+            Err(DownloadError::AlreadyDownloading("Demo.ipa".to_string()))
+        );
+        // a finished download does not block a new one
+        let mut cancelled = progress(1, "Demo.ipa");
+        cancelled.state = DownloadState::Cancelled;
+        manager.update(&cancelled);
+        manager.insert(progress(3, "Demo.ipa"), token()).unwrap();
+        assert_eq!(manager.list().len(), 3);
+    }
+}
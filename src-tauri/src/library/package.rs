@@ -0,0 +1,825 @@
+/**
+ * This module manages the package library in `~/.costa/library`, app packages (.app, .app.zip, .ipa)
+ * and simulator runtimes (.dmg) are imported from the watch folders or the downloads and indexed in the store.
+ * Packages are deduplicated by sha256, and old packages are cleaned by the retention settings.
+ */
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use debug_print::debug_println;
+use log::error;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    path::{get_library_dir, get_user_home},
+    preference::get_preferences,
+    simulator::install::{read_app_bundle_info, read_archive_bundle_info},
+    sotre::{get_tauri_store, update_tauri_store, StoreKey},
+};
+
+/** files modified within this duration may still be written, e.g. by the browser */
+pub const WATCH_SETTLE_DURATION: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageKind {
+    /** .app, .app.zip or .ipa */
+    App,
+    /** Simulator_Runtime.dmg */
+    Runtime,
+}
+
+impl PackageKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".app") || name.ends_with(".app.zip") || name.ends_with(".ipa") {
+            Some(PackageKind::App)
+        } else if name.contains("simulator_runtime") && name.ends_with(".dmg") {
+            Some(PackageKind::Runtime)
+        } else {
+            None
+        }
+    }
+}
+
+/**
+ * A package indexed in the library
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PackageRecord {
+    /** sha256 of the package, a .app folder is hashed by its files */
+    pub sha256: String,
+    pub kind: PackageKind,
+    /** CFBundleDisplayName of apps, file name of runtimes */
+    pub name: String,
+    pub bundle_id: Option<String>,
+    /** CFBundleShortVersionString of apps, parsed from the file name of runtimes */
+    pub version: Option<String>,
+    pub build: Option<String>,
+    pub min_os_version: Option<String>,
+    /** url the package was downloaded from */
+    pub source_url: Option<String>,
+    /** paths the package was imported from */
+    pub source_paths: Vec<String>,
+    /** path of the package in the library */
+    pub path: String,
+    pub size: u64,
+    /** unix timestamp in milliseconds */
+    pub added_at: u128,
+}
+
+/**
+ * Settings of the library, saved in the preferences
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct LibrarySettings {
+    /** folders scanned for new packages, `~` is the user home */
+    pub watch_folders: Vec<String>,
+    /** keep the newest n packages of each bundle id (platform for runtimes), None to keep all */
+    pub keep_versions: Option<usize>,
+    /** remove packages added more than n days ago, None to keep all */
+    pub max_age_days: Option<u64>,
+}
+
+impl Default for LibrarySettings {
+    fn default() -> Self {
+        Self {
+            // the packages used to be picked from the downloads
+            watch_folders: vec!["~/Downloads".to_string()],
+            keep_versions: Some(3),
+            max_age_days: None,
+        }
+    }
+}
+
+/**
+ * The source of a removed package, the watch folders do not import it again until it is replaced
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct IgnoredSource {
+    pub path: String,
+    pub size: u64,
+    /** unix timestamp in milliseconds of the last modification */
+    pub modified: u128,
+}
+
+impl IgnoredSource {
+    /** None if the source is gone */
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = path.metadata().ok()?;
+        Some(Self {
+            path: path.to_string_lossy().to_string(),
+            size: metadata.len(),
+            modified: modified_millis(&metadata),
+        })
+    }
+}
+
+/**
+ * The index saved in the store
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct PackageIndex {
+    pub packages: Vec<PackageRecord>,
+    /** sources of the removed packages */
+    pub ignored_sources: Vec<IgnoredSource>,
+}
+
+/**
+ * The scan lock managed by tauri, the webview polls the list while a large runtime may be importing
+ */
+#[derive(Default)]
+pub struct PackageLibrary(tokio::sync::Mutex<()>);
+
+fn now_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+}
+
+fn modified_millis(metadata: &std::fs::Metadata) -> u128 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => get_user_home().join(rest),
+        None if path == "~" => get_user_home(),
+        None => PathBuf::from(path),
+    }
+}
+
+pub fn get_package_index(handle: &AppHandle) -> PackageIndex {
+    get_tauri_store(handle.clone())
+        .and_then(|store| store.package_library)
+        .unwrap_or_default()
+}
+
+pub fn set_package_index(handle: &AppHandle, index: &PackageIndex) -> Result<(), String> {
+    update_tauri_store(handle.clone(), StoreKey::PackageLibrary, json!(index))
+        .map_err(|e| e.to_string())
+}
+
+/**
+ * get the packages of the kind, newest first
+ */
+pub fn get_packages(handle: &AppHandle, kind: PackageKind) -> Vec<PackageRecord> {
+    let mut packages = get_package_index(handle)
+        .packages
+        .into_iter()
+        .filter(|p| p.kind == kind)
+        .collect::<Vec<_>>();
+    packages.sort_by_key(|p| std::cmp::Reverse(p.added_at));
+    packages
+}
+
+fn hash_file(path: &Path, hasher: &mut Sha256) -> std::io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut buffer = vec![0; 1024 * 1024];
+    let mut size = 0;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(size);
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() && !path.is_symlink() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/**
+ * sha256 & size of a file, or of the relative paths & contents of the files in a folder
+ */
+pub fn hash_package(path: &Path) -> std::io::Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let size = if path.is_dir() {
+        let mut files = vec![];
+        collect_files(path, &mut files)?;
+        files.sort();
+        let mut size = 0;
+        for file in files {
+            hasher.update(
+                file.strip_prefix(path)
+                    .unwrap_or(&file)
+                    .to_string_lossy()
+                    .as_bytes(),
+            );
+            if file.is_symlink() {
+                hasher.update(std::fs::read_link(&file)?.to_string_lossy().as_bytes());
+            } else {
+                size += hash_file(&file, &mut hasher)?;
+            }
+        }
+        size
+    } else {
+        hash_file(path, &mut hasher)?
+    };
+    let sha256 = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    Ok((sha256, size))
+}
+
+fn copy_dir(source: &Path, destination: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(destination)?;
+    for entry in std::fs::read_dir(source)? {
+        let path = entry?.path();
+        let target = destination.join(path.file_name().unwrap_or_default());
+        if path.is_symlink() {
+            std::os::unix::fs::symlink(std::fs::read_link(&path)?, &target)?;
+        } else if path.is_dir() {
+            copy_dir(&path, &target)?;
+        } else {
+            std::fs::copy(&path, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/**
+ * copy the package into the library, files are hard linked if possible, runtimes are several GB
+ */
+fn copy_package(source: &Path, destination: &Path) -> std::io::Result<()> {
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if source.is_dir() {
+        copy_dir(source, destination)
+    } else if std::fs::hard_link(source, destination).is_ok() {
+        Ok(())
+    } else {
+        std::fs::copy(source, destination).map(|_| ())
+    }
+}
+
+/**
+ * the first version-like part of the file name, e.g. "iOS_17.5_Simulator_Runtime.dmg" -> "17.5"
+ */
+fn version_from_file_name(name: &str) -> Option<String> {
+    name.split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .map(|part| part.trim_matches('.'))
+        .find(|part| part.contains('.') && part.starts_with(|c: char| c.is_ascii_digit()))
+        .map(|part| part.to_string())
+}
+
+/**
+ * the platform of a runtime, its file name without the version
+ * e.g. "iOS_17.5_Simulator_Runtime.dmg" -> "ios__simulator_runtime.dmg"
+ */
+fn runtime_platform(name: &str) -> String {
+    let name = name.to_lowercase();
+    match version_from_file_name(&name) {
+        Some(version) => name.replacen(&version, "", 1),
+        None => name,
+    }
+}
+
+/**
+ * build the record of a package in the library, apps are inspected by the Info.plist
+ */
+fn inspect_package(path: &Path, kind: PackageKind, sha256: String, size: u64) -> PackageRecord {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut record = PackageRecord {
+        sha256,
+        kind,
+        name: file_name.clone(),
+        bundle_id: None,
+        version: None,
+        build: None,
+        min_os_version: None,
+        source_url: None,
+        source_paths: vec![],
+        path: path.to_string_lossy().to_string(),
+        size,
+        added_at: now_millis(),
+    };
+    match kind {
+        PackageKind::App => {
+            let info = if path.is_dir() {
+                read_app_bundle_info(path)
+            } else {
+                read_archive_bundle_info(path)
+            };
+            match info {
+                Ok(info) => {
+                    record.name = info.name;
+                    record.bundle_id = Some(info.bundle_id);
+                    record.version = info.version;
+                    record.build = info.build;
+                    record.min_os_version = info.min_os_version;
+                }
+                Err(e) => error!("inspect package {} error: {}", file_name, e),
+            }
+        }
+        PackageKind::Runtime => record.version = version_from_file_name(&file_name),
+    }
+    record
+}
+
+/**
+ * hash & copy the package into the library, return the record and whether it is new
+ * a package with the same sha256 is not copied again, its missing source is filled instead
+ */
+fn import_into_library(
+    index: &mut PackageIndex,
+    library: &Path,
+    source: &Path,
+    source_url: Option<String>,
+) -> Result<(PackageRecord, bool), String> {
+    let kind = PackageKind::from_path(source)
+        .ok_or_else(|| format!("unsupported package: {}", source.to_string_lossy()))?;
+    let (sha256, size) = hash_package(source).map_err(|e| e.to_string())?;
+    let source_path = source.to_string_lossy().to_string();
+    index.ignored_sources.retain(|s| s.path != source_path);
+    if let Some(existing) = index.packages.iter_mut().find(|p| p.sha256 == sha256) {
+        debug_println!("package already in library: {}", existing.path);
+        if existing.source_url.is_none() {
+            existing.source_url = source_url;
+        }
+        if !existing.source_paths.contains(&source_path) {
+            existing.source_paths.push(source_path);
+        }
+        return Ok((existing.clone(), false));
+    }
+    let file_name = source.file_name().unwrap_or_default();
+    // one folder per package, packages of different versions often have the same file name
+    let destination = library.join(&sha256[..16]).join(file_name);
+    copy_package(source, &destination).map_err(|e| e.to_string())?;
+    let mut record = inspect_package(&destination, kind, sha256, size);
+    record.source_url = source_url;
+    record.source_paths = vec![source_path];
+    debug_println!("package imported: {:?}", record);
+    index.packages.push(record.clone());
+    Ok((record, true))
+}
+
+/**
+ * import a package into the library
+ */
+pub async fn import_package(
+    handle: &AppHandle,
+    source: &Path,
+    source_url: Option<String>,
+) -> Result<PackageRecord, String> {
+    let library = handle.state::<PackageLibrary>();
+    let _lock = library.0.lock().await;
+    let mut index = get_package_index(handle);
+    let source = source.to_path_buf();
+    let (record, index) = tokio::task::spawn_blocking(move || {
+        import_into_library(&mut index, &get_library_dir(), &source, source_url)
+            .map(|(record, _)| (record, index))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    set_package_index(handle, &index)?;
+    Ok(record)
+}
+
+/**
+ * check the file of the watch folder is already imported or removed, so it is not hashed again
+ * a file replaced by a download of the same name has another size, and a new modification time
+ */
+fn is_known_source(index: &PackageIndex, path: &Path) -> bool {
+    let source_path = path.to_string_lossy().to_string();
+    if let Some(source) = IgnoredSource::of(path) {
+        if index.ignored_sources.contains(&source) {
+            return true;
+        }
+    }
+    let size = path.metadata().map(|m| m.len()).unwrap_or_default();
+    index
+        .packages
+        .iter()
+        .any(|p| p.source_paths.contains(&source_path) && (path.is_dir() || p.size == size))
+}
+
+fn is_settled(path: &Path) -> bool {
+    path.metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .map(|elapsed| elapsed >= WATCH_SETTLE_DURATION)
+        .unwrap_or(false)
+}
+
+/**
+ * import the new packages of the watch folders, return the imported records
+ * a scan is skipped if another one is running, files already imported from the same path are skipped
+ */
+pub async fn scan_watch_folders(handle: &AppHandle) -> Result<Vec<PackageRecord>, String> {
+    let library = handle.state::<PackageLibrary>();
+    let lock = match library.0.try_lock() {
+        Ok(lock) => lock,
+        Err(_) => return Ok(vec![]),
+    };
+    let settings = get_preferences(handle).library;
+    let saved = get_package_index(handle);
+    let index = saved.clone();
+    let (imported, index) = tokio::task::spawn_blocking(move || {
+        let mut index = index;
+        let mut imported = vec![];
+        let library = get_library_dir();
+        for folder in settings.watch_folders.iter().map(|f| expand_home(f)) {
+            let entries = match std::fs::read_dir(&folder) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    debug_println!("read watch folder {:?} error: {}", folder, e);
+                    continue;
+                }
+            };
+            for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+                if PackageKind::from_path(&path).is_none()
+                    || !is_settled(&path)
+                    || is_known_source(&index, &path)
+                {
+                    continue;
+                }
+                match import_into_library(&mut index, &library, &path, None) {
+                    Ok((record, true)) => imported.push(record),
+                    Ok(_) => {}
+                    Err(e) => error!("import package {:?} error: {}", path, e),
+                }
+            }
+        }
+        (imported, index)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    // a duplicate adds its source path & is no longer ignored, even if nothing is imported
+    if index != saved {
+        set_package_index(handle, &index)?;
+    }
+    if imported.is_empty() {
+        return Ok(imported);
+    }
+    drop(lock);
+    // the new packages may expire the older versions
+    clean_library(handle).await?;
+    Ok(imported)
+}
+
+/**
+ * the packages removed by the retention settings
+ */
+pub fn expired_packages(
+    packages: &[PackageRecord],
+    settings: &LibrarySettings,
+) -> Vec<PackageRecord> {
+    let now = now_millis();
+    let mut expired: Vec<PackageRecord> = vec![];
+    if let Some(days) = settings.max_age_days {
+        let max_age = Duration::from_secs(days * 24 * 60 * 60).as_millis();
+        expired.extend(
+            packages
+                .iter()
+                .filter(|p| now.saturating_sub(p.added_at) > max_age)
+                .cloned(),
+        );
+    }
+    if let Some(keep) = settings.keep_versions {
+        let mut sorted = packages.to_vec();
+        sorted.sort_by_key(|p| std::cmp::Reverse(p.added_at));
+        let mut seen: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+        for record in sorted {
+            let key = match (record.kind, &record.bundle_id) {
+                (PackageKind::Runtime, _) => runtime_platform(&record.name),
+                (PackageKind::App, Some(bundle_id)) => bundle_id.clone(),
+                (PackageKind::App, None) => record.name.clone(),
+            };
+            let count = seen.entry(key).or_insert(0);
+            *count += 1;
+            if *count > keep && !expired.iter().any(|p| p.sha256 == record.sha256) {
+                expired.push(record);
+            }
+        }
+    }
+    expired
+}
+
+/**
+ * remove the files of the package, its sources are ignored by the next scans until they are replaced
+ */
+fn remove_from_library(index: &mut PackageIndex, library: &Path, record: &PackageRecord) {
+    index.packages.retain(|p| p.sha256 != record.sha256);
+    for source in record.source_paths.iter().map(Path::new) {
+        if let Some(source) = IgnoredSource::of(source) {
+            index.ignored_sources.retain(|s| s.path != source.path);
+            index.ignored_sources.push(source);
+        }
+    }
+    let path = Path::new(&record.path);
+    // the package folder is named by the sha256
+    let folder = path.parent().filter(|p| p.starts_with(library));
+    let res = match folder {
+        Some(folder) => std::fs::remove_dir_all(folder),
+        None => Ok(()),
+    };
+    if let Err(e) = res {
+        error!("remove package {} error: {}", record.path, e);
+    }
+}
+
+/**
+ * remove a package from the library & the index
+ */
+pub async fn remove_package(handle: &AppHandle, sha256: &str) -> Result<PackageRecord, String> {
+    let library = handle.state::<PackageLibrary>();
+    let _lock = library.0.lock().await;
+    let mut index = get_package_index(handle);
+    let record = index
+        .packages
+        .iter()
+        .find(|p| p.sha256 == sha256)
+        .cloned()
+        .ok_or_else(|| format!("package not found: {}", sha256))?;
+    remove_from_library(&mut index, &get_library_dir(), &record);
+    set_package_index(handle, &index)?;
+    Ok(record)
+}
+
+/**
+ * remove the packages expired by the retention settings, return the removed records
+ */
+pub async fn clean_library(handle: &AppHandle) -> Result<Vec<PackageRecord>, String> {
+    let library = handle.state::<PackageLibrary>();
+    let _lock = library.0.lock().await;
+    let settings = get_preferences(handle).library;
+    let mut index = get_package_index(handle);
+    let expired = expired_packages(&index.packages, &settings);
+    if expired.is_empty() {
+        return Ok(expired);
+    }
+    let library = get_library_dir();
+    for record in &expired {
+        debug_println!("package expired: {}", record.path);
+        remove_from_library(&mut index, &library, record);
+    }
+    set_package_index(handle, &index)?;
+    Ok(expired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    const DAY_MILLIS: u128 = 24 * 60 * 60 * 1000;
+
+    fn record(
+        sha256: &str,
+        kind: PackageKind,
+        name: &str,
+        bundle_id: Option<&str>,
+    ) -> PackageRecord {
+        PackageRecord {
+            sha256: sha256.to_string(),
+            kind,
+            name: name.to_string(),
+            bundle_id: bundle_id.map(|id| id.to_string()),
+            version: None,
+            build: None,
+            min_os_version: None,
+            source_url: None,
+            source_paths: vec![],
+            path: format!("/library/{}/{}", sha256, name),
+            size: 1,
+            added_at: now_millis(),
+        }
+    }
+
+    /** records added n and a half days ago, the first one is the newest */
+    fn by_age(mut records: Vec<PackageRecord>) -> Vec<PackageRecord> {
+        let now = now_millis();
+        for (age, record) in records.iter_mut().enumerate() {
+            record.added_at = now - age as u128 * DAY_MILLIS - DAY_MILLIS / 2;
+        }
+        records
+    }
+
+    fn sha256s(records: &[PackageRecord]) -> Vec<&str> {
+        records.iter().map(|r| r.sha256.as_str()).collect()
+    }
+
+    #[test]
+    fn kind_of_the_path() {
+        let kind = |path: &str| PackageKind::from_path(Path::new(path));
+        assert_eq!(kind("/Downloads/Demo.app"), Some(PackageKind::App));
+        assert_eq!(kind("/Downloads/Demo.APP.zip"), Some(PackageKind::App));
+        assert_eq!(kind("/Downloads/Demo 1.2.ipa"), Some(PackageKind::App));
+        assert_eq!(
+            kind("/Downloads/iOS_17.5_Simulator_Runtime.dmg"),
+            Some(PackageKind::Runtime)
+        );
+        assert_eq!(kind("/Downloads/Xcode_16.dmg"), None);
+        assert_eq!(kind("/Downloads/Demo.zip"), None);
+        assert_eq!(kind("/"), None);
+    }
+
+    #[test]
+    fn version_of_the_file_name() {
+        let version = |name| version_from_file_name(name);
+        assert_eq!(
+            version("iOS_17.5_Simulator_Runtime.dmg"),
+            Some("17.5".to_string())
+        );
+        assert_eq!(
+            version("watchOS_10.5.1_Simulator_Runtime.dmg"),
+            Some("10.5.1".to_string())
+        );
+        assert_eq!(version("iOS_18_Simulator_Runtime.dmg"), None);
+        assert_eq!(version("Simulator_Runtime.dmg"), None);
+        assert_eq!(
+            runtime_platform("iOS_17.5_Simulator_Runtime.dmg"),
+            runtime_platform("iOS_18.0_Simulator_Runtime.dmg")
+        );
+        assert_ne!(
+            runtime_platform("iOS_17.5_Simulator_Runtime.dmg"),
+            runtime_platform("watchOS_10.5_Simulator_Runtime.dmg")
+        );
+    }
+
+    #[test]
+    fn keeps_the_newest_versions_of_each_app_and_platform() {
+        let app = |sha256, bundle_id| record(sha256, PackageKind::App, "Demo", Some(bundle_id));
+        let runtime = |sha256, name| record(sha256, PackageKind::Runtime, name, None);
+        let packages = by_age(vec![
+            app("demo-3", "com.costa.demo"),
+            runtime("ios-18", "iOS_18.0_Simulator_Runtime.dmg"),
+            app("other-1", "com.costa.other"),
+            app("demo-2", "com.costa.demo"),
+            runtime("ios-17", "iOS_17.5_Simulator_Runtime.dmg"),
+            runtime("watch-10", "watchOS_10.5_Simulator_Runtime.dmg"),
+            app("demo-1", "com.costa.demo"),
+            runtime("ios-16", "iOS_16.4_Simulator_Runtime.dmg"),
+        ]);
+        let settings = LibrarySettings {
+            keep_versions: Some(2),
+            max_age_days: None,
+            ..Default::default()
+        };
+        assert_eq!(
+            sha256s(&expired_packages(&packages, &settings)),
+            vec!["demo-1", "ios-16"]
+        );
+        let settings = LibrarySettings {
+            keep_versions: None,
+            ..settings
+        };
+        assert!(expired_packages(&packages, &settings).is_empty());
+    }
+
+    #[test]
+    fn expires_old_packages_once() {
+        let packages = by_age(vec![
+            record("demo-3", PackageKind::App, "Demo", Some("com.costa.demo")),
+            record("demo-2", PackageKind::App, "Demo", Some("com.costa.demo")),
+            record("demo-1", PackageKind::App, "Demo", Some("com.costa.demo")),
+        ]);
+        let settings = LibrarySettings {
+            keep_versions: Some(2),
+            max_age_days: Some(2),
+            ..Default::default()
+        };
+        // demo-1 is too old & one version too many, it is removed once
+        assert_eq!(
+            sha256s(&expired_packages(&packages, &settings)),
+            vec!["demo-1"]
+        );
+        let settings = LibrarySettings {
+            keep_versions: Some(1),
+            ..settings
+        };
+        assert_eq!(
+            sha256s(&expired_packages(&packages, &settings)),
+            vec!["demo-1", "demo-2"]
+        );
+    }
+
+    #[test]
+    fn hashes_files_and_folders() {
+        let dir = TempDir::new("package-hash");
+        std::fs::write(dir.join("file.ipa"), "content").unwrap();
+        let (sha256, size) = hash_package(&dir.join("file.ipa")).unwrap();
+        assert_eq!(
+            sha256,
+            "ed7002b439e9ac845f22357d822bac1444730fbdb6016d3ec9432297b9ec9f73"
+        );
+        assert_eq!(size, 7);
+
+        let app = dir.join("Demo.app");
+        std::fs::create_dir_all(app.join("Frameworks")).unwrap();
+        std::fs::write(app.join("Demo"), "binary").unwrap();
+        std::fs::write(app.join("Frameworks/Kit"), "kit").unwrap();
+        std::os::unix::fs::symlink("Frameworks/Kit", app.join("Kit")).unwrap();
+        let (folder_sha256, size) = hash_package(&app).unwrap();
+        // the links are hashed by their target, not counted in the size
+        assert_eq!(size, 9);
+        assert_ne!(folder_sha256, sha256);
+
+        // the same files in another folder have the same hash
+        let copy = dir.join("Copy/Demo.app");
+        copy_dir(&app, &copy).unwrap();
+        assert_eq!(hash_package(&copy).unwrap().0, folder_sha256);
+        // a renamed file changes it
+        std::fs::rename(copy.join("Demo"), copy.join("Demo2")).unwrap();
+        assert_ne!(hash_package(&copy).unwrap().0, folder_sha256);
+    }
+
+    #[test]
+    fn imports_a_duplicate_once() {
+        let dir = TempDir::new("package-import");
+        let library = dir.join("library");
+        let first = dir.join("Downloads/Demo.ipa");
+        let second = dir.join("Desktop/Demo copy.ipa");
+        std::fs::create_dir_all(first.parent().unwrap()).unwrap();
+        std::fs::create_dir_all(second.parent().unwrap()).unwrap();
+        std::fs::write(&first, "not a zip").unwrap();
+        std::fs::write(&second, "not a zip").unwrap();
+        let mut index = PackageIndex::default();
+
+        let (record, new) = import_into_library(&mut index, &library, &first, None).unwrap();
+        assert!(new);
+        assert_eq!(record.kind, PackageKind::App);
+        assert!(Path::new(&record.path).starts_with(&library));
+        assert_eq!(std::fs::read_to_string(&record.path).unwrap(), "not a zip");
+
+        let url = Some("https://example.com/Demo.ipa".to_string());
+        let (duplicate, new) = import_into_library(&mut index, &library, &second, url).unwrap();
+        assert!(!new);
+        assert_eq!(duplicate.path, record.path);
+        assert_eq!(
+            duplicate.source_url.as_deref(),
+            Some("https://example.com/Demo.ipa")
+        );
+        assert_eq!(index.packages.len(), 1);
+        assert_eq!(
+            index.packages[0].source_paths,
+            vec![
+                first.to_string_lossy().to_string(),
+                second.to_string_lossy().to_string()
+            ]
+        );
+        assert_eq!(std::fs::read_dir(&library).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn imports_a_removed_source_again_once_replaced() {
+        let dir = TempDir::new("package-ignored");
+        let library = dir.join("library");
+        let source = dir.join("Demo.ipa");
+        std::fs::write(&source, "build 1").unwrap();
+        let mut index = PackageIndex::default();
+        let (record, _) = import_into_library(&mut index, &library, &source, None).unwrap();
+        assert!(is_known_source(&index, &source));
+
+        remove_from_library(&mut index, &library, &record);
+        assert!(index.packages.is_empty());
+        assert!(!Path::new(&record.path).exists());
+        assert!(is_known_source(&index, &source));
+
+        // the next build of the same size, downloaded later
+        std::fs::write(&source, "build 2").unwrap();
+        let file = File::options().write(true).open(&source).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        assert!(!is_known_source(&index, &source));
+
+        let (record, new) = import_into_library(&mut index, &library, &source, None).unwrap();
+        assert!(new);
+        assert_eq!(std::fs::read_to_string(&record.path).unwrap(), "build 2");
+        assert!(index.ignored_sources.is_empty());
+    }
+}
@@ -1,35 +1,61 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod catalog;
 mod clipboard;
 mod command;
 mod constant;
 mod file;
 mod host;
+mod library;
 mod path;
+mod preference;
 mod simulator;
 mod sotre;
+#[cfg(test)]
+mod test_util;
 mod tick;
 mod tray;
 mod window;
 mod constant_local;
 
+use catalog::CatalogState;
 use command::handler::assamble_handler;
 use file::check_file_if_exists;
+use host::{mdns::MdnsBrowser, scanner::HostScanner};
+use library::{download::DownloadManager, package::PackageLibrary};
 use log::{debug, LevelFilter};
 use path::get_sotre_path;
+use simulator::{
+    appearance::DisplaySettingsCache, backend::SimctlState, capture::RecordingSessions,
+    log_stream::LogStreams, pasteboard::PasteboardSync, runner::CancelRegistry,
+    status_bar::StatusBarOverrides,
+};
 use sotre::{set_tauri_store, setup_tauri_store};
 use tauri::Manager;
 use tauri_plugin_log::LogTarget;
 use tick::tick;
 use tray::event::on_system_tray_event;
 use tray::tray::{init_system_tray, init_system_tray_menu};
+use window::event::on_window_event;
 
 fn main() {
     // init app
     let mut app_builder = tauri::Builder::default();
     app_builder = assamble_handler(app_builder); // assemble command handler
     let app = app_builder
+        .manage(SimctlState::default()) // simctl backend
+        .manage(CancelRegistry::default()) // cancellable simctl operations
+        .manage(RecordingSessions::default()) // screen recording sessions
+        .manage(LogStreams::default()) // device log streams
+        .manage(StatusBarOverrides::default()) // status bar presets applied from the webview
+        .manage(DisplaySettingsCache::default()) // appearance settings read back from the devices
+        .manage(PasteboardSync::default()) // clipboard sync with the booted device
+        .manage(PackageLibrary::default()) // package library scan lock
+        .manage(DownloadManager::default()) // app & runtime downloads
+        .manage(CatalogState::default()) // app catalog
+        .manage(HostScanner::default()) // probe history of the debug host discovery
+        .manage(MdnsBrowser::default()) // debug hosts announced by mDNS
         .plugin(tauri_plugin_store::Builder::default().build()) // store plugin
         .plugin(tauri_plugin_clipboard::init()) // clipboard plugin
         .plugin(
@@ -48,12 +74,15 @@ fn main() {
             }
             // setup tauri store
             setup_tauri_store(app.handle().clone());
+            // load the app catalog, the tray lists its apps
+            let _ = catalog::load_app_catalog(&app.handle());
             // init tray menu
-            let menu = init_system_tray_menu(Some(&app), Some(app.handle().clone()));
+            let menu = tauri::async_runtime::block_on(init_system_tray_menu(app.handle()));
             let _ = app.tray_handle().set_menu(menu);
             Ok(())
         })
         .on_system_tray_event(on_system_tray_event) // listen system tray event
+        .on_window_event(on_window_event) // listen window event, e.g. file drop
         .build(tauri::generate_context!())
         .expect("error while running tauri application");
     // start tick, to update system tray menu in an infinity loop
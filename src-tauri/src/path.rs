@@ -3,7 +3,10 @@ use std::path::{Path, PathBuf};
 use debug_print::debug_println;
 use tauri::api::path;
 
-use crate::constant::{APP_NAME, STORE_PATH};
+use crate::constant::{
+  APP_NAME, CAPTURES_PATH, CATALOG_FILE, DOWNLOADS_PATH, FIXTURES_PATH, INSTALL_CACHE_PATH,
+  LIBRARY_PATH, LOGS_PATH, STORE_PATH,
+};
 
 pub fn get_user_home() -> PathBuf {
   path::home_dir().unwrap()
@@ -18,3 +21,31 @@ pub fn get_app_data_dir() -> PathBuf {
 pub fn get_sotre_path() -> PathBuf {
   get_app_data_dir().join(STORE_PATH)
 }
+
+pub fn get_captures_dir() -> PathBuf {
+  get_app_data_dir().join(CAPTURES_PATH)
+}
+
+pub fn get_logs_dir() -> PathBuf {
+  get_app_data_dir().join(LOGS_PATH)
+}
+
+pub fn get_fixtures_dir() -> PathBuf {
+  get_app_data_dir().join(FIXTURES_PATH)
+}
+
+pub fn get_install_cache_dir() -> PathBuf {
+  get_app_data_dir().join(INSTALL_CACHE_PATH)
+}
+
+pub fn get_library_dir() -> PathBuf {
+  get_app_data_dir().join(LIBRARY_PATH)
+}
+
+pub fn get_downloads_dir() -> PathBuf {
+  get_app_data_dir().join(DOWNLOADS_PATH)
+}
+
+pub fn get_catalog_path() -> PathBuf {
+  get_app_data_dir().join(CATALOG_FILE)
+}
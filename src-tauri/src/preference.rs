@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::AppHandle;
+
+use crate::{
+    constant::DEFAULT_DEVICE_NAME,
+    host::scanner::HostDiscoverySettings,
+    library::package::LibrarySettings,
+    simulator::{
+        appearance::DisplaySettings, device_type::DeviceSpec,
+        status_bar::DEFAULT_STATUS_BAR_PRESET,
+    },
+    sotre::{get_tauri_store, update_tauri_store, StoreKey},
+};
+
+// all fields are optional to avoid breaking the existing store
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Preferences {
+    /** name of the device used when no device is specified, e.g. "iPhone 15 Pro" */
+    pub default_device_name: Option<String>,
+    /** the standard device matrix provisioned for a new teammate */
+    pub device_matrix: Vec<DeviceSpec>,
+    /** status bar preset applied before screenshots, None to keep the real status bar */
+    pub screenshot_status_bar_preset: Option<String>,
+    /** appearance & accessibility settings re-applied on boot, key: device udid */
+    pub display_settings: HashMap<String, DisplaySettings>,
+    /** watch folders & retention of the package library */
+    pub library: LibrarySettings,
+    /** hosts & ports scanned for debug hosts */
+    pub host_discovery: HostDiscoverySettings,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            default_device_name: None,
+            device_matrix: vec![],
+            screenshot_status_bar_preset: Some(DEFAULT_STATUS_BAR_PRESET.to_string()),
+            display_settings: HashMap::new(),
+            library: LibrarySettings::default(),
+            host_discovery: HostDiscoverySettings::default(),
+        }
+    }
+}
+
+impl Preferences {
+    pub fn default_device_name(&self) -> String {
+        self.default_device_name
+            .clone()
+            .unwrap_or_else(|| DEFAULT_DEVICE_NAME.to_string())
+    }
+}
+
+pub fn get_preferences(handle: &AppHandle) -> Preferences {
+    get_tauri_store(handle.clone())
+        .and_then(|store| store.preferences)
+        .unwrap_or_default()
+}
+
+pub fn set_preferences(handle: &AppHandle, preferences: Preferences) -> Result<(), String> {
+    update_tauri_store(handle.clone(), StoreKey::Preferences, json!(preferences))
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,271 @@
+/**
+ * This module lists the apps installed in the simulator by `xcrun simctl listapps`.
+ * The list is cached per device in the store, so the webview can show it without running simctl.
+ */
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+use crate::sotre::{get_tauri_store, update_tauri_store, StoreKey};
+
+use super::{
+    backend::get_simctl_backend, device::Device, error::SimctlError, openstep::parse_openstep_plist,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum AppType {
+    User,
+    System,
+    /** any other `ApplicationType`, e.g. "Hidden" */
+    Other(String),
+}
+
+impl From<&str> for AppType {
+    fn from(value: &str) -> Self {
+        match value {
+            "User" => AppType::User,
+            "System" => AppType::System,
+            _ => AppType::Other(value.to_string()),
+        }
+    }
+}
+
+/**
+ * An app installed in the simulator
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstalledApp {
+    pub bundle_id: String,
+    pub name: String,
+    /** CFBundleShortVersionString, e.g. "1.2.0" */
+    pub version: Option<String>,
+    /** CFBundleVersion, e.g. "1200" */
+    pub build: Option<String>,
+    pub app_type: AppType,
+    /** path of the .app bundle */
+    pub bundle_path: Option<String>,
+    pub data_container: Option<String>,
+    /** key: app group id, value: container path */
+    pub group_containers: HashMap<String, String>,
+}
+
+/**
+ * convert a `file://` url of listapps into a path, e.g. "file:///Users/a%20b/" -> "/Users/a b"
+ */
+fn file_url_to_path(url: &str) -> String {
+    let path = url.strip_prefix("file://").unwrap_or(url);
+    let path = if path.len() > 1 {
+        path.trim_end_matches('/')
+    } else {
+        path
+    };
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(b) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn get_string(info: &Value, key: &str) -> Option<String> {
+    info.get(key)
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+impl InstalledApp {
+    fn from_listapps(bundle_id: &str, info: &Value) -> Self {
+        let name = get_string(info, "CFBundleDisplayName")
+            .or_else(|| get_string(info, "CFBundleName"))
+            .unwrap_or_else(|| bundle_id.to_string());
+        let bundle_path = get_string(info, "Path")
+            .or_else(|| get_string(info, "Bundle").map(|url| file_url_to_path(&url)));
+        let group_containers = info
+            .get("GroupContainers")
+            .and_then(|v| v.as_object())
+            .map(|groups| {
+                groups
+                    .iter()
+                    .filter_map(|(id, url)| {
+                        url.as_str().map(|url| (id.clone(), file_url_to_path(url)))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        InstalledApp {
+            bundle_id: bundle_id.to_string(),
+            name,
+            version: get_string(info, "CFBundleShortVersionString"),
+            build: get_string(info, "CFBundleVersion"),
+            app_type: get_string(info, "ApplicationType")
+                .map(|t| AppType::from(t.as_str()))
+                .unwrap_or(AppType::User),
+            bundle_path,
+            data_container: get_string(info, "DataContainer").map(|url| file_url_to_path(&url)),
+            group_containers,
+        }
+    }
+}
+
+/**
+ * parse the output of `simctl listapps`, user apps first, then sorted by name
+ */
+pub fn parse_listapps(output: &str) -> Result<Vec<InstalledApp>, SimctlError> {
+    let value = parse_openstep_plist(output)?;
+    let apps = value
+        .as_object()
+        .ok_or_else(|| SimctlError::Parse("listapps output is not a dictionary".to_string()))?;
+    let mut apps = apps
+        .iter()
+        .map(|(bundle_id, info)| InstalledApp::from_listapps(bundle_id, info))
+        .collect::<Vec<_>>();
+    apps.sort_by(|a, b| {
+        (a.app_type != AppType::User, a.name.to_lowercase())
+            .cmp(&(b.app_type != AppType::User, b.name.to_lowercase()))
+    });
+    Ok(apps)
+}
+
+/**
+ * cached lists by device udid
+ */
+pub fn get_installed_app_cache(handle: &AppHandle) -> HashMap<String, Vec<InstalledApp>> {
+    get_tauri_store(handle.clone())
+        .and_then(|store| store.installed_apps)
+        .unwrap_or_default()
+}
+
+/**
+ * list the apps from simctl and update the cache
+ */
+pub async fn refresh_installed_apps(
+    handle: &AppHandle,
+    device: &Device,
+) -> Result<Vec<InstalledApp>, String> {
+    let backend = get_simctl_backend(handle);
+    let apps = device.list_apps(backend.as_ref()).await?;
+    let mut cache = get_installed_app_cache(handle);
+    cache.insert(device.udid.clone(), apps.clone());
+    update_tauri_store(handle.clone(), StoreKey::InstalledApps, json!(cache))
+        .map_err(|e| e.to_string())?;
+    Ok(apps)
+}
+
+/**
+ * get the cached list, list from simctl if the device is not cached or `refresh` is true
+ */
+pub async fn get_installed_apps(
+    handle: &AppHandle,
+    device: &Device,
+    refresh: bool,
+) -> Result<Vec<InstalledApp>, String> {
+    if !refresh {
+        if let Some(apps) = get_installed_app_cache(handle).remove(&device.udid) {
+            return Ok(apps);
+        }
+    }
+    refresh_installed_apps(handle, device).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /** captured from `xcrun simctl listapps <udid>`, trimmed to three apps */
+    const LISTAPPS_OUTPUT: &str = r#"{
+    "com.apple.mobilesafari" =     {
+        ApplicationType = System;
+        Bundle = "file:///Library/Developer/CoreSimulator/Volumes/iOS_21F79/Library/Developer/CoreSimulator/Profiles/Runtimes/iOS%2017.5.simruntime/Contents/Resources/RuntimeRoot/Applications/MobileSafari.app/";
+        CFBundleDisplayName = Safari;
+        CFBundleExecutable = MobileSafari;
+        CFBundleIdentifier = "com.apple.mobilesafari";
+        CFBundleName = Safari;
+        CFBundleVersion = "8618.2.12.10.5";
+        DataContainer = "file:///Users/costa/Library/Developer/CoreSimulator/Devices/5A1D1E4B-2F87-4C59-9C0C-63E0A1F3B6D2/data/Containers/Data/Application/0C6A7E0B-2B8C-4A0E-9D2F-1F3E5A7B9C1D/";
+        GroupContainers =         {
+        };
+        SBAppTags =         (
+        );
+    };
+    "com.apple.webapp" =     {
+        ApplicationType = Hidden;
+        Bundle = "file:///Library/Developer/CoreSimulator/Volumes/iOS_21F79/Library/Developer/CoreSimulator/Profiles/Runtimes/iOS%2017.5.simruntime/Contents/Resources/RuntimeRoot/Applications/Web.app/";
+        CFBundleExecutable = Web;
+        CFBundleIdentifier = "com.apple.webapp";
+        CFBundleName = Web;
+        CFBundleVersion = "8618.2.12.10.5";
+    };
+    "com.costa.demo" =     {
+        ApplicationType = User;
+        Bundle = "file:///Users/costa/Library/Developer/CoreSimulator/Devices/5A1D1E4B-2F87-4C59-9C0C-63E0A1F3B6D2/data/Containers/Bundle/Application/7D2E4F6A-8B0C-4D1E-A3F5-6B7C8D9E0F1A/Costa%20Demo.app/";
+        CFBundleDisplayName = "Costa Demo";
+        CFBundleExecutable = CostaDemo;
+        CFBundleIdentifier = "com.costa.demo";
+        CFBundleName = CostaDemo;
+        CFBundleShortVersionString = "1.2.0";
+        CFBundleVersion = 1200;
+        DataContainer = "file:///Users/costa/Library/Developer/CoreSimulator/Devices/5A1D1E4B-2F87-4C59-9C0C-63E0A1F3B6D2/data/Containers/Data/Application/3B5D7F9A-1C3E-4A6B-8D0F-2E4A6C8E0B2D/";
+        GroupContainers =         {
+            "group.com.costa.demo" = "file:///Users/costa/Library/Developer/CoreSimulator/Devices/5A1D1E4B-2F87-4C59-9C0C-63E0A1F3B6D2/data/Containers/Shared/AppGroup/9F1B3D5E-7A9C-4B1D-8F3A-5C7E9A1B3D5F/";
+        };
+        Path = "/Users/costa/Library/Developer/CoreSimulator/Devices/5A1D1E4B-2F87-4C59-9C0C-63E0A1F3B6D2/data/Containers/Bundle/Application/7D2E4F6A-8B0C-4D1E-A3F5-6B7C8D9E0F1A/Costa Demo.app";
+    };
+}"#;
+
+    #[test]
+    fn parses_the_listapps_output() {
+        let apps = parse_listapps(LISTAPPS_OUTPUT).unwrap();
+        let names = apps.iter().map(|app| app.name.as_str()).collect::<Vec<_>>();
+        // user apps first, then by name
+        assert_eq!(names, vec!["Costa Demo", "Safari", "Web"]);
+
+        let demo = &apps[0];
+        assert_eq!(demo.bundle_id, "com.costa.demo");
+        assert_eq!(demo.app_type, AppType::User);
+        assert_eq!(demo.version.as_deref(), Some("1.2.0"));
+        assert_eq!(demo.build.as_deref(), Some("1200"));
+        assert_eq!(
+            demo.bundle_path.as_deref(),
+            Some("/Users/costa/Library/Developer/CoreSimulator/Devices/5A1D1E4B-2F87-4C59-9C0C-63E0A1F3B6D2/data/Containers/Bundle/Application/7D2E4F6A-8B0C-4D1E-A3F5-6B7C8D9E0F1A/Costa Demo.app")
+        );
+        assert_eq!(
+            demo.group_containers.get("group.com.costa.demo").map(|p| p.as_str()),
+            Some("/Users/costa/Library/Developer/CoreSimulator/Devices/5A1D1E4B-2F87-4C59-9C0C-63E0A1F3B6D2/data/Containers/Shared/AppGroup/9F1B3D5E-7A9C-4B1D-8F3A-5C7E9A1B3D5F")
+        );
+
+        let safari = &apps[1];
+        assert_eq!(safari.app_type, AppType::System);
+        assert_eq!(safari.version, None);
+        assert!(safari.group_containers.is_empty());
+        // without `Path`, the bundle url is decoded
+        assert_eq!(
+            safari.bundle_path.as_deref(),
+            Some("/Library/Developer/CoreSimulator/Volumes/iOS_21F79/Library/Developer/CoreSimulator/Profiles/Runtimes/iOS 17.5.simruntime/Contents/Resources/RuntimeRoot/Applications/MobileSafari.app")
+        );
+
+        // no display name, CFBundleName is used
+        assert_eq!(apps[2].app_type, AppType::Other("Hidden".to_string()));
+        assert_eq!(apps[2].data_container, None);
+    }
+
+    #[test]
+    fn converts_the_file_urls() {
+        assert_eq!(file_url_to_path("file:///Users/a%20b/"), "/Users/a b");
+        assert_eq!(file_url_to_path("file:///"), "/");
+        // an invalid escape is kept
+        assert_eq!(file_url_to_path("file:///tmp/100%zz"), "/tmp/100%zz");
+        assert_eq!(file_url_to_path("/tmp/a%2Fb"), "/tmp/a/b");
+    }
+}
@@ -0,0 +1,206 @@
+/**
+ * This module changes the appearance & accessibility settings of the simulator by `xcrun simctl ui`.
+ * The settings are saved per device in the preferences and re-applied after the device is booted.
+ */
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use debug_print::debug_println;
+use log::error;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumIter, EnumString};
+use tauri::{AppHandle, Manager};
+
+use crate::preference::{get_preferences, set_preferences};
+
+use super::{
+    backend::{get_simctl_backend, SimctlBackend},
+    command,
+    device::Device,
+    error::SimctlError,
+};
+
+/** the tray reads the settings from simctl again after this duration */
+pub const DISPLAY_SETTINGS_MAX_AGE: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, EnumString, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Appearance {
+    Light,
+    Dark,
+}
+
+/**
+ * Dynamic Type sizes of `simctl ui <udid> content_size`
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, EnumString, EnumIter, Display)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum ContentSize {
+    ExtraSmall,
+    Small,
+    Medium,
+    Large,
+    ExtraLarge,
+    ExtraExtraLarge,
+    ExtraExtraExtraLarge,
+    AccessibilityMedium,
+    AccessibilityLarge,
+    AccessibilityExtraLarge,
+    AccessibilityExtraExtraLarge,
+    AccessibilityExtraExtraExtraLarge,
+}
+
+/**
+ * Appearance & accessibility settings of a device, None means unknown or unchanged
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct DisplaySettings {
+    pub appearance: Option<Appearance>,
+    pub content_size: Option<ContentSize>,
+    pub increase_contrast: Option<bool>,
+}
+
+impl DisplaySettings {
+    /** overwrite the fields which are set in `other` */
+    pub fn merge(&mut self, other: &DisplaySettings) {
+        if other.appearance.is_some() {
+            self.appearance = other.appearance;
+        }
+        if other.content_size.is_some() {
+            self.content_size = other.content_size;
+        }
+        if other.increase_contrast.is_some() {
+            self.increase_contrast = other.increase_contrast;
+        }
+    }
+}
+
+/**
+ * read the current settings from the booted device
+ * a value which simctl reports as unknown or unsupported is None
+ */
+pub async fn read_display_settings(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+) -> Result<DisplaySettings, SimctlError> {
+    Ok(DisplaySettings {
+        appearance: command::get_appearance(backend, udid).await?,
+        content_size: command::get_content_size(backend, udid).await?,
+        increase_contrast: command::get_increase_contrast(backend, udid).await?,
+    })
+}
+
+/**
+ * apply the fields which are set
+ */
+pub async fn apply_display_settings(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    settings: &DisplaySettings,
+) -> Result<(), SimctlError> {
+    if let Some(appearance) = settings.appearance {
+        command::set_appearance(backend, udid, appearance).await?;
+    }
+    if let Some(content_size) = settings.content_size {
+        command::set_content_size(backend, udid, content_size).await?;
+    }
+    if let Some(enabled) = settings.increase_contrast {
+        command::set_increase_contrast(backend, udid, enabled).await?;
+    }
+    Ok(())
+}
+
+/**
+ * Settings read back from the devices managed by tauri, so the tray does not run simctl on every tick
+ * key: device udid
+ * value: read time & settings
+ */
+#[derive(Default)]
+pub struct DisplaySettingsCache(Mutex<HashMap<String, (Instant, DisplaySettings)>>);
+
+impl DisplaySettingsCache {
+    /** get the settings if they were read within `max_age` */
+    pub fn get(&self, udid: &str, max_age: Duration) -> Option<DisplaySettings> {
+        self.0
+            .lock()
+            .unwrap()
+            .get(udid)
+            .filter(|(read_at, _)| read_at.elapsed() < max_age)
+            .map(|(_, settings)| settings.clone())
+    }
+    pub fn insert(&self, udid: &str, settings: DisplaySettings) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(udid.to_string(), (Instant::now(), settings));
+    }
+}
+
+/**
+ * get the settings of the device for the tray, read from simctl if the cache is stale
+ */
+pub async fn get_display_settings(handle: &AppHandle, udid: &str) -> DisplaySettings {
+    if let Some(settings) = handle
+        .state::<DisplaySettingsCache>()
+        .get(udid, DISPLAY_SETTINGS_MAX_AGE)
+    {
+        return settings;
+    }
+    let backend = get_simctl_backend(handle);
+    let settings = read_display_settings(backend.as_ref(), udid)
+        .await
+        .unwrap_or_else(|e| {
+            debug_println!("read display settings error: {}", e);
+            DisplaySettings::default()
+        });
+    handle
+        .state::<DisplaySettingsCache>()
+        .insert(udid, settings.clone());
+    settings
+}
+
+/**
+ * apply the changed settings, save them as the preference of the device
+ * return the settings read back from the device
+ */
+pub async fn update_display_settings(
+    handle: &AppHandle,
+    device: &Device,
+    changes: &DisplaySettings,
+) -> Result<DisplaySettings, String> {
+    let backend = get_simctl_backend(handle);
+    apply_display_settings(backend.as_ref(), &device.udid, changes).await?;
+    let mut preferences = get_preferences(handle);
+    preferences
+        .display_settings
+        .entry(device.udid.clone())
+        .or_default()
+        .merge(changes);
+    set_preferences(handle, preferences)?;
+    let settings = read_display_settings(backend.as_ref(), &device.udid).await?;
+    handle
+        .state::<DisplaySettingsCache>()
+        .insert(&device.udid, settings.clone());
+    Ok(settings)
+}
+
+/**
+ * re-apply the saved settings after the device is booted
+ */
+pub async fn apply_saved_display_settings(handle: &AppHandle, udid: &str) {
+    let settings = match get_preferences(handle).display_settings.remove(udid) {
+        Some(settings) => settings,
+        None => return,
+    };
+    debug_println!("apply display settings to {}: {:?}", udid, settings);
+    let backend = get_simctl_backend(handle);
+    if let Err(e) = apply_display_settings(backend.as_ref(), udid, &settings).await {
+        error!("apply display settings error: {}", e);
+    }
+}
@@ -0,0 +1,446 @@
+/**
+ * This module abstracts how `xcrun simctl` is executed.
+ * The real backend shells out to xcrun, the recorded backend replays captured
+ * simctl output and keeps every issued command, so the simulator logic can run without Xcode.
+ */
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use tauri::{AppHandle, Manager};
+
+use super::{
+    error::SimctlError,
+    runner::{ProcessRunner, RunOptions},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct SimctlOutput {
+    /** exit code of the process, None if it was killed by a signal */
+    pub status: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl SimctlOutput {
+    pub fn ok(stdout: &str) -> Self {
+        Self {
+            status: Some(0),
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+        }
+    }
+    pub fn failed(status: i32, stderr: &str) -> Self {
+        Self {
+            status: Some(status),
+            stdout: String::new(),
+            stderr: stderr.to_string(),
+        }
+    }
+    pub fn success(&self) -> bool {
+        self.status == Some(0)
+    }
+    /** return stdout if the command succeeded, otherwise the stderr wrapped in SimctlError */
+    pub fn into_stdout(self, args: &[&str]) -> Result<String, SimctlError> {
+        if self.success() {
+            Ok(self.stdout)
+        } else {
+            Err(SimctlError::NonZeroExit {
+                command: format!("simctl {}", args.join(" ")),
+                status: self.status,
+                stderr: self.stderr,
+            })
+        }
+    }
+}
+
+/**
+ * A source of output lines, e.g. the stdout of `simctl spawn <udid> log stream`
+ */
+#[async_trait]
+pub trait LineSource: Send {
+    /** next line without the trailing newline, None when the source is closed */
+    async fn next_line(&mut self) -> Option<String>;
+}
+
+/**
+ * A line source feeding the given lines, used by the recorded backend
+ */
+#[derive(Debug, Default)]
+pub struct VecLineSource(VecDeque<String>);
+
+impl VecLineSource {
+    pub fn new(lines: Vec<String>) -> Self {
+        Self(lines.into())
+    }
+}
+
+#[async_trait]
+impl LineSource for VecLineSource {
+    async fn next_line(&mut self) -> Option<String> {
+        self.0.pop_front()
+    }
+}
+
+/**
+ * A long running simctl process, e.g. `simctl io recordVideo`
+ */
+#[async_trait]
+pub trait SimctlProcess: Send {
+    /** interrupt the process (SIGINT) so it can flush its output, then wait for it to exit */
+    async fn stop(&mut self) -> Result<Option<i32>, SimctlError>;
+    /** take the stdout of the process as lines, None if it has been taken */
+    fn take_stdout(&mut self) -> Option<Box<dyn LineSource>> {
+        None
+    }
+}
+
+#[async_trait]
+pub trait SimctlBackend: Send + Sync {
+    /** exec `xcrun simctl <args>` with the given timeout & cancellation, return the captured output */
+    async fn run(&self, args: &[&str], options: RunOptions) -> Result<SimctlOutput, SimctlError>;
+    /** spawn `xcrun simctl <args>` without waiting for it */
+    async fn spawn(&self, args: &[&str]) -> Result<Box<dyn SimctlProcess>, SimctlError>;
+    /** exec a host program other than simctl, e.g. `osascript` or `open` */
+    async fn run_program(
+        &self,
+        program: &str,
+        args: &[&str],
+        options: RunOptions,
+    ) -> Result<SimctlOutput, SimctlError>;
+    /** exec `xcrun simctl <args>` with the default timeout of the sub command */
+    async fn simctl(&self, args: &[&str]) -> Result<SimctlOutput, SimctlError> {
+        self.run(args, RunOptions::for_simctl(args)).await
+    }
+}
+
+/**
+ * The real backend, exec `xcrun simctl` on the host
+ */
+#[derive(Debug, Default)]
+pub struct XcrunBackend {
+    runner: ProcessRunner,
+}
+
+#[async_trait]
+impl SimctlBackend for XcrunBackend {
+    async fn run(&self, args: &[&str], options: RunOptions) -> Result<SimctlOutput, SimctlError> {
+        let mut simctl_args = vec!["simctl"];
+        simctl_args.extend_from_slice(args);
+        self.runner.run("xcrun", &simctl_args, options).await
+    }
+    async fn run_program(
+        &self,
+        program: &str,
+        args: &[&str],
+        options: RunOptions,
+    ) -> Result<SimctlOutput, SimctlError> {
+        self.runner.run(program, args, options).await
+    }
+    async fn spawn(&self, args: &[&str]) -> Result<Box<dyn SimctlProcess>, SimctlError> {
+        let mut simctl_args = vec!["simctl"];
+        simctl_args.extend_from_slice(args);
+        Ok(Box::new(self.runner.spawn("xcrun", &simctl_args)?))
+    }
+}
+
+/**
+ * A fake backend which replays recorded simctl output.
+ * key: the simctl args joined by space, e.g. `list --json devices available`
+ * or the program & its args for the other programs, e.g. `open -a Simulator`
+ * value: the recorded output
+ * commands without a recorded output succeed with an empty stdout.
+ */
+#[derive(Debug, Default)]
+pub struct RecordedBackend {
+    responses: Mutex<HashMap<String, SimctlOutput>>,
+    commands: Mutex<Vec<Vec<String>>>,
+}
+
+impl RecordedBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_response(self, args: &[&str], output: SimctlOutput) -> Self {
+        self.responses
+            .lock()
+            .unwrap()
+            .insert(args.join(" "), output);
+        self
+    }
+    /** replay a captured `simctl list --json devices available` output */
+    pub fn with_devices_json(self, json: &str) -> Self {
+        self.with_response(
+            &["list", "--json", "devices", "available"],
+            SimctlOutput::ok(json),
+        )
+    }
+    /** replay a captured `simctl runtime list --json` output */
+    pub fn with_runtimes_json(self, json: &str) -> Self {
+        self.with_response(&["runtime", "list", "--json"], SimctlOutput::ok(json))
+    }
+    /** all commands issued to this backend, in order */
+    pub fn commands(&self) -> Vec<Vec<String>> {
+        self.commands.lock().unwrap().clone()
+    }
+    /** check if the command has been issued, e.g. `["boot", udid]` */
+    pub fn has_command(&self, args: &[&str]) -> bool {
+        self.commands
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|c| c.iter().map(|s| s.as_str()).eq(args.iter().copied()))
+    }
+}
+
+/**
+ * A recorded process, its stdout replays the recorded output line by line
+ * and it stops immediately with exit code 0
+ */
+#[derive(Debug, Default)]
+pub struct RecordedProcess {
+    lines: Option<Vec<String>>,
+}
+
+#[async_trait]
+impl SimctlProcess for RecordedProcess {
+    async fn stop(&mut self) -> Result<Option<i32>, SimctlError> {
+        Ok(Some(0))
+    }
+    fn take_stdout(&mut self) -> Option<Box<dyn LineSource>> {
+        self.lines
+            .take()
+            .map(|lines| Box::new(VecLineSource::new(lines)) as Box<dyn LineSource>)
+    }
+}
+
+#[async_trait]
+impl SimctlBackend for RecordedBackend {
+    async fn run(&self, args: &[&str], options: RunOptions) -> Result<SimctlOutput, SimctlError> {
+        if options.cancel.map(|c| c.is_cancelled()).unwrap_or(false) {
+            return Err(SimctlError::Cancelled(format!("simctl {}", args.join(" "))));
+        }
+        self.commands
+            .lock()
+            .unwrap()
+            .push(args.iter().map(|s| s.to_string()).collect());
+        Ok(self
+            .responses
+            .lock()
+            .unwrap()
+            .get(&args.join(" "))
+            .cloned()
+            .unwrap_or_else(|| SimctlOutput::ok("")))
+    }
+    async fn run_program(
+        &self,
+        program: &str,
+        args: &[&str],
+        options: RunOptions,
+    ) -> Result<SimctlOutput, SimctlError> {
+        let mut command = vec![program];
+        command.extend_from_slice(args);
+        self.run(&command, options).await
+    }
+    async fn spawn(&self, args: &[&str]) -> Result<Box<dyn SimctlProcess>, SimctlError> {
+        self.commands
+            .lock()
+            .unwrap()
+            .push(args.iter().map(|s| s.to_string()).collect());
+        let lines = self
+            .responses
+            .lock()
+            .unwrap()
+            .get(&args.join(" "))
+            .map(|output| output.stdout.lines().map(|l| l.to_string()).collect())
+            .unwrap_or_default();
+        Ok(Box::new(RecordedProcess { lines: Some(lines) }))
+    }
+}
+
+/**
+ * The backend managed by tauri, use `get_simctl_backend` to get it from the app handle
+ */
+pub struct SimctlState(pub Arc<dyn SimctlBackend>);
+
+impl Default for SimctlState {
+    fn default() -> Self {
+        Self(Arc::new(XcrunBackend::default()))
+    }
+}
+
+pub fn get_simctl_backend(handle: &AppHandle) -> Arc<dyn SimctlBackend> {
+    handle.state::<SimctlState>().0.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::command::{
+        boot_device, get_all_devices, install_app, open_simulator_app, open_url,
+    };
+
+    /** captured from `xcrun simctl list --json devices available`, trimmed to two runtimes */
+    const DEVICES_JSON: &str = r#"{
+      "devices" : {
+        "com.apple.CoreSimulator.SimRuntime.iOS-17-2" : [
+          {
+            "lastBootedAt" : "2024-03-01T08:12:45Z",
+            "dataPath" : "/Users/costa/Library/Developer/CoreSimulator/Devices/5A1D1E4B-2F87-4C59-9C0C-63E0A1F3B6D2/data",
+            "dataPathSize" : 1811939328,
+            "logPath" : "/Users/costa/Library/Logs/CoreSimulator/5A1D1E4B-2F87-4C59-9C0C-63E0A1F3B6D2",
+            "udid" : "5A1D1E4B-2F87-4C59-9C0C-63E0A1F3B6D2",
+            "isAvailable" : true,
+            "deviceTypeIdentifier" : "com.apple.CoreSimulator.SimDeviceType.iPhone-15-Pro",
+            "state" : "Booted",
+            "name" : "iPhone 15 Pro"
+          },
+          {
+            "dataPath" : "/Users/costa/Library/Developer/CoreSimulator/Devices/9E4B3C2A-1D0F-4E8B-A7C6-5B4A3F2E1D0C/data",
+            "dataPathSize" : 0,
+            "logPath" : "/Users/costa/Library/Logs/CoreSimulator/9E4B3C2A-1D0F-4E8B-A7C6-5B4A3F2E1D0C",
+            "udid" : "9E4B3C2A-1D0F-4E8B-A7C6-5B4A3F2E1D0C",
+            "isAvailable" : true,
+            "deviceTypeIdentifier" : "com.apple.CoreSimulator.SimDeviceType.iPad-Air-5th-generation",
+            "state" : "Shutdown",
+            "name" : "iPad Air (5th generation)"
+          }
+        ],
+        "com.apple.CoreSimulator.SimRuntime.iOS-16-4" : [
+          {
+            "dataPath" : "/Users/costa/Library/Developer/CoreSimulator/Devices/0C7F6E5D-4B3A-4291-8E7D-6C5B4A392817/data",
+            "dataPathSize" : 524288000,
+            "logPath" : "/Users/costa/Library/Logs/CoreSimulator/0C7F6E5D-4B3A-4291-8E7D-6C5B4A392817",
+            "udid" : "0C7F6E5D-4B3A-4291-8E7D-6C5B4A392817",
+            "isAvailable" : true,
+            "deviceTypeIdentifier" : "com.apple.CoreSimulator.SimDeviceType.iPhone-14",
+            "state" : "Shutdown",
+            "name" : "iPhone 14"
+          }
+        ]
+      }
+    }"#;
+
+    const BOOTED_UDID: &str = "5A1D1E4B-2F87-4C59-9C0C-63E0A1F3B6D2";
+    const SHUTDOWN_UDID: &str = "0C7F6E5D-4B3A-4291-8E7D-6C5B4A392817";
+
+    fn command(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn replays_the_device_list() {
+        let backend = RecordedBackend::new().with_devices_json(DEVICES_JSON);
+        let devices = get_all_devices(&backend).await.unwrap();
+
+        assert_eq!(devices.devices.len(), 2);
+        assert_eq!(
+            devices.devices["com.apple.CoreSimulator.SimRuntime.iOS-17-2"].len(),
+            2
+        );
+        let booted = devices.get_first_booted_device().unwrap();
+        assert_eq!(booted.udid, BOOTED_UDID);
+        assert_eq!(booted.name, "iPhone 15 Pro");
+        assert_eq!(booted.data_path_size, 1811939328);
+        // the os version is the last part of the runtime key
+        assert_eq!(booted.os_version.as_deref(), Some("iOS-17-2"));
+        let device = devices.get_default_device("iPhone 14").unwrap();
+        assert_eq!(device.udid, SHUTDOWN_UDID);
+        assert_eq!(device.state, "Shutdown");
+        assert_eq!(device.os_version.as_deref(), Some("iOS-16-4"));
+        assert!(devices.get_device_by_udid("unknown").is_none());
+
+        assert_eq!(
+            backend.commands(),
+            vec![command(&["list", "--json", "devices", "available"])]
+        );
+    }
+
+    #[tokio::test]
+    async fn records_the_device_commands() {
+        let backend = RecordedBackend::new().with_devices_json(DEVICES_JSON);
+        let devices = get_all_devices(&backend).await.unwrap();
+        let device = devices.get_default_device("iPhone 14").unwrap();
+
+        boot_device(&backend, &device.udid).await.unwrap();
+        device
+            .install_app(&backend, "/tmp/costa/Demo.app")
+            .await
+            .unwrap();
+        device
+            .open_url(&backend, "sslocal://webview?url=https%3A%2F%2Fexample.com")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            backend.commands(),
+            vec![
+                command(&["list", "--json", "devices", "available"]),
+                command(&["boot", SHUTDOWN_UDID]),
+                command(&["install", SHUTDOWN_UDID, "/tmp/costa/Demo.app"]),
+                command(&[
+                    "openurl",
+                    SHUTDOWN_UDID,
+                    "sslocal://webview?url=https%3A%2F%2Fexample.com"
+                ]),
+            ]
+        );
+        assert!(backend.has_command(&["boot", SHUTDOWN_UDID]));
+        assert!(!backend.has_command(&["boot", BOOTED_UDID]));
+    }
+
+    #[tokio::test]
+    async fn replays_a_failed_command() {
+        let stderr = "An error was encountered processing the command (domain=com.apple.CoreSimulator.SimError, code=405):\nUnable to boot device in current state: Booted";
+        let backend = RecordedBackend::new()
+            .with_response(&["boot", BOOTED_UDID], SimctlOutput::failed(149, stderr));
+
+        match boot_device(&backend, BOOTED_UDID).await {
+            Err(SimctlError::NonZeroExit {
+                command,
+                status,
+                stderr: err,
+            }) => {
+                assert_eq!(command, format!("simctl boot {}", BOOTED_UDID));
+                assert_eq!(status, Some(149));
+                assert_eq!(err, stderr);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(backend.commands(), vec![command(&["boot", BOOTED_UDID])]);
+    }
+
+    #[tokio::test]
+    async fn invalid_device_json_is_a_parse_error() {
+        let backend = RecordedBackend::new().with_devices_json("{\"devices\": [");
+        assert!(matches!(
+            get_all_devices(&backend).await,
+            Err(SimctlError::Parse(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn reports_a_failed_host_program() {
+        const SIMULATOR_APP: &str =
+            "/Applications/Xcode.app/Contents/Developer/Applications/Simulator.app";
+        let stderr = "The file /Applications/Xcode.app/Contents/Developer/Applications/Simulator.app does not exist.";
+        let backend = RecordedBackend::new()
+            .with_response(&["open", SIMULATOR_APP], SimctlOutput::failed(1, stderr));
+
+        match open_simulator_app(&backend).await {
+            Err(SimctlError::NonZeroExit {
+                command,
+                status,
+                stderr: err,
+            }) => {
+                assert_eq!(command, "open");
+                assert_eq!(status, Some(1));
+                assert_eq!(err, stderr);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(backend.commands(), vec![command(&["open", SIMULATOR_APP])]);
+    }
+}
@@ -0,0 +1,398 @@
+/**
+ * This module captures screenshots and screen recordings of the simulator by `xcrun simctl io`.
+ * Captures are saved into `~/.costa/captures` and indexed in the store.
+ */
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use chrono::Local;
+use debug_print::debug_println;
+use log::error;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use strum_macros::{Display, EnumString};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    path::get_captures_dir,
+    preference::get_preferences,
+    sotre::{get_tauri_store, update_tauri_store, StoreKey},
+};
+
+use super::{
+    backend::{get_simctl_backend, SimctlProcess},
+    device::Device,
+    status_bar::{get_status_bar_preset, StatusBarOverrides},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, EnumString, Display, Default)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum ScreenshotFormat {
+    #[default]
+    Png,
+    Jpeg,
+    Tiff,
+    Bmp,
+    Gif,
+}
+
+/**
+ * How to handle the non-rectangular display mask, see `xcrun simctl io --help`
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, EnumString, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum MaskPolicy {
+    Ignored,
+    Alpha,
+    Black,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureKind {
+    Screenshot,
+    Video,
+}
+
+/**
+ * An item of the capture index
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CaptureRecord {
+    pub id: String,
+    pub kind: CaptureKind,
+    pub udid: String,
+    pub device_name: String,
+    pub path: String,
+    /** route of the debug host when the capture was taken */
+    pub route: Option<String>,
+    pub created_at: String,
+}
+
+/**
+ * A running `simctl io recordVideo`
+ */
+pub struct RecordingSession {
+    pub process: Box<dyn SimctlProcess>,
+    pub path: PathBuf,
+    pub device_name: String,
+    pub route: Option<String>,
+    pub started_at: String,
+}
+
+/**
+ * Recording sessions managed by tauri
+ * key: device udid
+ * value: None while `simctl io recordVideo` is starting
+ */
+#[derive(Default)]
+pub struct RecordingSessions(Mutex<HashMap<String, Option<RecordingSession>>>);
+
+impl RecordingSessions {
+    /** true if the device is recording or the recording is starting */
+    pub fn is_recording(&self, udid: &str) -> bool {
+        self.0.lock().unwrap().contains_key(udid)
+    }
+    /**
+     * reserve the device for a new recording, false if it is already recording
+     * the check & the reservation share the lock, so two starts cannot both spawn a process
+     */
+    fn reserve(&self, udid: &str) -> bool {
+        let mut sessions = self.0.lock().unwrap();
+        if sessions.contains_key(udid) {
+            return false;
+        }
+        sessions.insert(udid.to_string(), None);
+        true
+    }
+    /** give the reservation up, e.g. the process failed to start */
+    fn release(&self, udid: &str) {
+        let mut sessions = self.0.lock().unwrap();
+        if matches!(sessions.get(udid), Some(None)) {
+            sessions.remove(udid);
+        }
+    }
+    fn insert(&self, udid: &str, session: RecordingSession) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(udid.to_string(), Some(session));
+    }
+    /**
+     * put the session back after its process failed to stop, so the recording can be stopped again
+     * a recording started in the meantime is kept
+     */
+    fn restore(&self, udid: &str, session: RecordingSession) {
+        self.0
+            .lock()
+            .unwrap()
+            .entry(udid.to_string())
+            .or_insert(Some(session));
+    }
+    /** remove the running session, a starting one is kept until it has started */
+    fn take(&self, udid: &str) -> Result<RecordingSession, String> {
+        let mut sessions = self.0.lock().unwrap();
+        match sessions.get(udid) {
+            Some(Some(_)) => Ok(sessions.remove(udid).flatten().unwrap()),
+            Some(None) => Err("recording is starting".to_string()),
+            None => Err("device is not recording".to_string()),
+        }
+    }
+}
+
+/** sequence of the capture ids in this process */
+static NEXT_CAPTURE_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/**
+ * a unique id of a new capture, e.g. "1729767701123-0"
+ * the millis alone collide when two devices are captured at once
+ */
+fn new_capture_id() -> String {
+    format!(
+        "{}-{}",
+        Local::now().timestamp_millis(),
+        NEXT_CAPTURE_SEQ.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/**
+ * build the path of a new capture, e.g. `~/.costa/captures/iPhone_15_Pro_20241024-110141123.png`
+ */
+pub fn new_capture_path(device: &Device, extension: &str) -> Result<PathBuf, std::io::Error> {
+    let dir = get_captures_dir();
+    std::fs::create_dir_all(&dir)?;
+    let name = format!(
+        "{}_{}.{}",
+        device.name.replace(' ', "_"),
+        Local::now().format("%Y%m%d-%H%M%S%3f"),
+        extension
+    );
+    Ok(dir.join(name))
+}
+
+/**
+ * get the current route from the selected debug host, None if no host is available
+ */
+async fn get_current_route(handle: &AppHandle) -> Option<String> {
+    let host = get_tauri_store(handle.clone())?.debug_hosts?;
+    host.get_current_route().await
+}
+
+pub fn get_capture_list(handle: &AppHandle) -> Vec<CaptureRecord> {
+    get_tauri_store(handle.clone())
+        .and_then(|store| store.captures)
+        .unwrap_or_default()
+}
+
+fn save_capture_list(handle: &AppHandle, captures: Vec<CaptureRecord>) -> Result<(), String> {
+    update_tauri_store(handle.clone(), StoreKey::Captures, json!(captures))
+        .map_err(|e| e.to_string())
+}
+
+fn add_capture_record(handle: &AppHandle, record: CaptureRecord) -> Result<(), String> {
+    let mut captures = get_capture_list(handle);
+    captures.insert(0, record.clone());
+    save_capture_list(handle, captures)?;
+    let _ = handle.emit_all("capture_added", record);
+    Ok(())
+}
+
+/**
+ * remove the capture from the index and delete the file
+ */
+pub fn delete_capture(handle: &AppHandle, id: &str) -> Result<(), String> {
+    let mut captures = get_capture_list(handle);
+    if let Some(record) = captures.iter().find(|c| c.id == id) {
+        let _ = std::fs::remove_file(&record.path);
+    }
+    captures.retain(|c| c.id != id);
+    save_capture_list(handle, captures)
+}
+
+/**
+ * apply the status bar preset of the preferences before a screenshot
+ * return true if the preset was applied and should be cleared after the screenshot
+ * a preset applied from the webview is kept as is
+ */
+async fn apply_screenshot_status_bar(handle: &AppHandle, device: &Device) -> bool {
+    if handle
+        .state::<StatusBarOverrides>()
+        .get(&device.udid)
+        .is_some()
+    {
+        return false;
+    }
+    let preset = match get_preferences(handle)
+        .screenshot_status_bar_preset
+        .and_then(|name| get_status_bar_preset(handle, &name))
+    {
+        Some(preset) => preset,
+        None => return false,
+    };
+    let backend = get_simctl_backend(handle);
+    match device
+        .override_status_bar(backend.as_ref(), &preset.status_bar)
+        .await
+    {
+        Ok(_) => true,
+        Err(e) => {
+            error!("apply status bar preset {} error: {}", preset.name, e);
+            false
+        }
+    }
+}
+
+pub async fn take_screenshot(
+    handle: &AppHandle,
+    device: &Device,
+    format: ScreenshotFormat,
+    mask: Option<MaskPolicy>,
+) -> Result<CaptureRecord, String> {
+    let backend = get_simctl_backend(handle);
+    let status_bar_applied = apply_screenshot_status_bar(handle, device).await;
+    let result = device.screenshot(backend.as_ref(), format, mask).await;
+    if status_bar_applied {
+        if let Err(e) = device.clear_status_bar(backend.as_ref()).await {
+            error!("clear status bar error: {}", e);
+        }
+    }
+    let path = result?;
+    debug_println!("screenshot saved: {:?}", path);
+    let record = CaptureRecord {
+        id: new_capture_id(),
+        kind: CaptureKind::Screenshot,
+        udid: device.udid.clone(),
+        device_name: device.name.clone(),
+        path: path.to_string_lossy().to_string(),
+        route: get_current_route(handle).await,
+        created_at: Local::now().to_rfc3339(),
+    };
+    add_capture_record(handle, record.clone())?;
+    Ok(record)
+}
+
+pub async fn start_recording(
+    handle: &AppHandle,
+    device: &Device,
+    mask: Option<MaskPolicy>,
+) -> Result<(), String> {
+    if !handle.state::<RecordingSessions>().reserve(&device.udid) {
+        return Err(format!("{} is already recording", device.name));
+    }
+    let backend = get_simctl_backend(handle);
+    let (process, path) = match device.start_recording(backend.as_ref(), mask).await {
+        Ok(started) => started,
+        Err(e) => {
+            handle.state::<RecordingSessions>().release(&device.udid);
+            return Err(e.into());
+        }
+    };
+    let session = RecordingSession {
+        process,
+        path,
+        device_name: device.name.clone(),
+        route: get_current_route(handle).await,
+        started_at: Local::now().to_rfc3339(),
+    };
+    handle
+        .state::<RecordingSessions>()
+        .insert(&device.udid, session);
+    Ok(())
+}
+
+pub async fn stop_recording(handle: &AppHandle, udid: &str) -> Result<CaptureRecord, String> {
+    let mut session = handle.state::<RecordingSessions>().take(udid)?;
+    let status = match session.process.stop().await {
+        Ok(status) => status,
+        Err(e) => {
+            handle.state::<RecordingSessions>().restore(udid, session);
+            return Err(e.into());
+        }
+    };
+    debug_println!("recording stopped: {:?} {:?}", session.path, status);
+    let record = CaptureRecord {
+        id: new_capture_id(),
+        kind: CaptureKind::Video,
+        udid: udid.to_string(),
+        device_name: session.device_name,
+        path: session.path.to_string_lossy().to_string(),
+        route: session.route,
+        created_at: session.started_at,
+    };
+    add_capture_record(handle, record.clone())?;
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::backend::RecordedProcess;
+
+    fn session() -> RecordingSession {
+        RecordingSession {
+            process: Box::new(RecordedProcess::default()),
+            path: PathBuf::from("/tmp/costa/recording.mp4"),
+            device_name: "iPhone 15 Pro".to_string(),
+            route: None,
+            started_at: Local::now().to_rfc3339(),
+        }
+    }
+
+    #[test]
+    fn a_device_is_reserved_once() {
+        let sessions = RecordingSessions::default();
+        assert!(sessions.reserve("udid"));
+        assert!(!sessions.reserve("udid"));
+        assert!(sessions.is_recording("udid"));
+        // stopping a starting recording keeps the reservation
+        assert!(sessions.take("udid").is_err());
+        sessions.insert("udid", session());
+        assert!(!sessions.reserve("udid"));
+        // a running session is not released by a failed start
+        sessions.release("udid");
+        assert!(sessions.take("udid").is_ok());
+        assert!(!sessions.is_recording("udid"));
+        assert!(sessions.take("udid").is_err());
+    }
+
+    #[test]
+    fn a_failed_start_releases_the_device() {
+        let sessions = RecordingSessions::default();
+        assert!(sessions.reserve("udid"));
+        sessions.release("udid");
+        assert!(!sessions.is_recording("udid"));
+        assert!(sessions.reserve("udid"));
+    }
+
+    #[test]
+    fn a_session_failed_to_stop_is_restored() {
+        let sessions = RecordingSessions::default();
+        sessions.insert("udid", session());
+        let stopping = sessions.take("udid").unwrap();
+        sessions.restore("udid", stopping);
+        assert!(sessions.take("udid").is_ok());
+        // a recording started while stopping is kept
+        assert!(sessions.reserve("udid"));
+        sessions.restore("udid", session());
+        assert_eq!(
+            sessions.take("udid").err().as_deref(),
+            Some("recording is starting")
+        );
+    }
+
+    #[test]
+    fn capture_ids_are_unique() {
+        let ids = (0..100)
+            .map(|_| new_capture_id())
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(ids.len(), 100);
+    }
+}
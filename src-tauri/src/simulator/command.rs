@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 /**
  * This module is used to execute the command of the simulator
@@ -6,179 +6,625 @@ use std::collections::HashMap;
 use debug_print::debug_println;
 use log::info;
 
+use tokio_util::sync::CancellationToken;
+
+use super::app::{parse_listapps, InstalledApp};
+use super::appearance::{Appearance, ContentSize};
+use super::backend::{SimctlBackend, SimctlProcess};
+use super::capture::{MaskPolicy, ScreenshotFormat};
 use super::device::{Device, DeviceMap};
-use super::runtime::{Runtime};
-pub fn get_all_devices() -> DeviceMap {
-    // exec `xcrun simctl list --json devices` and parse the output
-    let output = std::process::Command::new("xcrun")
-        .arg("simctl")
-        .arg("list")
-        .arg("--json")
-        .arg("devices")
-        .arg("available")
-        .output()
-        .expect("failed to execute process");
-    let output = String::from_utf8(output.stdout).unwrap();
-    let mut devices: DeviceMap = serde_json::from_str(&output).unwrap();
-    // set os_version field in devices using the key of the hashmap
-    for (key, device) in devices.devices.iter_mut() {
-        device.iter_mut().for_each(|d| {
-            d.os_version = Some(
-                key.clone()
-                    .split(".")
-                    .collect::<Vec<&str>>()
-                    .pop()
-                    .unwrap_or("unknown system version")
-                    .to_string(),
-            );
+use super::device_type::{DeviceSpec, DeviceType, DeviceTypeList, ProvisionResult};
+use super::error::SimctlError;
+use super::location::Coordinate;
+use super::privacy::{PrivacyAction, PrivacyService};
+use super::runner::RunOptions;
+use super::runtime::{Runtime, RuntimeMatch};
+use super::status_bar::StatusBarOverride;
+
+/**
+ * exec `xcrun simctl <args>`, return stdout on success
+ * or a SimctlError carrying the exit status and stderr
+ */
+async fn exec(backend: &dyn SimctlBackend, args: &[&str]) -> Result<String, SimctlError> {
+    backend.simctl(args).await?.into_stdout(args)
+}
+
+/**
+ * same as `exec`, the command is killed when the token is cancelled
+ */
+async fn exec_cancellable(
+    backend: &dyn SimctlBackend,
+    args: &[&str],
+    cancel: Option<CancellationToken>,
+) -> Result<String, SimctlError> {
+    let mut options = RunOptions::for_simctl(args);
+    if let Some(cancel) = cancel {
+        options = options.with_cancel(cancel);
+    }
+    backend.run(args, options).await?.into_stdout(args)
+}
+
+/** timeout of the host programs, osascript waits for Safari to come to the front */
+const HOST_PROGRAM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/**
+ * exec `<program> <args>` on the host, e.g. `osascript`, return stdout on success
+ * the error names the program only, the args of osascript are a whole script
+ */
+async fn exec_program(
+    backend: &dyn SimctlBackend,
+    program: &str,
+    args: &[&str],
+) -> Result<String, SimctlError> {
+    let output = backend
+        .run_program(program, args, RunOptions::new(HOST_PROGRAM_TIMEOUT))
+        .await?;
+    if !output.success() {
+        return Err(SimctlError::NonZeroExit {
+            command: program.to_string(),
+            status: output.status,
+            stderr: output.stderr,
         });
     }
-    devices
+    debug_println!("{}: {}", program, output.stdout);
+    Ok(output.stdout)
+}
+
+pub async fn get_all_devices(backend: &dyn SimctlBackend) -> Result<DeviceMap, SimctlError> {
+    // exec `xcrun simctl list --json devices available` and parse the output
+    let output = exec(backend, &["list", "--json", "devices", "available"]).await?;
+    DeviceMap::from_simctl_json(&output)
+}
+
+pub async fn get_device(backend: &dyn SimctlBackend, udid: &str) -> Result<Device, SimctlError> {
+    get_all_devices(backend)
+        .await?
+        .get_device_by_udid(udid)
+        .cloned()
+        .ok_or_else(|| SimctlError::DeviceNotFound(udid.to_string()))
+}
+
+pub async fn get_all_runtimes(
+    backend: &dyn SimctlBackend,
+) -> Result<HashMap<String, Runtime>, SimctlError> {
+    // exec `xcrun simctl runtime list --json` and parse the output
+    let output = exec(backend, &["runtime", "list", "--json"]).await?;
+    let map: HashMap<String, Runtime> = serde_json::from_str(&output)?;
+    Ok(map)
+}
+
+/**
+ * the runtimes chosen for the SDKs of the selected Xcode, key: sdk name, e.g. "iphoneos17.5"
+ */
+pub async fn get_runtime_matches(
+    backend: &dyn SimctlBackend,
+) -> Result<HashMap<String, RuntimeMatch>, SimctlError> {
+    // exec `xcrun simctl runtime match list --json` and parse the output
+    let output = exec(backend, &["runtime", "match", "list", "--json"]).await?;
+    let map: HashMap<String, RuntimeMatch> = serde_json::from_str(&output)?;
+    Ok(map)
 }
 
-pub fn get_all_runtimes() -> HashMap<String, Runtime> {
-    // exec `xcrun simctl list --json runtimes` and parse the output
-    let output = std::process::Command::new("xcrun")
-        .arg("simctl")
-        .arg("runtime")
-        .arg("list")
-        .arg("--json")
-        .output()
-        .expect("failed to execute process");
-    let output = String::from_utf8(output.stdout).unwrap();
-    let map: HashMap<String, Runtime> = serde_json::from_str(&output).unwrap_or_else(|_| HashMap::new());
-    map
+/**
+ * build the dyld shared cache of the runtime, or of all runtimes if identifier is None
+ */
+pub async fn update_dyld_shared_cache(
+    backend: &dyn SimctlBackend,
+    identifier: Option<&str>,
+    cancel: Option<CancellationToken>,
+) -> Result<String, SimctlError> {
+    // exec `xcrun simctl runtime dyld_shared_cache update <identifier|--all>`
+    let target = identifier.unwrap_or("--all");
+    exec_cancellable(
+        backend,
+        &["runtime", "dyld_shared_cache", "update", target],
+        cancel,
+    )
+    .await
 }
 
-pub fn delete_runtime(app: tauri::AppHandle, id: String) -> Result<(), String> {
+pub async fn delete_runtime(
+    backend: &dyn SimctlBackend,
+    id: String,
+    cancel: Option<CancellationToken>,
+) -> Result<(), SimctlError> {
     debug_println!("delete simulator runtime: {}", id);
-    // exec `xcrun simctl    delete the runtime
-    let output = std::process::Command::new("xcrun")
-        .arg("simctl")
-        .arg("runtime")
-        .arg("delete")
-        .arg(id)
-        .output()
-        .expect("failed to execute process");
-    let output = String::from_utf8(output.stdout).unwrap();
-    println!("{}", output);
+    // exec `xcrun simctl runtime delete <id>` to delete the runtime
+    let output = exec_cancellable(backend, &["runtime", "delete", id.as_str()], cancel).await?;
+    info!("delete runtime: {}", output);
+    Ok(())
+}
+
+pub async fn get_all_device_types(
+    backend: &dyn SimctlBackend,
+) -> Result<Vec<DeviceType>, SimctlError> {
+    // exec `xcrun simctl list --json devicetypes` and parse the output
+    let output = exec(backend, &["list", "--json", "devicetypes"]).await?;
+    let list: DeviceTypeList = serde_json::from_str(&output)?;
+    Ok(list.devicetypes)
+}
+
+pub async fn create_device(
+    backend: &dyn SimctlBackend,
+    name: &str,
+    device_type: &str,
+    runtime: Option<&str>,
+) -> Result<String, SimctlError> {
+    // exec `xcrun simctl create <name> <device_type> [<runtime>]`, the udid of the new device is printed
+    let mut args = vec!["create", name, device_type];
+    if let Some(runtime) = runtime {
+        args.push(runtime);
+    }
+    let output = exec(backend, &args).await?;
+    Ok(output.trim().to_string())
+}
+
+pub async fn clone_device(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    name: &str,
+) -> Result<String, SimctlError> {
+    // exec `xcrun simctl clone <udid> <name>`, the udid of the new device is printed
+    let output = exec(backend, &["clone", udid, name]).await?;
+    Ok(output.trim().to_string())
+}
+
+pub async fn rename_device(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    name: &str,
+) -> Result<(), SimctlError> {
+    // exec `xcrun simctl rename <udid> <name>`
+    exec(backend, &["rename", udid, name]).await?;
+    Ok(())
+}
+
+pub async fn delete_device(backend: &dyn SimctlBackend, udid: &str) -> Result<(), SimctlError> {
+    // exec `xcrun simctl delete <udid>`
+    exec(backend, &["delete", udid]).await?;
     Ok(())
 }
 
+/**
+ * find the runtime identifier by version or identifier, e.g. "17.5" -> "com.apple.CoreSimulator.SimRuntime.iOS-17-5"
+ */
+fn find_runtime_identifier(runtimes: &HashMap<String, Runtime>, runtime: &str) -> Option<String> {
+    runtimes
+        .values()
+        .find(|r| r.version == runtime || r.runtime_identifier == runtime)
+        .map(|r| r.runtime_identifier.clone())
+}
+
+/**
+ * create the devices in the matrix, devices with the same name and runtime are kept
+ */
+pub async fn provision_devices(
+    backend: &dyn SimctlBackend,
+    specs: Vec<DeviceSpec>,
+) -> Result<Vec<ProvisionResult>, SimctlError> {
+    let devices = get_all_devices(backend).await?;
+    let runtimes = get_all_runtimes(backend).await?;
+    let mut results = vec![];
+    for spec in specs {
+        let name = spec.device_name();
+        let runtime = match spec.runtime.as_ref() {
+            Some(runtime) => match find_runtime_identifier(&runtimes, runtime) {
+                Some(id) => Some(id),
+                None => {
+                    results.push(ProvisionResult {
+                        spec: spec.clone(),
+                        udid: None,
+                        existed: false,
+                        error: Some(format!("runtime not installed: {}", runtime)),
+                    });
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let existing = devices
+            .devices
+            .iter()
+            .filter(|(key, _)| runtime.as_ref().map(|r| r == *key).unwrap_or(true))
+            .find_map(|(_, v)| v.iter().find(|d| d.name == name));
+        if let Some(device) = existing {
+            results.push(ProvisionResult {
+                spec: spec.clone(),
+                udid: Some(device.udid.clone()),
+                existed: true,
+                error: None,
+            });
+            continue;
+        }
+        let result = create_device(backend, &name, &spec.device_type, runtime.as_deref()).await;
+        results.push(ProvisionResult {
+            spec,
+            udid: result.as_ref().ok().cloned(),
+            existed: false,
+            error: result.err().map(|e| e.to_string()),
+        });
+    }
+    Ok(results)
+}
 
-pub fn boot_device(udid: &str) {
+pub async fn add_runtime(
+    backend: &dyn SimctlBackend,
+    path: &str,
+    cancel: Option<CancellationToken>,
+) -> Result<String, SimctlError> {
+    // exec `xcrun simctl runtime add <path>` to install the runtime from a dmg
+    exec_cancellable(backend, &["runtime", "add", path], cancel).await
+}
+
+pub async fn boot_device(backend: &dyn SimctlBackend, udid: &str) -> Result<(), SimctlError> {
     // exec `xcrun simctl boot <udid>` to boot the device
-    let output = std::process::Command::new("xcrun")
-        .arg("simctl")
-        .arg("boot")
-        .arg(udid)
-        .output()
-        .expect("failed to execute process");
-    let output = String::from_utf8(output.stdout).unwrap();
-    println!("boot device {}", output);
+    let output = exec(backend, &["boot", udid]).await?;
+    debug_println!("boot device {}", output);
+    Ok(())
 }
 
-pub fn shutdown_device(udid: &str) {
+pub async fn shutdown_device(backend: &dyn SimctlBackend, udid: &str) -> Result<(), SimctlError> {
     // exec `xcrun simctl shutdown <udid>` to shutdown the device
-    let output = std::process::Command::new("xcrun")
-        .arg("simctl")
-        .arg("shutdown")
-        .arg(udid)
-        .output()
-        .expect("failed to execute process");
-    let output = String::from_utf8(output.stdout).unwrap();
-    println!("{}", output);
+    exec(backend, &["shutdown", udid]).await?;
+    Ok(())
 }
 
-pub fn erase_device(udid: &str) {
+pub async fn erase_device(backend: &dyn SimctlBackend, udid: &str) -> Result<(), SimctlError> {
     // exec `xcrun simctl erase <udid>` to erase the device
-    let output = std::process::Command::new("xcrun")
-        .arg("simctl")
-        .arg("erase")
-        .arg(udid)
-        .output()
-        .expect("failed to execute process");
-    let output = String::from_utf8(output.stdout).unwrap();
-    println!("{}", output);
+    exec(backend, &["erase", udid]).await?;
+    Ok(())
 }
 
-pub fn install_app(udid: &str, app_path: &str) {
+pub async fn install_app(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    app_path: &str,
+) -> Result<(), SimctlError> {
     // exec `xcrun simctl install <udid> <app_path>` to install the app
-    let output = std::process::Command::new("xcrun")
-        .arg("simctl")
-        .arg("install")
-        .arg(udid)
-        .arg(app_path)
-        .output()
-        .expect("failed to install app");
-    let output = String::from_utf8(output.stdout).unwrap();
+    let output = exec(backend, &["install", udid, app_path]).await?;
     info!("install app: {}", output);
-    println!("{}", output);
+    Ok(())
 }
 
-pub fn uninstall_app(udid: &str, bundle_id: &str) {
+pub async fn uninstall_app(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    bundle_id: &str,
+) -> Result<(), SimctlError> {
     // exec `xcrun simctl uninstall <udid> <bundle_id>` to uninstall the app
-    let output = std::process::Command::new("xcrun")
-        .arg("simctl")
-        .arg("uninstall")
-        .arg(udid)
-        .arg(bundle_id)
-        .output()
-        .expect("failed to execute process");
-    let output = String::from_utf8(output.stdout).unwrap();
-    println!("{}", output);
-}
-
-pub fn launch_app(udid: &str, bundle_id: &str) {
+    exec(backend, &["uninstall", udid, bundle_id]).await?;
+    Ok(())
+}
+
+pub async fn launch_app(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    bundle_id: &str,
+) -> Result<(), SimctlError> {
     // exec `xcrun simctl launch <udid> <bundle_id>` to launch the app
-    let output = std::process::Command::new("xcrun")
-        .arg("simctl")
-        .arg("launch")
-        .arg(udid)
-        .arg(bundle_id)
-        .output()
-        .expect("failed to execute process");
-    let output = String::from_utf8(output.stdout).unwrap();
-    println!("{}", output);
-}
-
-pub fn terminate_app(udid: &str, bundle_id: &str) {
+    let output = exec(backend, &["launch", udid, bundle_id]).await?;
+    debug_println!("launch app {}", output);
+    Ok(())
+}
+
+pub async fn terminate_app(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    bundle_id: &str,
+) -> Result<(), SimctlError> {
     // exec `xcrun simctl terminate <udid> <bundle_id>` to terminate the app
-    let output = std::process::Command::new("xcrun")
-        .arg("simctl")
-        .arg("terminate")
-        .arg(udid)
-        .arg(bundle_id)
-        .output()
-        .expect("failed to execute process");
-    let output = String::from_utf8(output.stdout).unwrap();
-    println!("{}", output);
-}
-
-pub fn open_url(udid: &str, url: &str) {
+    exec(backend, &["terminate", udid, bundle_id]).await?;
+    Ok(())
+}
+
+pub async fn open_url(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    url: &str,
+) -> Result<(), SimctlError> {
     debug_println!("open url: {} {}", udid, url);
     // exec `xcrun simctl openurl <udid> <url>` to open the url
-    let output = std::process::Command::new("xcrun")
-        .arg("simctl")
-        .arg("openurl")
-        .arg(udid)
-        .arg(url)
-        .output()
-        .expect("failed to execute process");
-    let output = String::from_utf8(output.stdout).unwrap();
-    println!("{}", output);
-}
-
-pub fn list_apps(udid: &str) {
-    // exec `xcrun simctl listapps <udid>` to list the apps
-    let output = std::process::Command::new("xcrun")
-        .arg("simctl")
-        .arg("listapps")
-        .arg(udid)
-        .output()
-        .expect("failed to execute process");
-    let output = String::from_utf8(output.stdout).unwrap();
-    println!("{}", output);
+    exec(backend, &["openurl", udid, url]).await?;
+    Ok(())
+}
+
+pub async fn list_apps(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+) -> Result<Vec<InstalledApp>, SimctlError> {
+    // exec `xcrun simctl listapps <udid>` to list the apps, the output is an old-style plist
+    let output = exec(backend, &["listapps", udid]).await?;
+    parse_listapps(&output)
+}
+
+/**
+ * get the path of an app container
+ * `container` is "app", "data", "groups" or an app group id
+ */
+pub async fn get_app_container(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    bundle_id: &str,
+    container: &str,
+) -> Result<String, SimctlError> {
+    // exec `xcrun simctl get_app_container <udid> <bundle_id> <container>`
+    let output = exec(backend, &["get_app_container", udid, bundle_id, container]).await?;
+    Ok(output.trim().to_string())
+}
+
+pub async fn screenshot(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    path: &str,
+    format: ScreenshotFormat,
+    mask: Option<MaskPolicy>,
+) -> Result<(), SimctlError> {
+    // exec `xcrun simctl io <udid> screenshot --type=<format> [--mask=<mask>] <path>`
+    let format = format!("--type={}", format);
+    let mut args = vec!["io", udid, "screenshot", format.as_str()];
+    let mask = mask.map(|m| format!("--mask={}", m));
+    if let Some(mask) = mask.as_ref() {
+        args.push(mask.as_str());
+    }
+    args.push(path);
+    exec(backend, &args).await?;
+    Ok(())
+}
+
+pub async fn add_media(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    path: &str,
+) -> Result<(), SimctlError> {
+    // exec `xcrun simctl addmedia <udid> <path>` to add a photo, video or contact
+    exec(backend, &["addmedia", udid, path]).await?;
+    Ok(())
+}
+
+/**
+ * write the text into the pasteboard of the device
+ */
+pub async fn pbcopy(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    content: &str,
+) -> Result<(), SimctlError> {
+    // exec `xcrun simctl pbcopy <udid>`, the content is read from stdin
+    let args = ["pbcopy", udid];
+    let options = RunOptions::for_simctl(&args).with_stdin(content.to_string());
+    backend.run(&args, options).await?.into_stdout(&args)?;
+    Ok(())
+}
+
+/**
+ * read the text in the pasteboard of the device
+ */
+pub async fn pbpaste(backend: &dyn SimctlBackend, udid: &str) -> Result<String, SimctlError> {
+    // exec `xcrun simctl pbpaste <udid>`
+    exec(backend, &["pbpaste", udid]).await
+}
+
+pub async fn override_status_bar(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    status_bar: &StatusBarOverride,
+) -> Result<(), SimctlError> {
+    // exec `xcrun simctl status_bar <udid> override --time 9:41 ...`
+    let options = status_bar.override_args();
+    let mut args = vec!["status_bar", udid, "override"];
+    args.extend(options.iter().map(|a| a.as_str()));
+    exec(backend, &args).await?;
+    Ok(())
+}
+
+pub async fn clear_status_bar(backend: &dyn SimctlBackend, udid: &str) -> Result<(), SimctlError> {
+    // exec `xcrun simctl status_bar <udid> clear`
+    exec(backend, &["status_bar", udid, "clear"]).await?;
+    Ok(())
+}
+
+/**
+ * parse the value printed by `simctl ui`, None if it is "unknown" or "unsupported"
+ */
+fn parse_ui_value<T: std::str::FromStr>(output: &str) -> Option<T> {
+    output.trim().parse::<T>().ok()
+}
+
+pub async fn get_appearance(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+) -> Result<Option<Appearance>, SimctlError> {
+    // exec `xcrun simctl ui <udid> appearance`
+    let output = exec(backend, &["ui", udid, "appearance"]).await?;
+    Ok(parse_ui_value(&output))
+}
+
+pub async fn set_appearance(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    appearance: Appearance,
+) -> Result<(), SimctlError> {
+    // exec `xcrun simctl ui <udid> appearance <light|dark>`
+    let appearance = appearance.to_string();
+    exec(backend, &["ui", udid, "appearance", appearance.as_str()]).await?;
+    Ok(())
+}
+
+pub async fn get_content_size(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+) -> Result<Option<ContentSize>, SimctlError> {
+    // exec `xcrun simctl ui <udid> content_size`
+    let output = exec(backend, &["ui", udid, "content_size"]).await?;
+    Ok(parse_ui_value(&output))
+}
+
+pub async fn set_content_size(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    content_size: ContentSize,
+) -> Result<(), SimctlError> {
+    // exec `xcrun simctl ui <udid> content_size <size>`
+    let content_size = content_size.to_string();
+    exec(
+        backend,
+        &["ui", udid, "content_size", content_size.as_str()],
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn get_increase_contrast(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+) -> Result<Option<bool>, SimctlError> {
+    // exec `xcrun simctl ui <udid> increase_contrast`
+    let output = exec(backend, &["ui", udid, "increase_contrast"]).await?;
+    Ok(match output.trim() {
+        "enabled" => Some(true),
+        "disabled" => Some(false),
+        _ => None,
+    })
+}
+
+pub async fn set_increase_contrast(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    enabled: bool,
+) -> Result<(), SimctlError> {
+    // exec `xcrun simctl ui <udid> increase_contrast <enabled|disabled>`
+    let value = if enabled { "enabled" } else { "disabled" };
+    exec(backend, &["ui", udid, "increase_contrast", value]).await?;
+    Ok(())
+}
+
+pub async fn record_video(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    path: &str,
+    mask: Option<MaskPolicy>,
+) -> Result<Box<dyn SimctlProcess>, SimctlError> {
+    // spawn `xcrun simctl io <udid> recordVideo --codec=h264 [--mask=<mask>] <path>`
+    // the video is written until the process receives SIGINT
+    let mut args = vec!["io", udid, "recordVideo", "--codec=h264", "--force"];
+    let mask = mask.map(|m| format!("--mask={}", m));
+    if let Some(mask) = mask.as_ref() {
+        args.push(mask.as_str());
+    }
+    args.push(path);
+    backend.spawn(&args).await
+}
+
+pub async fn send_push(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    bundle_id: &str,
+    payload: &serde_json::Value,
+) -> Result<(), SimctlError> {
+    // exec `xcrun simctl push <udid> <bundle_id> <payload file>`
+    let path = std::env::temp_dir().join(format!(
+        "costa_push_{}.apns",
+        chrono::Local::now().timestamp_millis()
+    ));
+    std::fs::write(&path, payload.to_string())?;
+    let path_str = path.to_string_lossy().to_string();
+    let result = exec(backend, &["push", udid, bundle_id, path_str.as_str()]).await;
+    let _ = std::fs::remove_file(&path);
+    result?;
+    Ok(())
+}
+
+pub async fn set_location(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    coordinate: &Coordinate,
+) -> Result<(), SimctlError> {
+    // exec `xcrun simctl location <udid> set <lat>,<lon>`
+    let coordinate = coordinate.to_arg();
+    exec(backend, &["location", udid, "set", coordinate.as_str()]).await?;
+    Ok(())
+}
+
+/**
+ * grant, revoke or reset the permission of the app
+ * reset without a bundle id applies to all apps
+ */
+pub async fn set_privacy(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    action: PrivacyAction,
+    service: PrivacyService,
+    bundle_id: Option<&str>,
+) -> Result<(), SimctlError> {
+    // exec `xcrun simctl privacy <udid> <action> <service> [<bundle_id>]`
+    let action = action.to_string();
+    let service = service.to_string();
+    let mut args = vec!["privacy", udid, action.as_str(), service.as_str()];
+    if let Some(bundle_id) = bundle_id {
+        args.push(bundle_id);
+    }
+    exec(backend, &args).await?;
+    Ok(())
+}
+
+/**
+ * stop any running location simulation and clear the location
+ */
+pub async fn clear_location(backend: &dyn SimctlBackend, udid: &str) -> Result<(), SimctlError> {
+    // exec `xcrun simctl location <udid> clear`
+    exec(backend, &["location", udid, "clear"]).await?;
+    Ok(())
+}
+
+/**
+ * get the predefined scenarios of `simctl location run`, e.g. "Freeway Drive"
+ */
+pub async fn list_location_scenarios(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+) -> Result<Vec<String>, SimctlError> {
+    // exec `xcrun simctl location <udid> list`
+    let output = exec(backend, &["location", udid, "list"]).await?;
+    Ok(output
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect())
+}
+
+pub async fn run_location_scenario(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    scenario: &str,
+) -> Result<(), SimctlError> {
+    // exec `xcrun simctl location <udid> run <scenario>`
+    exec(backend, &["location", udid, "run", scenario]).await?;
+    Ok(())
+}
+
+/**
+ * move the device along the waypoints at `speed` meters per second
+ * the simulation runs in the simulator until it is cleared
+ */
+pub async fn start_location_route(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    points: &[Coordinate],
+    speed: Option<f64>,
+) -> Result<(), SimctlError> {
+    // exec `xcrun simctl location <udid> start --speed=<speed> <lat1>,<lon1> <lat2>,<lon2> ...`
+    let mut args = vec![
+        "location".to_string(),
+        udid.to_string(),
+        "start".to_string(),
+    ];
+    if let Some(speed) = speed {
+        args.push(format!("--speed={}", speed));
+    }
+    args.extend(points.iter().map(|p| p.to_arg()));
+    let args = args.iter().map(|a| a.as_str()).collect::<Vec<_>>();
+    exec(backend, &args).await?;
+    Ok(())
 }
 
 /**
@@ -186,7 +632,9 @@ pub fn list_apps(udid: &str) {
  * output is a JSON array
  * try to activate Safari and close the front window
  */
-fn find_all_menu_item_in_dev_tool() -> String {
+async fn find_all_menu_item_in_dev_tool(
+    backend: &dyn SimctlBackend,
+) -> Result<String, SimctlError> {
     let apple_script = r#"
     tell application "Safari"
         activate
@@ -234,37 +682,29 @@ fn find_all_menu_item_in_dev_tool() -> String {
     end tell
     "#;
     // exec `osascript -e 'tell application "System Events" to tell process "Safari" to get entire contents of menu bar 1'` to find all menu items in Safari Dev Tool
-    let output = std::process::Command::new("osascript")
-        .arg("-e")
-        .arg(apple_script)
-        .output()
-        .expect("failed to execute process");
-    let output = String::from_utf8(output.stdout).unwrap();
-    println!("{}", output);
-    output
+    exec_program(backend, "osascript", &["-e", apple_script]).await
 }
 
-pub fn find_all_web_view_windows_in_simultor(simulator: &str) -> String {
+pub async fn find_all_web_view_windows_in_simultor(
+    backend: &dyn SimctlBackend,
+    simulator: &str,
+) -> Result<String, SimctlError> {
     let apple_script = r#"
 
-    "#
-    .to_owned();
+    "#;
     // exec `osascript -e 'tell application "System Events" to tell process "Simulator" to get entire contents of menu bar 1'` to find all menu items in Safari Dev Tool
-    let output = std::process::Command::new("osascript")
-        .arg("-e")
-        .arg(apple_script)
-        .output()
-        .expect("failed to execute process");
-    let output = String::from_utf8(output.stdout).unwrap();
-    println!("{}", output);
-    output
-}
-
-pub fn open_safari_dev_tool(udid: &str, web_view_name: Option<&str>) {
-    let all_menu_items = find_all_menu_item_in_dev_tool();
-    let all_menu_items: Vec<String> = serde_json::from_str(&all_menu_items).unwrap();
-    let all_devices = get_all_devices();
-    let device = all_devices.get_device_by_udid(udid).unwrap();
+    debug_println!("find web view windows in {}", simulator);
+    exec_program(backend, "osascript", &["-e", apple_script]).await
+}
+
+pub async fn open_safari_dev_tool(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    web_view_name: Option<&str>,
+) -> Result<(), SimctlError> {
+    let all_menu_items = find_all_menu_item_in_dev_tool(backend).await?;
+    let all_menu_items: Vec<String> = serde_json::from_str(&all_menu_items)?;
+    let device = get_device(backend, udid).await?;
     let simulator = device.name.clone();
     let window = web_view_name.unwrap_or("WebView");
     // var1: ${simulator}
@@ -307,20 +747,16 @@ pub fn open_safari_dev_tool(udid: &str, web_view_name: Option<&str>) {
     .to_owned()
     .replace("${simulator}", &simulator)
     .replace("${window}", window);
-    let output = std::process::Command::new("osascript")
-        .arg("-e")
-        .arg(apple_script)
-        .output()
-        .expect("failed to execute process");
-    let output = String::from_utf8(output.stdout).unwrap();
-    println!("{}", output);
-}
-
-pub fn open_simulator_app() {
-    let output = std::process::Command::new("open")
-        .arg("/Applications/Xcode.app/Contents/Developer/Applications/Simulator.app")
-        .output()
-        .expect("failed to execute process");
-    let output = String::from_utf8(output.stdout).unwrap();
-    println!("open simulator app {}", output);
+    exec_program(backend, "osascript", &["-e", &apple_script]).await?;
+    Ok(())
+}
+
+pub async fn open_simulator_app(backend: &dyn SimctlBackend) -> Result<(), SimctlError> {
+    exec_program(
+        backend,
+        "open",
+        &["/Applications/Xcode.app/Contents/Developer/Applications/Simulator.app"],
+    )
+    .await?;
+    Ok(())
 }
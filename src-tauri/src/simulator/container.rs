@@ -0,0 +1,423 @@
+/**
+ * This module browses the sandbox containers of the simulator apps by `xcrun simctl get_app_container`.
+ * Every path from the webview is relative to the container root and must stay inside it.
+ */
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{backend::SimctlBackend, command, error::SimctlError};
+
+/** files larger than this are not read into the webview */
+pub const MAX_READ_BYTES: u64 = 10 * 1024 * 1024;
+
+/**
+ * A container of the app, see `xcrun simctl get_app_container --help`
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase", tag = "kind", content = "id")]
+pub enum ContainerKind {
+    /** the .app bundle */
+    App,
+    /** Documents, Library, tmp */
+    Data,
+    /** an app group container */
+    Group(String),
+}
+
+impl fmt::Display for ContainerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContainerKind::App => write!(f, "app"),
+            ContainerKind::Data => write!(f, "data"),
+            ContainerKind::Group(id) => write!(f, "{}", id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContainerError {
+    /** the path is absolute or goes above the container root */
+    OutsideContainer(String),
+    NotFound(String),
+    TooLarge {
+        size: u64,
+        limit: u64,
+    },
+    Io(String),
+    Simctl(String),
+}
+
+impl fmt::Display for ContainerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContainerError::OutsideContainer(path) => {
+                write!(f, "path is outside the container: {}", path)
+            }
+            ContainerError::NotFound(path) => write!(f, "file not found: {}", path),
+            ContainerError::TooLarge { size, limit } => {
+                write!(f, "file is {} bytes, the limit is {} bytes", size, limit)
+            }
+            ContainerError::Io(e) => write!(f, "{}", e),
+            ContainerError::Simctl(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ContainerError {}
+
+impl From<std::io::Error> for ContainerError {
+    fn from(e: std::io::Error) -> Self {
+        ContainerError::Io(e.to_string())
+    }
+}
+
+impl From<SimctlError> for ContainerError {
+    fn from(e: SimctlError) -> Self {
+        ContainerError::Simctl(e.to_string())
+    }
+}
+
+impl From<ContainerError> for String {
+    fn from(e: ContainerError) -> Self {
+        e.to_string()
+    }
+}
+
+/**
+ * get the root of the container
+ */
+pub async fn get_container_root(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    bundle_id: &str,
+    kind: &ContainerKind,
+) -> Result<PathBuf, ContainerError> {
+    let path =
+        command::get_app_container(backend, udid, bundle_id, kind.to_string().as_str()).await?;
+    Ok(PathBuf::from(path))
+}
+
+/**
+ * get the app group containers, key: group id, value: container path
+ */
+pub async fn get_group_containers(
+    backend: &dyn SimctlBackend,
+    udid: &str,
+    bundle_id: &str,
+) -> Result<HashMap<String, String>, ContainerError> {
+    // each line is `<group id>\t<path>`
+    let output = command::get_app_container(backend, udid, bundle_id, "groups").await?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let (id, path) = line.trim().split_once(char::is_whitespace)?;
+            Some((id.to_string(), path.trim().to_string()))
+        })
+        .collect())
+}
+
+/**
+ * join the relative path to the root, reject absolute paths & `..` which go above the root
+ * symlinks are resolved for existing paths, so a link cannot point outside the container either,
+ * a dangling link is rejected since writing to it creates its target
+ */
+pub fn resolve_in_container(root: &Path, relative: &str) -> Result<PathBuf, ContainerError> {
+    let outside = || ContainerError::OutsideContainer(relative.to_string());
+    let mut resolved = PathBuf::new();
+    for component in Path::new(relative).components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return Err(outside());
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return Err(outside()),
+        }
+    }
+    let path = root.join(resolved);
+    // check the real path of the deepest existing ancestor, `exists` follows the links
+    let root = root.canonicalize()?;
+    let mut existing = path.as_path();
+    while existing.symlink_metadata().is_err() {
+        existing = existing.parent().ok_or_else(outside)?;
+    }
+    let real = existing.canonicalize().map_err(|_| outside())?;
+    if !real.starts_with(&root) {
+        return Err(outside());
+    }
+    Ok(path)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ContainerEntry {
+    pub name: String,
+    /** path relative to the container root */
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    /** unix timestamp in milliseconds */
+    pub modified: Option<u128>,
+}
+
+/**
+ * list a directory of the container, directories first
+ */
+pub fn list_dir(root: &Path, relative: &str) -> Result<Vec<ContainerEntry>, ContainerError> {
+    let dir = resolve_in_container(root, relative)?;
+    if !dir.is_dir() {
+        return Err(ContainerError::NotFound(relative.to_string()));
+    }
+    let mut entries = vec![];
+    for entry in std::fs::read_dir(&dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let path = entry.path();
+        entries.push(ContainerEntry {
+            name: entry.file_name().to_string_lossy().to_string(),
+            path: path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string(),
+            is_dir: metadata.is_dir(),
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis()),
+        });
+    }
+    entries.sort_by(|a, b| (!a.is_dir, &a.name).cmp(&(!b.is_dir, &b.name)));
+    Ok(entries)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "lowercase", tag = "type", content = "content")]
+pub enum FileContent {
+    /** a plist converted to JSON */
+    Plist(Value),
+    Text(String),
+    /** a binary file, only the size is returned */
+    Binary(u64),
+}
+
+/**
+ * convert a plist value to JSON, data is encoded as hex and dates as RFC 3339 strings
+ */
+pub fn plist_to_json(value: &plist::Value) -> Value {
+    match value {
+        plist::Value::Array(items) => Value::Array(items.iter().map(plist_to_json).collect()),
+        plist::Value::Dictionary(dict) => Value::Object(
+            dict.iter()
+                .map(|(k, v)| (k.clone(), plist_to_json(v)))
+                .collect(),
+        ),
+        plist::Value::Boolean(b) => json!(b),
+        plist::Value::Data(data) => {
+            json!(data
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>())
+        }
+        plist::Value::Date(date) => {
+            json!(
+                chrono::DateTime::<chrono::Utc>::from(std::time::SystemTime::from(*date))
+                    .to_rfc3339()
+            )
+        }
+        plist::Value::Real(f) => json!(f),
+        plist::Value::Integer(i) => match i.as_signed() {
+            Some(i) => json!(i),
+            None => json!(i.as_unsigned()),
+        },
+        plist::Value::String(s) => json!(s),
+        plist::Value::Uid(uid) => json!(uid.get()),
+        _ => Value::Null,
+    }
+}
+
+/**
+ * read a file of the container, plists (binary or XML) are converted to JSON
+ */
+pub fn read_file(root: &Path, relative: &str) -> Result<FileContent, ContainerError> {
+    let path = resolve_in_container(root, relative)?;
+    if !path.is_file() {
+        return Err(ContainerError::NotFound(relative.to_string()));
+    }
+    let size = path.metadata()?.len();
+    if size > MAX_READ_BYTES {
+        return Err(ContainerError::TooLarge {
+            size,
+            limit: MAX_READ_BYTES,
+        });
+    }
+    let bytes = std::fs::read(&path)?;
+    if let Ok(value) = plist::Value::from_reader(std::io::Cursor::new(&bytes)) {
+        return Ok(FileContent::Plist(plist_to_json(&value)));
+    }
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok(FileContent::Text(text)),
+        Err(_) => Ok(FileContent::Binary(size)),
+    }
+}
+
+/**
+ * copy a file of the container to the host, return the destination path
+ * the file keeps its name if `destination` is a directory
+ */
+pub fn copy_out(root: &Path, relative: &str, destination: &str) -> Result<String, ContainerError> {
+    let source = resolve_in_container(root, relative)?;
+    if !source.is_file() {
+        return Err(ContainerError::NotFound(relative.to_string()));
+    }
+    let mut destination = PathBuf::from(destination);
+    if destination.is_dir() {
+        destination.push(source.file_name().unwrap_or_default());
+    }
+    std::fs::copy(&source, &destination)?;
+    Ok(destination.to_string_lossy().to_string())
+}
+
+/**
+ * copy a host file into a directory of the container, return the relative path of the copy
+ */
+pub fn copy_in(root: &Path, source: &str, relative_dir: &str) -> Result<String, ContainerError> {
+    let source = Path::new(source);
+    if !source.is_file() {
+        return Err(ContainerError::NotFound(
+            source.to_string_lossy().to_string(),
+        ));
+    }
+    let file_name = source
+        .file_name()
+        .ok_or_else(|| ContainerError::NotFound(source.to_string_lossy().to_string()))?;
+    let dir = resolve_in_container(root, relative_dir)?;
+    std::fs::create_dir_all(&dir)?;
+    // resolve the file again, an existing symlink with the same name must not point outside
+    let relative = Path::new(relative_dir).join(file_name);
+    let destination = resolve_in_container(root, &relative.to_string_lossy())?;
+    std::fs::copy(source, &destination)?;
+    Ok(destination
+        .strip_prefix(root)
+        .unwrap_or(&destination)
+        .to_string_lossy()
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    /** a container root & a directory outside of it, removed when dropped */
+    struct Sandbox {
+        dir: TempDir,
+    }
+
+    impl Sandbox {
+        fn new(name: &str) -> Self {
+            let dir = TempDir::new(name);
+            std::fs::create_dir_all(dir.join("container/Documents")).unwrap();
+            std::fs::create_dir_all(dir.join("outside")).unwrap();
+            Self { dir }
+        }
+        fn root(&self) -> PathBuf {
+            self.dir.join("container")
+        }
+        fn outside(&self) -> PathBuf {
+            self.dir.join("outside")
+        }
+    }
+
+    #[test]
+    fn resolves_paths_inside_the_root() {
+        let sandbox = Sandbox::new("container-inside");
+        let root = sandbox.root();
+        assert_eq!(
+            resolve_in_container(&root, "Documents/./new/../file.txt").unwrap(),
+            root.join("Documents/file.txt")
+        );
+        assert_eq!(
+            resolve_in_container(&root, "Library/Caches/a/b").unwrap(),
+            root.join("Library/Caches/a/b")
+        );
+        symlink(root.join("Documents"), root.join("docs")).unwrap();
+        assert_eq!(
+            resolve_in_container(&root, "docs/file.txt").unwrap(),
+            root.join("docs/file.txt")
+        );
+    }
+
+    #[test]
+    fn rejects_paths_above_the_root() {
+        let sandbox = Sandbox::new("container-above");
+        let root = sandbox.root();
+        for relative in ["..", "Documents/../../outside", "/etc/hosts"] {
+            assert_eq!(
+                resolve_in_container(&root, relative),
+                Err(ContainerError::OutsideContainer(relative.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_links_to_the_outside() {
+        let sandbox = Sandbox::new("container-link");
+        let root = sandbox.root();
+        symlink(sandbox.outside(), root.join("Documents/escape")).unwrap();
+        assert!(resolve_in_container(&root, "Documents/escape").is_err());
+        assert!(resolve_in_container(&root, "Documents/escape/new.txt").is_err());
+    }
+
+    #[test]
+    fn rejects_dangling_links() {
+        let sandbox = Sandbox::new("container-dangling");
+        let root = sandbox.root();
+        let target = sandbox.outside().join("created.txt");
+        symlink(&target, root.join("Documents/report.txt")).unwrap();
+        assert_eq!(
+            resolve_in_container(&root, "Documents/report.txt"),
+            Err(ContainerError::OutsideContainer(
+                "Documents/report.txt".to_string()
+            ))
+        );
+        // the link is not written through by a copy with the same name
+        let source = sandbox.dir.join("report.txt");
+        std::fs::write(&source, "report").unwrap();
+        assert!(copy_in(&root, &source.to_string_lossy(), "Documents").is_err());
+        assert!(!target.exists());
+        // a dangling link to a missing directory is rejected as well
+        symlink(sandbox.outside().join("missing"), root.join("Library")).unwrap();
+        assert!(resolve_in_container(&root, "Library/Preferences/app.plist").is_err());
+        assert!(copy_in(&root, &source.to_string_lossy(), "Library/Preferences").is_err());
+        assert!(!sandbox.outside().join("missing").exists());
+    }
+
+    #[test]
+    fn copies_into_the_container() {
+        let sandbox = Sandbox::new("container-copy-in");
+        let root = sandbox.root();
+        let source = sandbox.dir.join("fixture.json");
+        std::fs::write(&source, "{}").unwrap();
+        assert_eq!(
+            copy_in(&root, &source.to_string_lossy(), "Documents/fixtures").unwrap(),
+            "Documents/fixtures/fixture.json"
+        );
+        assert_eq!(
+            std::fs::read_to_string(root.join("Documents/fixtures/fixture.json")).unwrap(),
+            "{}"
+        );
+    }
+}
@@ -1,8 +1,17 @@
 use debug_print::debug_println;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 
-use super::command::{open_url, list_apps, terminate_app, install_app, uninstall_app};
+use super::backend::{SimctlBackend, SimctlProcess};
+use super::capture::{new_capture_path, MaskPolicy, ScreenshotFormat};
+use super::app::InstalledApp;
+use super::command::{open_url, list_apps, launch_app, terminate_app, install_app, uninstall_app, screenshot, record_video, send_push, set_location, clear_location, start_location_route, set_privacy, override_status_bar, clear_status_bar, add_media};
+use super::location::Coordinate;
+use super::media::{validate_media, MediaResult};
+use super::privacy::{PrivacyAction, PrivacyService};
+use super::status_bar::StatusBarOverride;
+use super::error::SimctlError;
 
 /**
  * HashMap<String, Vec<Device>> is a map of devices grouped by version
@@ -19,6 +28,26 @@ impl DeviceMap {
     fn new(devices: HashMap<String, Vec<Device>>) -> Self {
         Self { devices }
     }
+    /**
+     * parse the output of `xcrun simctl list --json devices available`
+     * and set the os_version field using the runtime key of each group
+     */
+    pub fn from_simctl_json(json: &str) -> Result<Self, SimctlError> {
+        let mut devices: DeviceMap = serde_json::from_str(json)?;
+        for (key, device) in devices.devices.iter_mut() {
+            device.iter_mut().for_each(|d| {
+                d.os_version = Some(
+                    key.clone()
+                        .split(".")
+                        .collect::<Vec<&str>>()
+                        .pop()
+                        .unwrap_or("unknown system version")
+                        .to_string(),
+                );
+            });
+        }
+        Ok(devices)
+    }
     pub fn add_device(&mut self, version: &str, device: Device) {
         let devices = self.devices.entry(version.to_string()).or_insert(vec![]);
         devices.push(device);
@@ -38,11 +67,13 @@ impl DeviceMap {
             .values()
             .find_map(|v| v.iter().find(|d| d.udid == udid))
     }
-    pub fn get_default_device(&self) -> Option<&Device> {
-        self.devices.values().next().and_then(|v| {
-            // find name on iPhone 15 Pro
-            v.iter().find(|d| d.name == "iPhone 15 Pro")
-        })
+    /**
+     * find the device by name, the name is the default device preference, e.g. "iPhone 15 Pro"
+     */
+    pub fn get_default_device(&self, name: &str) -> Option<&Device> {
+        self.devices
+            .values()
+            .find_map(|v| v.iter().find(|d| d.name == name))
     }
     pub fn get_first_booted_device(&self) -> Option<&Device> {
         self.devices
@@ -120,20 +151,115 @@ impl Device {
             os_version: os_version,
         }
     }
-    pub fn open_url(&self, url: &str) {
-        open_url(&self.udid, url);
+    pub async fn open_url(&self, backend: &dyn SimctlBackend, url: &str) -> Result<(), SimctlError> {
+        open_url(backend, &self.udid, url).await
     }
-    pub fn list_apps(&self) {
-        list_apps(&self.udid);
+    pub async fn list_apps(&self, backend: &dyn SimctlBackend) -> Result<Vec<InstalledApp>, SimctlError> {
+        list_apps(backend, &self.udid).await
     }
-    pub fn terminate_app(&self, bundle_id: &str) {
-        terminate_app(&self.udid, bundle_id);
+    pub async fn launch_app(&self, backend: &dyn SimctlBackend, bundle_id: &str) -> Result<(), SimctlError> {
+        launch_app(backend, &self.udid, bundle_id).await
     }
-    pub fn install_app(&self, app_path: &str) {
+    pub async fn terminate_app(&self, backend: &dyn SimctlBackend, bundle_id: &str) -> Result<(), SimctlError> {
+        terminate_app(backend, &self.udid, bundle_id).await
+    }
+    pub async fn install_app(&self, backend: &dyn SimctlBackend, app_path: &str) -> Result<(), SimctlError> {
         debug_println!("installing app: {}", app_path);
-        install_app(&self.udid, app_path);
+        install_app(backend, &self.udid, app_path).await
+    }
+    pub async fn uninstall_app(&self, backend: &dyn SimctlBackend, bundle_id: &str) -> Result<(), SimctlError> {
+        uninstall_app(backend, &self.udid, bundle_id).await
+    }
+    pub async fn send_push(
+        &self,
+        backend: &dyn SimctlBackend,
+        bundle_id: &str,
+        payload: &serde_json::Value,
+    ) -> Result<(), SimctlError> {
+        send_push(backend, &self.udid, bundle_id, payload).await
+    }
+    pub async fn set_privacy(
+        &self,
+        backend: &dyn SimctlBackend,
+        bundle_id: &str,
+        service: PrivacyService,
+        action: PrivacyAction,
+    ) -> Result<(), SimctlError> {
+        set_privacy(backend, &self.udid, action, service, Some(bundle_id)).await
+    }
+    /**
+     * add the files to the photo library or contacts one by one
+     * unsupported files are skipped without invoking simctl
+     */
+    pub async fn add_media(&self, backend: &dyn SimctlBackend, paths: &[String]) -> Vec<MediaResult> {
+        let mut results = vec![];
+        for path in paths {
+            let result = match validate_media(std::path::Path::new(path)) {
+                Ok(kind) => add_media(backend, &self.udid, path)
+                    .await
+                    .map(|_| kind)
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e),
+            };
+            debug_println!("add media {}: {:?}", path, result);
+            results.push(MediaResult {
+                path: path.clone(),
+                kind: result.as_ref().ok().copied(),
+                error: result.err(),
+            });
+        }
+        results
+    }
+    pub async fn override_status_bar(
+        &self,
+        backend: &dyn SimctlBackend,
+        status_bar: &StatusBarOverride,
+    ) -> Result<(), SimctlError> {
+        override_status_bar(backend, &self.udid, status_bar).await
+    }
+    pub async fn clear_status_bar(&self, backend: &dyn SimctlBackend) -> Result<(), SimctlError> {
+        clear_status_bar(backend, &self.udid).await
+    }
+    pub async fn set_location(&self, backend: &dyn SimctlBackend, coordinate: &Coordinate) -> Result<(), SimctlError> {
+        set_location(backend, &self.udid, coordinate).await
+    }
+    pub async fn clear_location(&self, backend: &dyn SimctlBackend) -> Result<(), SimctlError> {
+        clear_location(backend, &self.udid).await
+    }
+    /**
+     * move along the route at `speed` meters per second, stop it by `clear_location`
+     */
+    pub async fn start_route(
+        &self,
+        backend: &dyn SimctlBackend,
+        points: &[Coordinate],
+        speed: Option<f64>,
+    ) -> Result<(), SimctlError> {
+        start_location_route(backend, &self.udid, points, speed).await
+    }
+    /**
+     * take a screenshot into the captures dir, return the file path
+     */
+    pub async fn screenshot(
+        &self,
+        backend: &dyn SimctlBackend,
+        format: ScreenshotFormat,
+        mask: Option<MaskPolicy>,
+    ) -> Result<PathBuf, SimctlError> {
+        let path = new_capture_path(self, &format.to_string())?;
+        screenshot(backend, &self.udid, &path.to_string_lossy(), format, mask).await?;
+        Ok(path)
     }
-    pub fn uninstall_app(&self, bundle_id: &str) {
-        uninstall_app(&self.udid, bundle_id);
+    /**
+     * start recording the screen into the captures dir, stop the returned process to finish the video
+     */
+    pub async fn start_recording(
+        &self,
+        backend: &dyn SimctlBackend,
+        mask: Option<MaskPolicy>,
+    ) -> Result<(Box<dyn SimctlProcess>, PathBuf), SimctlError> {
+        let path = new_capture_path(self, "mp4")?;
+        let process = record_video(backend, &self.udid, &path.to_string_lossy(), mask).await?;
+        Ok((process, path))
     }
 }
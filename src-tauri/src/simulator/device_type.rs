@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+// {
+//   "devicetypes" : [
+//     {
+//       "productFamily" : "iPhone",
+//       "bundlePath" : "\/Library\/Developer\/CoreSimulator\/Profiles\/DeviceTypes\/iPhone 15 Pro.simdevicetype",
+//       "maxRuntimeVersion" : 4294967295,
+//       "maxRuntimeVersionString" : "65535.255.255",
+//       "identifier" : "com.apple.CoreSimulator.SimDeviceType.iPhone-15-Pro",
+//       "modelIdentifier" : "iPhone16,1",
+//       "minRuntimeVersionString" : "17.0.0",
+//       "minRuntimeVersion" : 1114112,
+//       "name" : "iPhone 15 Pro"
+//     }
+//   ]
+// }
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DeviceTypeList {
+    #[serde(default)]
+    pub devicetypes: Vec<DeviceType>,
+}
+
+// all fields are optional
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct DeviceType {
+    pub identifier: String,
+    pub name: String,
+    #[serde(rename = "productFamily")]
+    pub product_family: String,
+    #[serde(rename = "bundlePath")]
+    pub bundle_path: Option<String>,
+    #[serde(rename = "modelIdentifier")]
+    pub model_identifier: Option<String>,
+    #[serde(rename = "minRuntimeVersionString")]
+    pub min_runtime_version: Option<String>,
+    #[serde(rename = "maxRuntimeVersionString")]
+    pub max_runtime_version: Option<String>,
+}
+
+/**
+ * A device to be provisioned, e.g. "iPhone 15 Pro" on "17.5"
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceSpec {
+    /** name of the new device, defaults to the device type name */
+    pub name: Option<String>,
+    /** device type name or identifier, e.g. "iPhone 15 Pro" */
+    #[serde(rename = "deviceType")]
+    pub device_type: String,
+    /** runtime version or identifier, e.g. "17.5", the newest runtime is used if None */
+    pub runtime: Option<String>,
+}
+
+impl DeviceSpec {
+    pub fn device_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.device_type.clone())
+    }
+}
+
+/**
+ * Result of provisioning a device spec
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvisionResult {
+    pub spec: DeviceSpec,
+    /** udid of the created or existing device */
+    pub udid: Option<String>,
+    /** true if the device already exists and nothing was created */
+    pub existed: bool,
+    pub error: Option<String>,
+}
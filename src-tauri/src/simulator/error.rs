@@ -0,0 +1,75 @@
+use std::fmt;
+
+/**
+ * Error of a simctl command
+ */
+#[derive(Debug, Clone)]
+pub enum SimctlError {
+    /** the process could not be spawned, e.g. xcrun is not installed */
+    Spawn(String),
+    /** the process exited with a non-zero status */
+    NonZeroExit {
+        command: String,
+        status: Option<i32>,
+        stderr: String,
+    },
+    /** the output of simctl could not be parsed */
+    Parse(String),
+    /** no device matches the given udid */
+    DeviceNotFound(String),
+    /** the process did not finish in time */
+    Timeout(String),
+    /** the process was cancelled by the user */
+    Cancelled(String),
+}
+
+impl fmt::Display for SimctlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimctlError::Spawn(e) => write!(f, "failed to execute simctl: {}", e),
+            SimctlError::NonZeroExit {
+                command,
+                status,
+                stderr,
+            } => match status {
+                Some(code) => write!(
+                    f,
+                    "`{}` exited with status {}: {}",
+                    command,
+                    code,
+                    stderr.trim()
+                ),
+                None => write!(
+                    f,
+                    "`{}` was terminated by a signal: {}",
+                    command,
+                    stderr.trim()
+                ),
+            },
+            SimctlError::Parse(e) => write!(f, "failed to parse simctl output: {}", e),
+            SimctlError::DeviceNotFound(udid) => write!(f, "device not found: {}", udid),
+            SimctlError::Timeout(command) => write!(f, "`{}` timed out", command),
+            SimctlError::Cancelled(command) => write!(f, "`{}` was cancelled", command),
+        }
+    }
+}
+
+impl std::error::Error for SimctlError {}
+
+impl From<std::io::Error> for SimctlError {
+    fn from(e: std::io::Error) -> Self {
+        SimctlError::Spawn(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for SimctlError {
+    fn from(e: serde_json::Error) -> Self {
+        SimctlError::Parse(e.to_string())
+    }
+}
+
+impl From<SimctlError> for String {
+    fn from(e: SimctlError) -> Self {
+        e.to_string()
+    }
+}
@@ -0,0 +1,647 @@
+/**
+ * This module installs .app, .app.zip & .ipa packages into the simulator.
+ * Archives are extracted in-process into `~/.costa/install_cache`, the Info.plist is checked against the device,
+ * and every stage is reported to the webview by the `install_progress` event.
+ */
+use std::{
+    fmt,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use debug_print::debug_println;
+use log::error;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::path::get_install_cache_dir;
+
+use super::{app, backend::get_simctl_backend, device::Device, error::SimctlError, privacy};
+
+/** platform name of the simulator builds in CFBundleSupportedPlatforms */
+pub const SIMULATOR_PLATFORM: &str = "iPhoneSimulator";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstallError {
+    /** the package is not a .app directory, .zip or .ipa */
+    UnsupportedPackage(String),
+    Extract(String),
+    /** no .app bundle is found in the package */
+    BundleNotFound(String),
+    InvalidInfoPlist(String),
+    /** the app is built for devices, not for the simulator */
+    NotSimulatorBuild(Vec<String>),
+    /** the device runs an older iOS than MinimumOSVersion */
+    Incompatible {
+        min_os: String,
+        device_os: String,
+    },
+    Simctl(String),
+}
+
+impl fmt::Display for InstallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstallError::UnsupportedPackage(path) => write!(f, "unsupported package: {}", path),
+            InstallError::Extract(e) => write!(f, "failed to extract the package: {}", e),
+            InstallError::BundleNotFound(path) => write!(f, "no .app bundle found in {}", path),
+            InstallError::InvalidInfoPlist(e) => write!(f, "invalid Info.plist: {}", e),
+            InstallError::NotSimulatorBuild(platforms) => write!(
+                f,
+                "the app is built for {}, not for the simulator",
+                platforms.join(", ")
+            ),
+            InstallError::Incompatible { min_os, device_os } => write!(
+                f,
+                "the app requires iOS {}, the device runs {}",
+                min_os, device_os
+            ),
+            InstallError::Simctl(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for InstallError {}
+
+impl From<std::io::Error> for InstallError {
+    fn from(e: std::io::Error) -> Self {
+        InstallError::Extract(e.to_string())
+    }
+}
+
+impl From<zip::result::ZipError> for InstallError {
+    fn from(e: zip::result::ZipError) -> Self {
+        InstallError::Extract(e.to_string())
+    }
+}
+
+impl From<SimctlError> for InstallError {
+    fn from(e: SimctlError) -> Self {
+        InstallError::Simctl(e.to_string())
+    }
+}
+
+impl From<InstallError> for String {
+    fn from(e: InstallError) -> Self {
+        e.to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallStage {
+    Extracting,
+    Inspecting,
+    Installing,
+    Launching,
+    Done,
+    Failed,
+}
+
+/**
+ * Metadata read from the Info.plist of the .app bundle
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AppBundleInfo {
+    pub bundle_id: String,
+    pub name: String,
+    pub version: Option<String>,
+    pub build: Option<String>,
+    pub min_os_version: Option<String>,
+    pub supported_platforms: Vec<String>,
+    /** path of the extracted .app bundle */
+    pub path: String,
+}
+
+/**
+ * The payload of the `install_progress` event
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstallProgress {
+    /** id given by the webview, or the package path */
+    pub id: String,
+    pub stage: InstallStage,
+    pub message: Option<String>,
+    pub app: Option<AppBundleInfo>,
+}
+
+/**
+ * check the relative link target, resolved from the folder of the link, stays inside the root
+ */
+fn link_stays_inside(link: &Path, target: &str, root: &Path) -> bool {
+    let mut depth = match link.parent().and_then(|p| p.strip_prefix(root).ok()) {
+        Some(parent) => parent.components().count(),
+        None => return false,
+    };
+    for component in Path::new(target).components() {
+        match component {
+            std::path::Component::Normal(_) => depth += 1,
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                if depth == 0 {
+                    return false;
+                }
+                depth -= 1;
+            }
+            std::path::Component::RootDir | std::path::Component::Prefix(_) => return false,
+        }
+    }
+    true
+}
+
+/**
+ * check no existing component of the entry is a symlink, so the entry is not written through a link
+ */
+fn ensure_no_link(destination: &Path, relative: &Path) -> Result<(), InstallError> {
+    let mut path = destination.to_path_buf();
+    for component in relative.components() {
+        path.push(component);
+        match path.symlink_metadata() {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                return Err(InstallError::Extract(format!(
+                    "entry inside a link: {}",
+                    relative.to_string_lossy()
+                )));
+            }
+            Ok(_) => {}
+            // the rest of the path does not exist yet
+            Err(_) => break,
+        }
+    }
+    Ok(())
+}
+
+/**
+ * extract the zip archive into `destination`, entries escaping the destination are rejected
+ * unix permissions & symlinks are kept, the app executable is not runnable otherwise
+ */
+pub fn extract_archive(archive: &Path, destination: &Path) -> Result<(), InstallError> {
+    let mut zip = zip::ZipArchive::new(File::open(archive)?)?;
+    // relative path & target of the links
+    let mut links = vec![];
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        let relative = entry
+            .enclosed_name()
+            .map(|p| p.to_path_buf())
+            .ok_or_else(|| InstallError::Extract(format!("invalid entry: {}", entry.name())))?;
+        ensure_no_link(destination, &relative)?;
+        let path = destination.join(&relative);
+        if entry.is_dir() {
+            std::fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mode = entry.unix_mode();
+        // S_IFLNK, the content of the entry is the link target
+        if mode.map(|m| m & 0o170000 == 0o120000).unwrap_or(false) {
+            let mut target = String::new();
+            std::io::Read::read_to_string(&mut entry, &mut target)?;
+            if !link_stays_inside(&path, &target, destination) {
+                return Err(InstallError::Extract(format!(
+                    "invalid link: {}",
+                    entry.name()
+                )));
+            }
+            links.push((relative, target));
+            continue;
+        }
+        let mut file = File::create(&path)?;
+        std::io::copy(&mut entry, &mut file)?;
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode & 0o777))?;
+        }
+    }
+    // the links are created after the other entries, so no entry is written through a link
+    for (relative, target) in links.iter() {
+        ensure_no_link(destination, relative)?;
+        let path = destination.join(relative);
+        let _ = std::fs::remove_file(&path);
+        std::os::unix::fs::symlink(target, &path)?;
+    }
+    // a target resolved through another link may still point outside, e.g. `s/../..` with `s -> ..`
+    let root = destination.canonicalize()?;
+    for (relative, _) in links.iter() {
+        let path = destination.join(relative);
+        let inside = path
+            .canonicalize()
+            .map(|real| real.starts_with(&root))
+            .unwrap_or(false);
+        if !inside {
+            let _ = std::fs::remove_file(&path);
+            return Err(InstallError::Extract(format!(
+                "invalid link: {}",
+                relative.to_string_lossy()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/**
+ * find the .app bundle, `Payload/<name>.app` of an ipa or the first .app within a few levels
+ */
+pub fn find_app_bundle(dir: &Path) -> Option<PathBuf> {
+    fn search(dir: &Path, depth: usize) -> Option<PathBuf> {
+        let mut entries = std::fs::read_dir(dir)
+            .ok()?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .collect::<Vec<_>>();
+        entries.sort();
+        if let Some(app) = entries
+            .iter()
+            .find(|p| p.extension().map(|e| e == "app").unwrap_or(false))
+        {
+            return Some(app.clone());
+        }
+        if depth == 0 {
+            return None;
+        }
+        entries
+            .iter()
+            // skip the resource fork folder of archives created by Finder
+            .filter(|p| !p.ends_with("__MACOSX"))
+            .find_map(|p| search(p, depth - 1))
+    }
+    search(dir, 3)
+}
+
+/**
+ * read the metadata from `<app>/Info.plist`
+ */
+pub fn read_app_bundle_info(app_path: &Path) -> Result<AppBundleInfo, InstallError> {
+    let info_plist = app_path.join("Info.plist");
+    let value = plist::Value::from_file(&info_plist)
+        .map_err(|e| InstallError::InvalidInfoPlist(e.to_string()))?;
+    bundle_info_from_plist(&value, app_path)
+}
+
+/**
+ * read the metadata from the `<name>.app/Info.plist` entry of a zip or ipa without extracting it
+ * the path of the returned info is the archive
+ */
+pub fn read_archive_bundle_info(archive: &Path) -> Result<AppBundleInfo, InstallError> {
+    let mut zip = zip::ZipArchive::new(File::open(archive)?)?;
+    // the shallowest Info.plist directly inside a .app, frameworks & plugins have their own
+    let entry_name = zip
+        .file_names()
+        .filter(|name| {
+            let path = Path::new(name);
+            path.file_name().map(|n| n == "Info.plist").unwrap_or(false)
+                && path
+                    .parent()
+                    .and_then(|p| p.extension())
+                    .map(|e| e == "app")
+                    .unwrap_or(false)
+        })
+        .min_by_key(|name| name.matches('/').count())
+        .map(|name| name.to_string())
+        .ok_or_else(|| InstallError::BundleNotFound(archive.to_string_lossy().to_string()))?;
+    let mut bytes = vec![];
+    std::io::Read::read_to_end(&mut zip.by_name(&entry_name)?, &mut bytes)?;
+    let value = plist::Value::from_reader(std::io::Cursor::new(bytes))
+        .map_err(|e| InstallError::InvalidInfoPlist(e.to_string()))?;
+    bundle_info_from_plist(&value, archive)
+}
+
+fn bundle_info_from_plist(
+    value: &plist::Value,
+    path: &Path,
+) -> Result<AppBundleInfo, InstallError> {
+    let dict = value
+        .as_dictionary()
+        .ok_or_else(|| InstallError::InvalidInfoPlist("root is not a dictionary".to_string()))?;
+    let get_string = |key: &str| {
+        dict.get(key)
+            .and_then(|v| v.as_string())
+            .map(|s| s.to_string())
+    };
+    let bundle_id = get_string("CFBundleIdentifier").ok_or_else(|| {
+        InstallError::InvalidInfoPlist("CFBundleIdentifier is missing".to_string())
+    })?;
+    let supported_platforms = dict
+        .get("CFBundleSupportedPlatforms")
+        .and_then(|v| v.as_array())
+        .map(|platforms| {
+            platforms
+                .iter()
+                .filter_map(|p| p.as_string().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(AppBundleInfo {
+        name: get_string("CFBundleDisplayName")
+            .or_else(|| get_string("CFBundleName"))
+            .unwrap_or_else(|| bundle_id.clone()),
+        bundle_id,
+        version: get_string("CFBundleShortVersionString"),
+        build: get_string("CFBundleVersion"),
+        min_os_version: get_string("MinimumOSVersion"),
+        supported_platforms,
+        path: path.to_string_lossy().to_string(),
+    })
+}
+
+/**
+ * numeric components of a version, e.g. "iOS-17-5" -> [17, 5], "12.0.1" -> [12, 0, 1]
+ */
+pub fn parse_version(version: &str) -> Vec<u32> {
+    version
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| s.parse().ok())
+        .collect()
+}
+
+/**
+ * compare versions by components, missing components are 0, e.g. "17" == "17.0"
+ */
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let (a, b) = (parse_version(a), parse_version(b));
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != std::cmp::Ordering::Equal {
+            return ordering;
+        }
+    }
+    std::cmp::Ordering::Equal
+}
+
+/**
+ * check the app can run on the device
+ */
+pub fn check_compatibility(info: &AppBundleInfo, device: &Device) -> Result<(), InstallError> {
+    if !info.supported_platforms.is_empty()
+        && !info
+            .supported_platforms
+            .iter()
+            .any(|p| p == SIMULATOR_PLATFORM)
+    {
+        return Err(InstallError::NotSimulatorBuild(
+            info.supported_platforms.clone(),
+        ));
+    }
+    if let (Some(min_os), Some(device_os)) =
+        (info.min_os_version.as_ref(), device.os_version.as_ref())
+    {
+        // the device os is unknown if it has no number, e.g. "unknown system version"
+        if !parse_version(device_os).is_empty()
+            && compare_versions(device_os, min_os) == std::cmp::Ordering::Less
+        {
+            return Err(InstallError::Incompatible {
+                min_os: min_os.clone(),
+                device_os: device_os.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/**
+ * get the .app bundle of the package, archives are extracted into the install cache
+ */
+pub async fn prepare_app_bundle(package: &Path) -> Result<PathBuf, InstallError> {
+    let name = package
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let lower = name.to_lowercase();
+    if package.is_dir() && lower.ends_with(".app") {
+        return Ok(package.to_path_buf());
+    }
+    if !package.is_file() || !(lower.ends_with(".zip") || lower.ends_with(".ipa")) {
+        return Err(InstallError::UnsupportedPackage(
+            package.to_string_lossy().to_string(),
+        ));
+    }
+    // one cache folder per package name, extracted again on every install
+    let destination = get_install_cache_dir().join(&name);
+    let archive = package.to_path_buf();
+    let extract_to = destination.clone();
+    tokio::task::spawn_blocking(move || {
+        if extract_to.exists() {
+            std::fs::remove_dir_all(&extract_to)?;
+        }
+        std::fs::create_dir_all(&extract_to)?;
+        extract_archive(&archive, &extract_to)
+    })
+    .await
+    .map_err(|e| InstallError::Extract(e.to_string()))??;
+    find_app_bundle(&destination)
+        .ok_or_else(|| InstallError::BundleNotFound(package.to_string_lossy().to_string()))
+}
+
+fn emit_progress(
+    handle: &AppHandle,
+    id: &str,
+    stage: InstallStage,
+    message: Option<String>,
+    app: Option<&AppBundleInfo>,
+) {
+    debug_println!("install {}: {:?} {:?}", id, stage, message);
+    let _ = handle.emit_all(
+        "install_progress",
+        InstallProgress {
+            id: id.to_string(),
+            stage,
+            message,
+            app: app.cloned(),
+        },
+    );
+}
+
+async fn run_install_pipeline(
+    handle: &AppHandle,
+    device: &Device,
+    package: &Path,
+    launch: bool,
+    id: &str,
+) -> Result<AppBundleInfo, InstallError> {
+    emit_progress(handle, id, InstallStage::Extracting, None, None);
+    let app_path = prepare_app_bundle(package).await?;
+
+    emit_progress(handle, id, InstallStage::Inspecting, None, None);
+    let info = read_app_bundle_info(&app_path)?;
+    check_compatibility(&info, device)?;
+
+    emit_progress(handle, id, InstallStage::Installing, None, Some(&info));
+    let backend = get_simctl_backend(handle);
+    device.install_app(backend.as_ref(), &info.path).await?;
+    privacy::apply_profile_after_install(handle, backend.as_ref(), device, &info.bundle_id).await;
+    // keep the installed app list up to date
+    if let Err(e) = app::refresh_installed_apps(handle, device).await {
+        error!("refresh installed apps error: {}", e);
+    }
+
+    if launch {
+        emit_progress(handle, id, InstallStage::Launching, None, Some(&info));
+        device.launch_app(backend.as_ref(), &info.bundle_id).await?;
+    }
+    Ok(info)
+}
+
+/**
+ * extract, inspect, install and optionally launch the package on the device
+ * every stage is emitted by the `install_progress` event with the given id
+ */
+pub async fn install_package(
+    handle: &AppHandle,
+    device: &Device,
+    package: &Path,
+    launch: bool,
+    id: &str,
+) -> Result<AppBundleInfo, InstallError> {
+    match run_install_pipeline(handle, device, package, launch, id).await {
+        Ok(info) => {
+            emit_progress(handle, id, InstallStage::Done, None, Some(&info));
+            Ok(info)
+        }
+        Err(e) => {
+            emit_progress(handle, id, InstallStage::Failed, Some(e.to_string()), None);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    enum Entry<'a> {
+        File(&'a str, &'a str),
+        Link(&'a str, &'a str),
+    }
+
+    /** a work folder with `archive.zip` & the `cache/app` destination, removed when dropped */
+    struct Workspace {
+        dir: TempDir,
+    }
+
+    impl Workspace {
+        fn new(name: &str, entries: &[Entry]) -> Self {
+            let dir = TempDir::new(name);
+            std::fs::create_dir_all(dir.join("cache/app")).unwrap();
+            let mut zip = ZipWriter::new(File::create(dir.join("archive.zip")).unwrap());
+            let options = SimpleFileOptions::default().unix_permissions(0o755);
+            for entry in entries {
+                match entry {
+                    Entry::File(name, content) => {
+                        zip.start_file(*name, options).unwrap();
+                        zip.write_all(content.as_bytes()).unwrap();
+                    }
+                    Entry::Link(name, target) => zip.add_symlink(*name, *target, options).unwrap(),
+                }
+            }
+            zip.finish().unwrap();
+            Self { dir }
+        }
+        fn extract(&self) -> Result<(), InstallError> {
+            extract_archive(&self.dir.join("archive.zip"), &self.destination())
+        }
+        fn destination(&self) -> PathBuf {
+            self.dir.join("cache/app")
+        }
+    }
+
+    #[test]
+    fn extracts_files_and_links() {
+        let workspace = Workspace::new(
+            "extract-app",
+            &[
+                Entry::File("Payload/Demo.app/Info.plist", "<plist/>"),
+                Entry::File(
+                    "Payload/Demo.app/Frameworks/Kit.framework/Versions/A/Kit",
+                    "kit",
+                ),
+                Entry::Link(
+                    "Payload/Demo.app/Frameworks/Kit.framework/Versions/Current",
+                    "A",
+                ),
+                Entry::Link(
+                    "Payload/Demo.app/Frameworks/Kit.framework/Kit",
+                    "Versions/Current/Kit",
+                ),
+            ],
+        );
+        workspace.extract().unwrap();
+        let app = workspace.destination().join("Payload/Demo.app");
+        assert_eq!(find_app_bundle(&workspace.destination()), Some(app.clone()));
+        assert_eq!(
+            std::fs::read_to_string(app.join("Frameworks/Kit.framework/Kit")).unwrap(),
+            "kit"
+        );
+        use std::os::unix::fs::PermissionsExt;
+        let mode = app
+            .join("Info.plist")
+            .metadata()
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    #[test]
+    fn rejects_links_to_the_outside() {
+        let workspace = Workspace::new(
+            "extract-link-outside",
+            &[Entry::Link("Payload/escape", "../../../outside")],
+        );
+        assert!(matches!(workspace.extract(), Err(InstallError::Extract(_))));
+        let workspace = Workspace::new("extract-link-absolute", &[Entry::Link("escape", "/etc")]);
+        assert!(matches!(workspace.extract(), Err(InstallError::Extract(_))));
+    }
+
+    #[test]
+    fn does_not_write_through_chained_links() {
+        // `l` looks inside by its text, but `s` is the destination, so `l` is 3 levels above it
+        let workspace = Workspace::new(
+            "extract-chained-links",
+            &[
+                Entry::Link("a/b/c/s", "../../.."),
+                Entry::Link("a/b/c/l", "s/../../.."),
+                Entry::File("a/b/c/l/.zshrc", "echo pwned"),
+            ],
+        );
+        assert!(matches!(workspace.extract(), Err(InstallError::Extract(_))));
+        assert!(!workspace.dir.join(".zshrc").exists());
+        assert!(!workspace.dir.join("cache/.zshrc").exists());
+    }
+
+    #[test]
+    fn rejects_a_link_resolved_outside_through_another_link() {
+        let workspace = Workspace::new(
+            "extract-chained-link",
+            &[
+                Entry::Link("a/b/c/s", "../../.."),
+                Entry::Link("a/b/c/l", "s/../../.."),
+            ],
+        );
+        assert!(matches!(workspace.extract(), Err(InstallError::Extract(_))));
+        // the rejected link is removed
+        assert!(workspace
+            .destination()
+            .join("a/b/c/l")
+            .symlink_metadata()
+            .is_err());
+    }
+
+    #[test]
+    fn does_not_write_into_an_existing_link() {
+        let workspace = Workspace::new("extract-existing-link", &[Entry::File("Payload/x", "x")]);
+        let outside = workspace.dir.join("outside");
+        std::fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, workspace.destination().join("Payload")).unwrap();
+        assert!(matches!(workspace.extract(), Err(InstallError::Extract(_))));
+        assert!(!outside.join("x").exists());
+    }
+}
@@ -0,0 +1,379 @@
+/**
+ * This module simulates the device location by `xcrun simctl location`.
+ * Named places & routes are kept in the store, routes can be imported from GPX files.
+ */
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::AppHandle;
+
+use crate::sotre::{get_tauri_store, update_tauri_store, StoreKey};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Coordinate {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Coordinate {
+    pub fn new(latitude: f64, longitude: f64) -> Result<Self, GpxError> {
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(GpxError::InvalidCoordinate(format!(
+                "{},{}",
+                latitude, longitude
+            )));
+        }
+        Ok(Self {
+            latitude,
+            longitude,
+        })
+    }
+    /** format as the simctl argument, e.g. `31.2304,121.4737` */
+    pub fn to_arg(&self) -> String {
+        format!("{},{}", self.latitude, self.longitude)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Place {
+    pub name: String,
+    pub coordinate: Coordinate,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Route {
+    pub name: String,
+    pub points: Vec<Coordinate>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct LocationStore {
+    pub places: Vec<Place>,
+    pub routes: Vec<Route>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GpxError {
+    /** a point has no lat or lon attribute, or it is not a number */
+    InvalidPoint(String),
+    InvalidCoordinate(String),
+    /** a route needs at least 2 points */
+    TooFewPoints(usize),
+}
+
+impl fmt::Display for GpxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GpxError::InvalidPoint(tag) => write!(f, "invalid GPX point: {}", tag),
+            GpxError::InvalidCoordinate(c) => write!(f, "coordinate out of range: {}", c),
+            GpxError::TooFewPoints(n) => {
+                write!(f, "a route needs at least 2 points, found {}", n)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GpxError {}
+
+impl From<GpxError> for String {
+    fn from(e: GpxError) -> Self {
+        e.to_string()
+    }
+}
+
+/** GPX elements which carry a coordinate, in order of preference */
+const GPX_POINT_TAGS: [&str; 3] = ["trkpt", "rtept", "wpt"];
+
+/**
+ * get the value of an attribute in a start tag, e.g. `lat` in `<trkpt lat="31.2" lon="121.4">`
+ */
+fn find_attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+    while let Some(index) = rest.find(name) {
+        let before = rest[..index].chars().last();
+        let after = rest[index + name.len()..].trim_start();
+        rest = &rest[index + name.len()..];
+        // the attribute name must be a whole word followed by `=`
+        if !before.map(|c| c.is_whitespace()).unwrap_or(false) || !after.starts_with('=') {
+            continue;
+        }
+        let value = after[1..].trim_start();
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let end = value[1..].find(quote)?;
+        return Some(value[1..end + 1].to_string());
+    }
+    None
+}
+
+/**
+ * get the text of the first `<name>` element, e.g. the track name
+ */
+fn find_name(gpx: &str) -> Option<String> {
+    let start = gpx.find("<name>")? + "<name>".len();
+    let end = gpx[start..].find("</name>")?;
+    let name = gpx[start..start + end].trim();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+/**
+ * parse the points of the GPX document
+ * track points are used if present, otherwise route points, otherwise way points
+ */
+pub fn parse_gpx_points(gpx: &str) -> Result<Vec<Coordinate>, GpxError> {
+    for tag_name in GPX_POINT_TAGS.iter() {
+        let open = format!("<{}", tag_name);
+        let mut points = vec![];
+        let mut rest = gpx;
+        while let Some(index) = rest.find(&open) {
+            let tag_start = &rest[index..];
+            // skip tags which only share the prefix, e.g. `<trkpts>`
+            let next = tag_start[open.len()..].chars().next();
+            let end = tag_start.find('>').unwrap_or(tag_start.len());
+            rest = &tag_start[end..];
+            if !next
+                .map(|c| c.is_whitespace() || c == '>' || c == '/')
+                .unwrap_or(false)
+            {
+                continue;
+            }
+            let tag = &tag_start[..end];
+            let latitude = find_attribute(tag, "lat").and_then(|v| v.trim().parse::<f64>().ok());
+            let longitude = find_attribute(tag, "lon").and_then(|v| v.trim().parse::<f64>().ok());
+            match (latitude, longitude) {
+                (Some(latitude), Some(longitude)) => {
+                    points.push(Coordinate::new(latitude, longitude)?)
+                }
+                _ => return Err(GpxError::InvalidPoint(tag.to_string())),
+            }
+        }
+        if !points.is_empty() {
+            return Ok(points);
+        }
+    }
+    Ok(vec![])
+}
+
+/**
+ * parse a GPX document into a route, the name in the document is used if `name` is None
+ */
+pub fn parse_gpx_route(gpx: &str, name: Option<String>) -> Result<Route, GpxError> {
+    let points = parse_gpx_points(gpx)?;
+    if points.len() < 2 {
+        return Err(GpxError::TooFewPoints(points.len()));
+    }
+    Ok(Route {
+        name: name
+            .or_else(|| find_name(gpx))
+            .unwrap_or_else(|| "Imported Route".to_string()),
+        points,
+    })
+}
+
+pub fn get_location_store(handle: &AppHandle) -> LocationStore {
+    get_tauri_store(handle.clone())
+        .and_then(|store| store.locations)
+        .unwrap_or_default()
+}
+
+pub fn set_location_store(handle: &AppHandle, locations: LocationStore) -> Result<(), String> {
+    update_tauri_store(handle.clone(), StoreKey::Locations, json!(locations))
+        .map_err(|e| e.to_string())
+}
+
+/**
+ * check the points of a route which is not parsed from GPX, e.g. edited in the webview
+ */
+pub fn validate_route(route: &Route) -> Result<(), GpxError> {
+    if route.points.len() < 2 {
+        return Err(GpxError::TooFewPoints(route.points.len()));
+    }
+    for point in route.points.iter() {
+        Coordinate::new(point.latitude, point.longitude)?;
+    }
+    Ok(())
+}
+
+/**
+ * add or replace the route with the same name
+ */
+pub fn save_route(handle: &AppHandle, route: Route) -> Result<(), String> {
+    validate_route(&route)?;
+    let mut locations = get_location_store(handle);
+    locations.routes.retain(|r| r.name != route.name);
+    locations.routes.push(route);
+    set_location_store(handle, locations)
+}
+
+/**
+ * add or replace the place with the same name
+ */
+pub fn save_place(handle: &AppHandle, place: Place) -> Result<(), String> {
+    Coordinate::new(place.coordinate.latitude, place.coordinate.longitude)?;
+    let mut locations = get_location_store(handle);
+    locations.places.retain(|p| p.name != place.name);
+    locations.places.push(place);
+    set_location_store(handle, locations)
+}
+
+/**
+ * parse the GPX file and save it as a route
+ */
+pub fn import_gpx_file(
+    handle: &AppHandle,
+    path: &str,
+    name: Option<String>,
+) -> Result<Route, String> {
+    let gpx = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let route = parse_gpx_route(&gpx, name)?;
+    save_route(handle, route.clone())?;
+    Ok(route)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coordinate(latitude: f64, longitude: f64) -> Coordinate {
+        Coordinate {
+            latitude,
+            longitude,
+        }
+    }
+
+    #[test]
+    fn parses_track_points() {
+        let gpx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="costa">
+  <trk>
+    <name> Bund walk </name>
+    <trkseg>
+      <trkpt lat="31.2304" lon="121.4737"><ele>4.2</ele></trkpt>
+      <trkpt lon='121.4901' lat='31.2397'/>
+      <trkpt
+        lat="31.2422" lon="121.4998">
+      </trkpt>
+    </trkseg>
+  </trk>
+  <wpt lat="0" lon="0"/>
+</gpx>"#;
+        let route = parse_gpx_route(gpx, None).unwrap();
+        assert_eq!(route.name, "Bund walk");
+        // the way point is ignored if there are track points
+        assert_eq!(
+            route.points,
+            vec![
+                coordinate(31.2304, 121.4737),
+                coordinate(31.2397, 121.4901),
+                coordinate(31.2422, 121.4998),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_route_points_then_way_points() {
+        let gpx = r#"<gpx><rte>
+            <rtept lat="37.3349" lon="-122.0090"/>
+            <rtept lat="37.3318" lon="-122.0312"/>
+        </rte><wpt lat="1" lon="2"/></gpx>"#;
+        assert_eq!(
+            parse_gpx_points(gpx).unwrap(),
+            vec![
+                coordinate(37.3349, -122.009),
+                coordinate(37.3318, -122.0312)
+            ]
+        );
+
+        let gpx = r#"<gpx><wpt lat="-33.8568" lon="151.2153"><name>Opera House</name></wpt></gpx>"#;
+        assert_eq!(
+            parse_gpx_points(gpx).unwrap(),
+            vec![coordinate(-33.8568, 151.2153)]
+        );
+        assert_eq!(parse_gpx_points("<gpx></gpx>").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn skips_tags_sharing_the_prefix() {
+        let gpx = r#"<gpx>
+            <trkpts count="2"/>
+            <extensions><rteptx lat="bad" lon="bad"/></extensions>
+            <rtept lat="10" lon="20"/>
+            <rtept lat="11" lon="21"/>
+        </gpx>"#;
+        assert_eq!(
+            parse_gpx_points(gpx).unwrap(),
+            vec![coordinate(10.0, 20.0), coordinate(11.0, 21.0)]
+        );
+    }
+
+    #[test]
+    fn attributes_must_be_whole_words() {
+        // `xlat` & `lon` inside a value are not the coordinate
+        let gpx = r#"<gpx><wpt xlat="1" desc="lon=2" lat="3" lon="4"/></gpx>"#;
+        assert_eq!(parse_gpx_points(gpx).unwrap(), vec![coordinate(3.0, 4.0)]);
+    }
+
+    #[test]
+    fn rejects_missing_or_invalid_coordinates() {
+        let missing_lon = r#"<gpx><trkpt lat="31.2"/><trkpt lat="31.3" lon="121.5"/></gpx>"#;
+        assert_eq!(
+            parse_gpx_points(missing_lon),
+            Err(GpxError::InvalidPoint(r#"<trkpt lat="31.2"/"#.to_string()))
+        );
+        let not_a_number = r#"<gpx><rtept lat="north" lon="121.5"/></gpx>"#;
+        assert!(matches!(
+            parse_gpx_points(not_a_number),
+            Err(GpxError::InvalidPoint(_))
+        ));
+        let unquoted = r#"<gpx><wpt lat=31.2 lon=121.5/></gpx>"#;
+        assert!(matches!(
+            parse_gpx_points(unquoted),
+            Err(GpxError::InvalidPoint(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_out_of_range_coordinates() {
+        let gpx = r#"<gpx><trkpt lat="91" lon="0"/><trkpt lat="0" lon="0"/></gpx>"#;
+        assert_eq!(
+            parse_gpx_points(gpx),
+            Err(GpxError::InvalidCoordinate("91,0".to_string()))
+        );
+        let gpx = r#"<gpx><trkpt lat="0" lon="-180.5"/></gpx>"#;
+        assert!(matches!(
+            parse_gpx_route(gpx, None),
+            Err(GpxError::InvalidCoordinate(_))
+        ));
+    }
+
+    #[test]
+    fn a_route_needs_two_points() {
+        assert_eq!(
+            parse_gpx_route("<gpx></gpx>", None).unwrap_err(),
+            GpxError::TooFewPoints(0)
+        );
+        let gpx = r#"<gpx><trk><trkseg><trkpt lat="1" lon="2"/></trkseg></trk></gpx>"#;
+        assert_eq!(
+            parse_gpx_route(gpx, Some("One".to_string())).unwrap_err(),
+            GpxError::TooFewPoints(1)
+        );
+    }
+
+    #[test]
+    fn the_given_name_is_preferred() {
+        let gpx =
+            r#"<gpx><name>In the file</name><wpt lat="1" lon="2"/><wpt lat="3" lon="4"/></gpx>"#;
+        let route = parse_gpx_route(gpx, Some("Given".to_string())).unwrap();
+        assert_eq!(route.name, "Given");
+        let gpx = r#"<gpx><name> </name><wpt lat="1" lon="2"/><wpt lat="3" lon="4"/></gpx>"#;
+        assert_eq!(parse_gpx_route(gpx, None).unwrap().name, "Imported Route");
+    }
+}
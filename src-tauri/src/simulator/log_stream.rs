@@ -0,0 +1,435 @@
+/**
+ * This module streams the device logs by `xcrun simctl spawn <udid> log stream`.
+ * Lines are forwarded to the webview by the `simulator_log` event, and optionally written to a rotating file.
+ */
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use debug_print::debug_println;
+use log::error;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+
+use crate::path::get_logs_dir;
+
+use super::{
+    backend::{get_simctl_backend, LineSource},
+    error::SimctlError,
+};
+
+/** the log file is rotated when it reaches this size */
+pub const LOG_FILE_MAX_BYTES: u64 = 5 * 1024 * 1024;
+/** number of rotated log files kept, e.g. `<udid>.log.1` ~ `<udid>.log.3` */
+pub const LOG_FILE_MAX_FILES: usize = 3;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, EnumString, Display, Default)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum LogLevel {
+    #[default]
+    Default,
+    Info,
+    Debug,
+}
+
+/**
+ * Filters of the log stream, all conditions are combined by AND
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct LogFilter {
+    /** match the subsystem or the sender image of the app, e.g. "com.costa.app" */
+    pub bundle_id: Option<String>,
+    /** process name, e.g. "MobileSafari" */
+    pub process: Option<String>,
+    /** os_log subsystem prefix, e.g. "com.apple.WebKit" */
+    pub subsystem: Option<String>,
+    pub level: LogLevel,
+}
+
+impl LogFilter {
+    /**
+     * build the NSPredicate of `log stream --predicate`, None if no filter is set
+     */
+    pub fn predicate(&self) -> Option<String> {
+        let mut conditions = vec![];
+        if let Some(bundle_id) = self.bundle_id.as_ref() {
+            conditions.push(format!(
+                "(subsystem BEGINSWITH {0} OR senderImagePath CONTAINS {0})",
+                quote(bundle_id)
+            ));
+        }
+        if let Some(process) = self.process.as_ref() {
+            conditions.push(format!("process == {}", quote(process)));
+        }
+        if let Some(subsystem) = self.subsystem.as_ref() {
+            conditions.push(format!("subsystem BEGINSWITH {}", quote(subsystem)));
+        }
+        if conditions.is_empty() {
+            None
+        } else {
+            Some(conditions.join(" AND "))
+        }
+    }
+    /**
+     * args of `xcrun simctl spawn <udid> log stream`
+     */
+    pub fn stream_args(&self, udid: &str) -> Vec<String> {
+        let mut args = vec![
+            "spawn".to_string(),
+            udid.to_string(),
+            "log".to_string(),
+            "stream".to_string(),
+            "--style".to_string(),
+            "compact".to_string(),
+            "--level".to_string(),
+            self.level.to_string(),
+        ];
+        if let Some(predicate) = self.predicate() {
+            args.push("--predicate".to_string());
+            args.push(predicate);
+        }
+        args
+    }
+}
+
+/** quote a string literal of NSPredicate */
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/**
+ * The payload of the `simulator_log` event
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LogLine {
+    pub udid: String,
+    pub line: String,
+}
+
+/**
+ * A log file rotated by size, `<name>.log` -> `<name>.log.1` -> ... -> `<name>.log.<max_files>`
+ */
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    pub fn open(path: &Path, max_bytes: u64, max_files: usize) -> std::io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            written,
+            max_bytes,
+            max_files,
+        })
+    }
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        PathBuf::from(format!("{}.{}", self.path.to_string_lossy(), index))
+    }
+    fn rotate(&mut self) -> std::io::Result<()> {
+        for index in (1..self.max_files).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                std::fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        if self.max_files > 0 {
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+    pub fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.written + line.len() as u64 + 1 > self.max_bytes && self.written > 0 {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.file.write_all(b"\n")?;
+        self.written += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+/**
+ * forward every line of the source to `on_line` until the source is closed or the token is cancelled
+ */
+pub async fn pump_lines(
+    mut source: Box<dyn LineSource>,
+    cancel: CancellationToken,
+    mut on_line: impl FnMut(String) + Send,
+) {
+    loop {
+        tokio::select! {
+            line = source.next_line() => match line {
+                Some(line) => on_line(line),
+                None => break,
+            },
+            _ = cancel.cancelled() => break,
+        }
+    }
+}
+
+/**
+ * Running log streams managed by tauri
+ * key: device udid
+ * value: stream id & the token which stops the stream
+ */
+#[derive(Default)]
+pub struct LogStreams {
+    streams: Mutex<HashMap<String, (u64, CancellationToken)>>,
+    next_id: AtomicU64,
+}
+
+impl LogStreams {
+    pub fn is_streaming(&self, udid: &str) -> bool {
+        self.streams.lock().unwrap().contains_key(udid)
+    }
+    /** stop the stream, return false if the device is not streaming */
+    pub fn stop(&self, udid: &str) -> bool {
+        match self.streams.lock().unwrap().remove(udid) {
+            Some((_, cancel)) => {
+                cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+    fn insert(&self, udid: &str, cancel: CancellationToken) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.streams
+            .lock()
+            .unwrap()
+            .insert(udid.to_string(), (id, cancel));
+        id
+    }
+    /** remove the stream if it has not been replaced by a new one */
+    fn remove(&self, udid: &str, id: u64) {
+        let mut streams = self.streams.lock().unwrap();
+        if streams.get(udid).map(|(i, _)| *i == id).unwrap_or(false) {
+            streams.remove(udid);
+        }
+    }
+}
+
+pub fn get_log_file_path(udid: &str) -> PathBuf {
+    get_logs_dir().join(format!("{}.log", udid))
+}
+
+/**
+ * start `log stream` on the device, lines are emitted by the `simulator_log` event
+ * return the token which stops the stream
+ */
+pub async fn start_log_stream(
+    handle: &AppHandle,
+    udid: &str,
+    filter: &LogFilter,
+    write_to_file: bool,
+) -> Result<CancellationToken, SimctlError> {
+    let streams = handle.state::<LogStreams>();
+    if streams.is_streaming(udid) {
+        streams.stop(udid);
+    }
+    let backend = get_simctl_backend(handle);
+    let args = filter.stream_args(udid);
+    let args = args.iter().map(|a| a.as_str()).collect::<Vec<_>>();
+    let mut process = backend.spawn(&args).await?;
+    let source = process
+        .take_stdout()
+        .ok_or_else(|| SimctlError::Spawn("log stream has no stdout".to_string()))?;
+    let mut file = if write_to_file {
+        Some(RotatingFile::open(
+            &get_log_file_path(udid),
+            LOG_FILE_MAX_BYTES,
+            LOG_FILE_MAX_FILES,
+        )?)
+    } else {
+        None
+    };
+    let cancel = CancellationToken::new();
+    let stream_id = handle.state::<LogStreams>().insert(udid, cancel.clone());
+
+    let app_handle = handle.clone();
+    let udid = udid.to_string();
+    let token = cancel.clone();
+    tauri::async_runtime::spawn(async move {
+        debug_println!("log stream started: {}", udid);
+        let event_udid = udid.clone();
+        let event_handle = app_handle.clone();
+        pump_lines(source, token, move |line| {
+            if let Some(file) = file.as_mut() {
+                if let Err(e) = file.write_line(&line) {
+                    error!("write log file error: {}", e);
+                }
+            }
+            let _ = event_handle.emit_all(
+                "simulator_log",
+                LogLine {
+                    udid: event_udid.clone(),
+                    line,
+                },
+            );
+        })
+        .await;
+        let _ = process.stop().await;
+        app_handle.state::<LogStreams>().remove(&udid, stream_id);
+        debug_println!("log stream stopped: {}", udid);
+    });
+    Ok(cancel)
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::{
+        simulator::backend::{RecordedBackend, SimctlBackend, SimctlOutput, VecLineSource},
+        test_util::TempDir,
+    };
+
+    /** a stream which never sends a line, like `log stream` of an idle device */
+    struct IdleSource;
+
+    #[async_trait]
+    impl LineSource for IdleSource {
+        async fn next_line(&mut self) -> Option<String> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn pumps_the_stream_of_the_filter() {
+        let filter = LogFilter {
+            bundle_id: Some("com.costa.app".to_string()),
+            level: LogLevel::Debug,
+            ..Default::default()
+        };
+        let args = filter.stream_args("udid");
+        let args = args.iter().map(|a| a.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            args,
+            vec![
+                "spawn",
+                "udid",
+                "log",
+                "stream",
+                "--style",
+                "compact",
+                "--level",
+                "debug",
+                "--predicate",
+                "(subsystem BEGINSWITH \"com.costa.app\" OR senderImagePath CONTAINS \"com.costa.app\")",
+            ]
+        );
+        let output = "Timestamp               Ty Process[PID:TID]\n\
+            2024-03-01 08:12:45.123 Df App[4321:1a2b] [com.costa.app:network] request finished\n\
+            2024-03-01 08:12:45.456 Db App[4321:1a2b] [com.costa.app:route] open sslocal://debug";
+        let backend = RecordedBackend::new().with_response(&args, SimctlOutput::ok(output));
+
+        let mut process = backend.spawn(&args).await.unwrap();
+        let source = process.take_stdout().unwrap();
+        let mut lines = vec![];
+        pump_lines(source, CancellationToken::new(), |line| lines.push(line)).await;
+
+        assert_eq!(lines, output.lines().collect::<Vec<_>>());
+        assert!(backend.has_command(&args));
+        assert!(process.take_stdout().is_none());
+    }
+
+    #[tokio::test]
+    async fn pump_lines_stops_at_the_end_of_the_source() {
+        let source = VecLineSource::new(vec!["first".to_string(), "second".to_string()]);
+        let mut lines = vec![];
+        pump_lines(Box::new(source), CancellationToken::new(), |line| {
+            lines.push(line)
+        })
+        .await;
+        assert_eq!(lines, vec!["first", "second"]);
+    }
+
+    #[tokio::test]
+    async fn pump_lines_stops_when_cancelled() {
+        let cancel = CancellationToken::new();
+        let pump = tokio::spawn(pump_lines(Box::new(IdleSource), cancel.clone(), |_| {}));
+        cancel.cancel();
+        tokio::time::timeout(std::time::Duration::from_secs(1), pump)
+            .await
+            .expect("the pump is stopped by the token")
+            .unwrap();
+    }
+
+    #[test]
+    fn rotates_at_the_size_limit() {
+        let dir = TempDir::new("rotating-file");
+        let path = dir.join("udid.log");
+        let rotated = |index: usize| PathBuf::from(format!("{}.{}", path.to_string_lossy(), index));
+        let read = |path: &Path| std::fs::read_to_string(path).unwrap();
+
+        // two lines of 10 bytes & the newlines do not fit into 21 bytes
+        let mut file = RotatingFile::open(&path, 21, 2).unwrap();
+        file.write_line("line-0001").unwrap();
+        file.write_line("line-0002").unwrap();
+        assert_eq!(read(&path), "line-0001\nline-0002\n");
+        assert!(!rotated(1).exists());
+
+        file.write_line("line-0003").unwrap();
+        assert_eq!(read(&path), "line-0003\n");
+        assert_eq!(read(&rotated(1)), "line-0001\nline-0002\n");
+
+        file.write_line("line-0004").unwrap();
+        file.write_line("line-0005").unwrap();
+        file.write_line("line-0006").unwrap();
+        assert_eq!(read(&path), "line-0005\nline-0006\n");
+        assert_eq!(read(&rotated(1)), "line-0003\nline-0004\n");
+        assert_eq!(read(&rotated(2)), "line-0001\nline-0002\n");
+
+        file.write_line("line-0007").unwrap();
+        assert_eq!(read(&path), "line-0007\n");
+        assert_eq!(read(&rotated(1)), "line-0005\nline-0006\n");
+        assert_eq!(read(&rotated(2)), "line-0003\nline-0004\n");
+        // the oldest file is dropped
+        assert!(!rotated(3).exists());
+
+        // a reopened file keeps counting its size
+        drop(file);
+        let mut file = RotatingFile::open(&path, 21, 2).unwrap();
+        file.write_line("line-0008").unwrap();
+        file.write_line("line-0009").unwrap();
+        assert_eq!(read(&path), "line-0009\n");
+        assert_eq!(read(&rotated(1)), "line-0007\nline-0008\n");
+    }
+
+    #[test]
+    fn a_line_longer_than_the_limit_is_written_to_an_empty_file() {
+        let dir = TempDir::new("rotating-long-line");
+        let path = dir.join("udid.log");
+        let mut file = RotatingFile::open(&path, 4, 1).unwrap();
+        file.write_line("a long line").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a long line\n");
+    }
+}
@@ -0,0 +1,161 @@
+/**
+ * This module adds photos, videos & contacts to the simulator by `xcrun simctl addmedia`.
+ * Fixture sets are folders under `~/.costa/fixtures`, e.g. `~/.costa/fixtures/upload/cat.jpg`.
+ */
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::path::get_fixtures_dir;
+
+use super::{backend::get_simctl_backend, device::Device};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    Photo,
+    Video,
+    Contact,
+}
+
+impl MediaKind {
+    /**
+     * get the kind by the file extension, None if simctl does not support it
+     */
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "jpg" | "jpeg" | "png" | "heic" | "heif" | "gif" | "tiff" | "tif" | "bmp" | "webp" => {
+                Some(MediaKind::Photo)
+            }
+            "mp4" | "mov" | "m4v" => Some(MediaKind::Video),
+            "vcf" => Some(MediaKind::Contact),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * Result of adding a file to the device
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MediaResult {
+    pub path: String,
+    pub kind: Option<MediaKind>,
+    pub error: Option<String>,
+}
+
+/**
+ * check the file before invoking simctl, return the kind of the media
+ */
+pub fn validate_media(path: &Path) -> Result<MediaKind, String> {
+    if !path.is_file() {
+        return Err("file not found".to_string());
+    }
+    MediaKind::from_path(path).ok_or_else(|| "unsupported file type".to_string())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FixtureSet {
+    pub name: String,
+    /** paths of the supported files in the set */
+    pub files: Vec<String>,
+}
+
+/**
+ * a fixture set name is a single folder name
+ */
+fn get_fixture_set_dir(name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || name.starts_with('.') || name.contains('/') || name.contains('\\') {
+        return Err(format!("invalid fixture set name: {}", name));
+    }
+    Ok(get_fixtures_dir().join(name))
+}
+
+fn list_fixture_files(dir: &Path) -> Vec<String> {
+    let mut files = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && MediaKind::from_path(p).is_some())
+                .map(|p| p.to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+pub fn get_fixture_sets() -> Vec<FixtureSet> {
+    let mut sets = std::fs::read_dir(get_fixtures_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir())
+                .map(|e| FixtureSet {
+                    name: e.file_name().to_string_lossy().to_string(),
+                    files: list_fixture_files(&e.path()),
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    sets.sort_by(|a, b| a.name.cmp(&b.name));
+    sets
+}
+
+pub fn get_fixture_set(name: &str) -> Result<FixtureSet, String> {
+    let dir = get_fixture_set_dir(name)?;
+    if !dir.is_dir() {
+        return Err(format!("fixture set not found: {}", name));
+    }
+    Ok(FixtureSet {
+        name: name.to_string(),
+        files: list_fixture_files(&dir),
+    })
+}
+
+/**
+ * copy the files into the fixture set, the set is created if it does not exist
+ */
+pub fn import_fixtures(name: &str, paths: &[String]) -> Result<Vec<MediaResult>, String> {
+    let dir = get_fixture_set_dir(name)?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(paths
+        .iter()
+        .map(|path| {
+            let source = Path::new(path);
+            let result = validate_media(source).and_then(|kind| {
+                let file_name = source.file_name().ok_or("invalid file name")?;
+                std::fs::copy(source, dir.join(file_name)).map_err(|e| e.to_string())?;
+                Ok(kind)
+            });
+            MediaResult {
+                path: path.clone(),
+                kind: result.as_ref().ok().copied(),
+                error: result.err(),
+            }
+        })
+        .collect())
+}
+
+pub fn delete_fixture_set(name: &str) -> Result<(), String> {
+    let dir = get_fixture_set_dir(name)?;
+    std::fs::remove_dir_all(dir).map_err(|e| e.to_string())
+}
+
+/**
+ * add every file of the fixture set to the device
+ */
+pub async fn seed_device(
+    handle: &tauri::AppHandle,
+    device: &Device,
+    name: &str,
+) -> Result<Vec<MediaResult>, String> {
+    let set = get_fixture_set(name)?;
+    if set.files.is_empty() {
+        return Err(format!("fixture set is empty: {}", name));
+    }
+    let backend = get_simctl_backend(handle);
+    Ok(device.add_media(backend.as_ref(), &set.files).await)
+}
@@ -1,4 +1,22 @@
+pub mod backend;
+pub mod error;
 pub mod device;
+pub mod device_type;
 pub mod command;
 pub mod app_command;
 pub mod runtime;
+pub mod runner;
+pub mod capture;
+pub mod log_stream;
+pub mod push;
+pub mod location;
+pub mod privacy;
+pub mod status_bar;
+pub mod appearance;
+pub mod media;
+pub mod pasteboard;
+pub mod openstep;
+pub mod app;
+pub mod container;
+pub mod install;
+pub mod runtime_manager;
@@ -0,0 +1,248 @@
+/**
+ * This module parses the old-style (OpenStep) plist printed by simctl, e.g. `xcrun simctl listapps`.
+ * Dictionaries become JSON objects, arrays become JSON arrays and every other value is a string.
+ */
+use serde_json::{Map, Value};
+
+use super::error::SimctlError;
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> SimctlError {
+        SimctlError::Parse(format!("invalid plist: {}", message))
+    }
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.chars.peek() {
+            if c.is_whitespace() {
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+    }
+    fn expect(&mut self, expected: char) -> Result<(), SimctlError> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.error(&format!("expected `{}`, found `{}`", expected, c))),
+            None => Err(self.error(&format!("expected `{}`, found end of input", expected))),
+        }
+    }
+    fn parse_value(&mut self) -> Result<Value, SimctlError> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('{') => self.parse_dict(),
+            Some('(') => self.parse_array(),
+            Some('"') => self.parse_quoted().map(Value::String),
+            Some('<') => self.parse_data().map(Value::String),
+            Some(_) => self.parse_unquoted().map(Value::String),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+    fn parse_dict(&mut self) -> Result<Value, SimctlError> {
+        self.expect('{')?;
+        let mut map = Map::new();
+        loop {
+            self.skip_whitespace();
+            if self.chars.peek() == Some(&'}') {
+                self.chars.next();
+                return Ok(Value::Object(map));
+            }
+            let key = match self.parse_value()? {
+                Value::String(key) => key,
+                _ => return Err(self.error("dictionary key must be a string")),
+            };
+            self.expect('=')?;
+            let value = self.parse_value()?;
+            self.expect(';')?;
+            map.insert(key, value);
+        }
+    }
+    fn parse_array(&mut self) -> Result<Value, SimctlError> {
+        self.expect('(')?;
+        let mut items = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.chars.peek() == Some(&')') {
+                self.chars.next();
+                return Ok(Value::Array(items));
+            }
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            // the separator is optional after the last item
+            if self.chars.peek() == Some(&',') {
+                self.chars.next();
+            }
+        }
+    }
+    fn parse_quoted(&mut self) -> Result<String, SimctlError> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(value),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('r') => value.push('\r'),
+                    Some('U') => value.push(self.parse_unicode_escape()?),
+                    Some(c) => value.push(c),
+                    None => return Err(self.error("unterminated string")),
+                },
+                Some(c) => value.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+    /** the 4 hex digits after `\U`, a UTF-16 code unit */
+    fn parse_utf16_unit(&mut self) -> Result<u16, SimctlError> {
+        let hex: String = (0..4).filter_map(|_| self.chars.next()).collect();
+        if hex.len() != 4 {
+            return Err(self.error("invalid unicode escape"));
+        }
+        u16::from_str_radix(&hex, 16).map_err(|_| self.error("invalid unicode escape"))
+    }
+    /**
+     * the character of a `\U` escape, characters outside the BMP are escaped as a surrogate pair,
+     * e.g. `\UD83D\UDE00`
+     */
+    fn parse_unicode_escape(&mut self) -> Result<char, SimctlError> {
+        let mut units = vec![self.parse_utf16_unit()?];
+        if (0xD800..=0xDBFF).contains(&units[0]) {
+            if self.chars.next() != Some('\\') || self.chars.next() != Some('U') {
+                return Err(self.error("unpaired surrogate in unicode escape"));
+            }
+            units.push(self.parse_utf16_unit()?);
+        }
+        match char::decode_utf16(units).collect::<Vec<_>>().as_slice() {
+            [Ok(c)] => Ok(*c),
+            _ => Err(self.error("unpaired surrogate in unicode escape")),
+        }
+    }
+    fn parse_data(&mut self) -> Result<String, SimctlError> {
+        self.expect('<')?;
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some('>') => return Ok(value),
+                Some(c) if !c.is_whitespace() => value.push(c),
+                Some(_) => {}
+                None => return Err(self.error("unterminated data")),
+            }
+        }
+    }
+    fn parse_unquoted(&mut self) -> Result<String, SimctlError> {
+        let mut value = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_alphanumeric() || "_$+/:.-".contains(c) {
+                value.push(c);
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        if value.is_empty() {
+            return Err(self.error("expected a value"));
+        }
+        Ok(value)
+    }
+}
+
+/**
+ * parse an old-style plist into JSON
+ */
+pub fn parse_openstep_plist(input: &str) -> Result<Value, SimctlError> {
+    let mut parser = Parser {
+        chars: input.chars().peekable(),
+    };
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.chars.peek().is_some() {
+        return Err(parser.error("unexpected content after the root value"));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parses_nested_values() {
+        let plist = r#"{
+            "com.costa.app" =     {
+                ApplicationType = User;
+                CFBundleDisplayName = "Costa Demo";
+                CFBundleIdentifier = "com.costa.app";
+                GroupContainers =         {
+                };
+                SBAppTags =         (
+                    hidden,
+                    "beta"
+                );
+                Token = <0fd2 3c4e>;
+            };
+        }"#;
+        assert_eq!(
+            parse_openstep_plist(plist).unwrap(),
+            json!({
+                "com.costa.app": {
+                    "ApplicationType": "User",
+                    "CFBundleDisplayName": "Costa Demo",
+                    "CFBundleIdentifier": "com.costa.app",
+                    "GroupContainers": {},
+                    "SBAppTags": ["hidden", "beta"],
+                    "Token": "0fd23c4e",
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_escapes() {
+        let plist = r#"{ name = "\U6d4b\U8bd5 \"app\"\n"; }"#;
+        assert_eq!(
+            parse_openstep_plist(plist).unwrap(),
+            json!({ "name": "\u{6d4b}\u{8bd5} \"app\"\n" })
+        );
+    }
+
+    #[test]
+    fn decodes_surrogate_pairs() {
+        let plist = r#"{ CFBundleDisplayName = "Demo \UD83D\UDE00"; }"#;
+        assert_eq!(
+            parse_openstep_plist(plist).unwrap(),
+            json!({ "CFBundleDisplayName": "Demo \u{1F600}" })
+        );
+    }
+
+    #[test]
+    fn rejects_unpaired_surrogates() {
+        for plist in [
+            r#""\UD83D""#,
+            r#""\UD83Dx""#,
+            r#""\UD83D\U0041""#,
+            r#""\UDE00""#,
+            r#""\U12""#,
+            r#""\UZZZZ""#,
+        ] {
+            assert!(
+                matches!(parse_openstep_plist(plist), Err(SimctlError::Parse(_))),
+                "{}",
+                plist
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_trailing_content() {
+        assert!(parse_openstep_plist("{ a = b; } c").is_err());
+        assert!(parse_openstep_plist("{ a = b }").is_err());
+        assert!(parse_openstep_plist(r#"{ a = "b; }"#).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+/**
+ * This module syncs the host clipboard with the pasteboard of the booted simulator by `xcrun simctl pbcopy/pbpaste`.
+ * The last synced text is remembered, so a text copied from one side is not copied back.
+ */
+use std::{sync::Mutex, time::Duration};
+
+use debug_print::debug_println;
+use log::error;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard::ClipboardManager;
+use tokio_util::sync::CancellationToken;
+
+use crate::command::util::get_target_device;
+
+use super::{backend::get_simctl_backend, command};
+
+/** interval of reading both clipboards */
+pub const PASTEBOARD_SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncDirection {
+    /** host clipboard -> device pasteboard */
+    HostToDevice,
+    /** both ways, the host wins if both sides changed */
+    TwoWay,
+}
+
+/**
+ * The running sync managed by tauri
+ */
+#[derive(Default)]
+pub struct PasteboardSync(Mutex<Option<(SyncDirection, CancellationToken)>>);
+
+impl PasteboardSync {
+    pub fn direction(&self) -> Option<SyncDirection> {
+        self.0
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|(direction, _)| *direction)
+    }
+    /** stop the sync, return false if it is not running */
+    pub fn stop(&self) -> bool {
+        match self.0.lock().unwrap().take() {
+            Some((_, cancel)) => {
+                cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+    fn replace(&self, direction: SyncDirection, cancel: CancellationToken) {
+        if let Some((_, old)) = self.0.lock().unwrap().replace((direction, cancel)) {
+            old.cancel();
+        }
+    }
+}
+
+fn read_host_clipboard(handle: &AppHandle) -> Option<String> {
+    let clipboard = handle.state::<ClipboardManager>();
+    match clipboard.has_text() {
+        Ok(true) => clipboard.read_text().ok(),
+        _ => None,
+    }
+}
+
+/**
+ * What a sync round should do, decided from both sides and the last synced text
+ */
+#[derive(Debug, PartialEq)]
+pub enum SyncAction {
+    None,
+    CopyToDevice(String),
+    CopyToHost(String),
+}
+
+/**
+ * the host wins if both sides changed since the last sync
+ */
+pub fn decide_sync_action(
+    last_synced: Option<&str>,
+    host: Option<&str>,
+    device: Option<&str>,
+) -> SyncAction {
+    match (host, device) {
+        (Some(host), _) if Some(host) != last_synced => SyncAction::CopyToDevice(host.to_string()),
+        (_, Some(device)) if Some(device) != last_synced && !device.is_empty() => {
+            SyncAction::CopyToHost(device.to_string())
+        }
+        _ => SyncAction::None,
+    }
+}
+
+/**
+ * run one round of the sync, return the synced text
+ */
+async fn sync_once(
+    handle: &AppHandle,
+    direction: SyncDirection,
+    udid: &str,
+    last_synced: Option<&str>,
+) -> Result<Option<String>, String> {
+    let backend = get_simctl_backend(handle);
+    let host = read_host_clipboard(handle);
+    let device = if direction == SyncDirection::TwoWay {
+        Some(command::pbpaste(backend.as_ref(), udid).await?)
+    } else {
+        None
+    };
+    match decide_sync_action(last_synced, host.as_deref(), device.as_deref()) {
+        SyncAction::CopyToDevice(text) => {
+            debug_println!("pasteboard sync: host -> {}", udid);
+            command::pbcopy(backend.as_ref(), udid, &text).await?;
+            Ok(Some(text))
+        }
+        SyncAction::CopyToHost(text) => {
+            debug_println!("pasteboard sync: {} -> host", udid);
+            handle
+                .state::<ClipboardManager>()
+                .write_text(text.clone())?;
+            Ok(Some(text))
+        }
+        SyncAction::None => Ok(last_synced.map(|s| s.to_string())),
+    }
+}
+
+/**
+ * start syncing with the booted device until it is stopped, a running sync is replaced
+ */
+pub fn start_pasteboard_sync(handle: &AppHandle, direction: SyncDirection) {
+    let cancel = CancellationToken::new();
+    handle
+        .state::<PasteboardSync>()
+        .replace(direction, cancel.clone());
+    let app_handle = handle.clone();
+    tauri::async_runtime::spawn(async move {
+        debug_println!("pasteboard sync started: {:?}", direction);
+        let mut interval = tokio::time::interval(PASTEBOARD_SYNC_INTERVAL);
+        // (udid, text) of the last sync, the text is copied again when the booted device changes
+        let mut last_synced: Option<(String, String)> = None;
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = cancel.cancelled() => break,
+            }
+            // no booted device, wait for the next round
+            let device = match get_target_device(&app_handle, None) {
+                Ok(device) => device,
+                Err(_) => continue,
+            };
+            let last_text = last_synced
+                .as_ref()
+                .filter(|(udid, _)| udid == &device.udid)
+                .map(|(_, text)| text.as_str());
+            match sync_once(&app_handle, direction, &device.udid, last_text).await {
+                Ok(Some(text)) => last_synced = Some((device.udid.clone(), text)),
+                Ok(None) => {}
+                Err(e) => error!("pasteboard sync error: {}", e),
+            }
+        }
+        debug_println!("pasteboard sync stopped");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decides_the_sync_action() {
+        let cases = [
+            // (last synced, host, device, expected)
+            (
+                Some("a"),
+                Some("b"),
+                Some("a"),
+                SyncAction::CopyToDevice("b".to_string()),
+            ),
+            (
+                Some("a"),
+                Some("a"),
+                Some("b"),
+                SyncAction::CopyToHost("b".to_string()),
+            ),
+            (Some("a"), Some("a"), Some("a"), SyncAction::None),
+            (Some("a"), Some("a"), Some(""), SyncAction::None),
+            (
+                Some("a"),
+                Some("b"),
+                Some("c"),
+                SyncAction::CopyToDevice("b".to_string()),
+            ),
+            // host to device only, the device is not read
+            (
+                Some("a"),
+                Some("b"),
+                None,
+                SyncAction::CopyToDevice("b".to_string()),
+            ),
+            // nothing synced yet, the host clipboard is empty
+            (
+                None,
+                None,
+                Some("b"),
+                SyncAction::CopyToHost("b".to_string()),
+            ),
+            (None, None, None, SyncAction::None),
+        ];
+        for (last_synced, host, device, expected) in cases {
+            assert_eq!(
+                decide_sync_action(last_synced, host, device),
+                expected,
+                "last synced: {:?}, host: {:?}, device: {:?}",
+                last_synced,
+                host,
+                device
+            );
+        }
+    }
+}
@@ -0,0 +1,115 @@
+/**
+ * This module manages the privacy permissions of the simulator apps by `xcrun simctl privacy`.
+ * A permission profile is saved per bundle id and re-applied after the app is installed.
+ */
+use debug_print::debug_println;
+use log::error;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use strum_macros::{Display, EnumString};
+use tauri::AppHandle;
+
+use crate::sotre::{get_tauri_store, update_tauri_store, StoreKey};
+
+use super::{backend::SimctlBackend, device::Device, error::SimctlError};
+
+/**
+ * Services of `simctl privacy`
+ * the simulator has no camera, so there is no camera service
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, EnumString, Display)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum PrivacyService {
+    /** every service, only valid for reset */
+    All,
+    Calendar,
+    ContactsLimited,
+    Contacts,
+    Location,
+    LocationAlways,
+    PhotosAdd,
+    Photos,
+    MediaLibrary,
+    Microphone,
+    Motion,
+    Reminders,
+    Siri,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, EnumString, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum PrivacyAction {
+    Grant,
+    Revoke,
+    /** forget the decision, the app prompts again next time */
+    Reset,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PermissionRule {
+    pub service: PrivacyService,
+    pub action: PrivacyAction,
+}
+
+/**
+ * Permissions applied to the app after it is installed
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PermissionProfile {
+    pub bundle_id: String,
+    pub rules: Vec<PermissionRule>,
+}
+
+impl PermissionProfile {
+    /**
+     * apply the rules in order, stop at the first failure
+     */
+    pub async fn apply(&self, backend: &dyn SimctlBackend, device: &Device) -> Result<(), SimctlError> {
+        for rule in self.rules.iter() {
+            device
+                .set_privacy(backend, &self.bundle_id, rule.service, rule.action)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+pub fn get_permission_profiles(handle: &AppHandle) -> Vec<PermissionProfile> {
+    get_tauri_store(handle.clone())
+        .and_then(|store| store.permission_profiles)
+        .unwrap_or_default()
+}
+
+pub fn set_permission_profiles(
+    handle: &AppHandle,
+    profiles: Vec<PermissionProfile>,
+) -> Result<(), String> {
+    update_tauri_store(handle.clone(), StoreKey::PermissionProfiles, json!(profiles))
+        .map_err(|e| e.to_string())
+}
+
+pub fn get_permission_profile(handle: &AppHandle, bundle_id: &str) -> Option<PermissionProfile> {
+    get_permission_profiles(handle)
+        .into_iter()
+        .find(|p| p.bundle_id == bundle_id)
+}
+
+/**
+ * re-apply the saved profile of the installed app, do nothing if there is no profile
+ * failures are logged, the install itself has succeeded
+ */
+pub async fn apply_profile_after_install(
+    handle: &AppHandle,
+    backend: &dyn SimctlBackend,
+    device: &Device,
+    bundle_id: &str,
+) {
+    if let Some(profile) = get_permission_profile(handle, bundle_id) {
+        debug_println!("apply permission profile: {}", bundle_id);
+        if let Err(e) = profile.apply(backend, device).await {
+            error!("apply permission profile of {} error: {}", bundle_id, e);
+        }
+    }
+}
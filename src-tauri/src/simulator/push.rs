@@ -0,0 +1,275 @@
+/**
+ * This module sends push notifications to the simulator apps by `xcrun simctl push`.
+ * Payload templates are stored in the store, placeholders are filled from the clipboard schema.
+ */
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+use crate::sotre::{get_tauri_store, update_tauri_store, StoreKey};
+
+use super::{backend::get_simctl_backend, device::Device};
+
+/** max size of an APNs payload */
+pub const MAX_PAYLOAD_BYTES: usize = 4096;
+/** placeholder replaced by the schema read from the clipboard */
+pub const SCHEMA_PLACEHOLDER: &str = "{{schema}}";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PushError {
+    InvalidJson(String),
+    /** the payload is not a JSON object */
+    NotObject,
+    /** the payload has no `aps` dictionary */
+    MissingAps,
+    TooLarge {
+        size: usize,
+        limit: usize,
+    },
+    /** the template needs a schema but the clipboard is empty */
+    MissingSchema,
+}
+
+impl fmt::Display for PushError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PushError::InvalidJson(e) => write!(f, "payload is not valid JSON: {}", e),
+            PushError::NotObject => write!(f, "payload must be a JSON object"),
+            PushError::MissingAps => write!(f, "payload must contain an `aps` dictionary"),
+            PushError::TooLarge { size, limit } => {
+                write!(f, "payload is {} bytes, the limit is {} bytes", size, limit)
+            }
+            PushError::MissingSchema => write!(f, "no schema found in the clipboard"),
+        }
+    }
+}
+
+impl std::error::Error for PushError {}
+
+impl From<PushError> for String {
+    fn from(e: PushError) -> Self {
+        e.to_string()
+    }
+}
+
+/**
+ * validate an APNs payload, return the parsed JSON
+ */
+pub fn validate_payload(payload: &str) -> Result<Value, PushError> {
+    let value: Value =
+        serde_json::from_str(payload).map_err(|e| PushError::InvalidJson(e.to_string()))?;
+    let object = value.as_object().ok_or(PushError::NotObject)?;
+    if !matches!(object.get("aps"), Some(Value::Object(_))) {
+        return Err(PushError::MissingAps);
+    }
+    // the limit applies to the compact JSON which is sent to the device
+    let size = value.to_string().len();
+    if size > MAX_PAYLOAD_BYTES {
+        return Err(PushError::TooLarge {
+            size,
+            limit: MAX_PAYLOAD_BYTES,
+        });
+    }
+    Ok(value)
+}
+
+/**
+ * replace `{{schema}}` in the template, the schema is escaped as a JSON string content
+ */
+pub fn fill_template(template: &str, schema: Option<&str>) -> Result<String, PushError> {
+    if !template.contains(SCHEMA_PLACEHOLDER) {
+        return Ok(template.to_string());
+    }
+    let schema = schema.ok_or(PushError::MissingSchema)?;
+    let escaped =
+        serde_json::to_string(schema).map_err(|e| PushError::InvalidJson(e.to_string()))?;
+    // strip the quotes, the placeholder is expected inside a JSON string
+    let escaped = &escaped[1..escaped.len() - 1];
+    Ok(template.replace(SCHEMA_PLACEHOLDER, escaped))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PushTemplate {
+    pub name: String,
+    /** bundle id to send to, the webview asks for it if None */
+    pub bundle_id: Option<String>,
+    /** the payload JSON, may contain `{{schema}}` */
+    pub payload: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LastPush {
+    pub udid: String,
+    pub bundle_id: String,
+    pub payload: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct PushStore {
+    pub templates: Vec<PushTemplate>,
+    pub last_push: Option<LastPush>,
+}
+
+impl PushStore {
+    pub fn default_templates() -> Vec<PushTemplate> {
+        vec![
+            PushTemplate {
+                name: "Alert".to_string(),
+                bundle_id: None,
+                payload: json!({
+                    "aps": { "alert": { "title": "Costa", "body": "Hello from Costa" }, "sound": "default" }
+                })
+                .to_string(),
+            },
+            PushTemplate {
+                name: "Deep Link".to_string(),
+                bundle_id: None,
+                payload: format!(
+                    r#"{{"aps":{{"alert":{{"title":"Costa","body":"Open schema"}}}},"schema":"{}"}}"#,
+                    SCHEMA_PLACEHOLDER
+                ),
+            },
+        ]
+    }
+}
+
+pub fn get_push_store(handle: &AppHandle) -> PushStore {
+    get_tauri_store(handle.clone())
+        .and_then(|store| store.push)
+        .unwrap_or_else(|| PushStore {
+            templates: PushStore::default_templates(),
+            last_push: None,
+        })
+}
+
+pub fn set_push_store(handle: &AppHandle, push: PushStore) -> Result<(), String> {
+    update_tauri_store(handle.clone(), StoreKey::Push, json!(push)).map_err(|e| e.to_string())
+}
+
+/**
+ * the schema in the clipboard content saved by the tray
+ */
+pub fn get_clipboard_schema(handle: &AppHandle) -> Option<String> {
+    get_tauri_store(handle.clone())
+        .and_then(|store| store.clipboard_content)
+        .map(|c| c.content)
+        .filter(|c| !c.is_empty())
+}
+
+/**
+ * fill the placeholders, validate and send the payload, then remember it as the last push
+ */
+pub async fn send_push(
+    handle: &AppHandle,
+    device: &Device,
+    bundle_id: &str,
+    payload: &str,
+) -> Result<(), String> {
+    let schema = get_clipboard_schema(handle);
+    let payload = fill_template(payload, schema.as_deref())?;
+    let value = validate_payload(&payload)?;
+    let backend = get_simctl_backend(handle);
+    device
+        .send_push(backend.as_ref(), bundle_id, &value)
+        .await?;
+    let mut push = get_push_store(handle);
+    push.last_push = Some(LastPush {
+        udid: device.udid.clone(),
+        bundle_id: bundle_id.to_string(),
+        payload: value.to_string(),
+    });
+    set_push_store(handle, push)
+}
+
+/**
+ * resend the last payload to the same device & bundle id
+ */
+pub async fn resend_last_push(handle: &AppHandle) -> Result<(), String> {
+    let last_push = get_push_store(handle)
+        .last_push
+        .ok_or_else(|| "no push has been sent".to_string())?;
+    let value = validate_payload(&last_push.payload)?;
+    let backend = get_simctl_backend(handle);
+    super::command::send_push(
+        backend.as_ref(),
+        &last_push.udid,
+        &last_push.bundle_id,
+        &value,
+    )
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_the_payload() {
+        let value =
+            validate_payload(r#"{ "aps" : { "alert" : "hi" }, "url" : "costa://" }"#).unwrap();
+        assert_eq!(value["aps"]["alert"], "hi");
+        assert!(matches!(
+            validate_payload("{"),
+            Err(PushError::InvalidJson(_))
+        ));
+        assert_eq!(validate_payload("[]"), Err(PushError::NotObject));
+        assert_eq!(
+            validate_payload(r#"{ "alert" : "hi" }"#),
+            Err(PushError::MissingAps)
+        );
+        assert_eq!(
+            validate_payload(r#"{ "aps" : "hi" }"#),
+            Err(PushError::MissingAps)
+        );
+    }
+
+    #[test]
+    fn the_size_limit_applies_to_the_compact_payload() {
+        // `{"aps":{"alert":"<text>"}}` is 20 bytes plus the text
+        let fits = format!(
+            r#"{{"aps":{{"alert":"{}"}}}}"#,
+            "a".repeat(MAX_PAYLOAD_BYTES - 20)
+        );
+        assert!(validate_payload(&fits).is_ok());
+        // the whitespace is not counted
+        let pretty = format!(
+            r#"{{ "aps" : {{ "alert" : "{}" }} }}"#,
+            "a".repeat(MAX_PAYLOAD_BYTES - 20)
+        );
+        assert!(validate_payload(&pretty).is_ok());
+        let too_large = format!(
+            r#"{{"aps":{{"alert":"{}"}}}}"#,
+            "a".repeat(MAX_PAYLOAD_BYTES - 19)
+        );
+        assert_eq!(
+            validate_payload(&too_large),
+            Err(PushError::TooLarge {
+                size: MAX_PAYLOAD_BYTES + 1,
+                limit: MAX_PAYLOAD_BYTES
+            })
+        );
+    }
+
+    #[test]
+    fn fills_the_schema_as_a_json_string() {
+        let template = r#"{ "aps" : { "alert" : "open" }, "url" : "{{schema}}" }"#;
+        let schema = r#"costa://open?title="a\b"&path=C:\tmp"#;
+        let payload = fill_template(template, Some(schema)).unwrap();
+        let value = validate_payload(&payload).unwrap();
+        assert_eq!(value["url"], schema);
+    }
+
+    #[test]
+    fn a_template_without_placeholder_needs_no_schema() {
+        let template = r#"{ "aps" : { "alert" : "hi" } }"#;
+        assert_eq!(fill_template(template, None).unwrap(), template);
+        assert_eq!(
+            fill_template(r#"{ "url" : "{{schema}}" }"#, None),
+            Err(PushError::MissingSchema)
+        );
+    }
+}
@@ -0,0 +1,362 @@
+/**
+ * This module runs external processes on tokio, so a hanging simctl does not block the runtime thread.
+ * Every process is killed when it times out or is cancelled,
+ * and the number of processes running at the same time is limited.
+ */
+use std::{collections::HashMap, process::Stdio, sync::Arc, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use debug_print::debug_println;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
+    process::{Child, ChildStdout},
+    sync::Semaphore,
+};
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    backend::{LineSource, SimctlOutput, SimctlProcess},
+    error::SimctlError,
+};
+
+/** max number of processes running at the same time */
+pub const MAX_CONCURRENT_PROCESSES: usize = 4;
+/** time to wait for a spawned process to exit after SIGINT before killing it */
+pub const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    pub timeout: Duration,
+    pub cancel: Option<CancellationToken>,
+    /** written to the stdin of the process, e.g. the content of `simctl pbcopy` */
+    pub stdin: Option<String>,
+}
+
+impl RunOptions {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            cancel: None,
+            stdin: None,
+        }
+    }
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+    pub fn with_stdin(mut self, stdin: String) -> Self {
+        self.stdin = Some(stdin);
+        self
+    }
+    /**
+     * default timeout by simctl sub command
+     * runtime add/delete may copy several GB, and building a dyld cache takes minutes
+     * boot & install take a while on a cold device
+     */
+    pub fn for_simctl(args: &[&str]) -> Self {
+        let timeout = match args {
+            ["runtime", "add", ..]
+            | ["runtime", "delete", ..]
+            | ["runtime", "dyld_shared_cache", ..] => Duration::from_secs(30 * 60),
+            ["boot", ..] | ["install", ..] | ["erase", ..] => Duration::from_secs(3 * 60),
+            ["list", ..] | ["runtime", "list", ..] => Duration::from_secs(30),
+            _ => Duration::from_secs(60),
+        };
+        Self::new(timeout)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessRunner {
+    permits: Arc<Semaphore>,
+}
+
+impl Default for ProcessRunner {
+    fn default() -> Self {
+        Self::new(MAX_CONCURRENT_PROCESSES)
+    }
+}
+
+impl ProcessRunner {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+        }
+    }
+    /**
+     * run `<program> <args>` and capture the output
+     * the child is killed on drop, so both timeout and cancellation terminate the process, also while writing stdin
+     */
+    pub async fn run(
+        &self,
+        program: &str,
+        args: &[&str],
+        options: RunOptions,
+    ) -> Result<SimctlOutput, SimctlError> {
+        let command = format!("{} {}", program, args.join(" "));
+        let cancel = options.cancel.unwrap_or_default();
+        let _permit = tokio::select! {
+            permit = self.permits.acquire() => permit.map_err(|e| SimctlError::Spawn(e.to_string()))?,
+            _ = cancel.cancelled() => return Err(SimctlError::Cancelled(command)),
+        };
+        debug_println!("run process: {}", command);
+        let stdin = if options.stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        };
+        let mut child = tokio::process::Command::new(program)
+            .args(args)
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let pipe = child.stdin.take();
+        // written while the output is read, a process filling its stdout before reading stdin would block the write
+        let write_stdin = async move {
+            match (options.stdin, pipe) {
+                (Some(input), Some(mut pipe)) => match pipe.write_all(input.as_bytes()).await {
+                    // the process exited without reading it all, its status tells
+                    Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+                    // dropping the pipe closes it, so the process sees EOF
+                    res => res,
+                },
+                _ => Ok(()),
+            }
+        };
+        let output = async {
+            let (written, output) = tokio::join!(write_stdin, child.wait_with_output());
+            written?;
+            output
+        };
+        tokio::select! {
+            output = tokio::time::timeout(options.timeout, output) => match output {
+                Ok(output) => {
+                    let output = output?;
+                    Ok(SimctlOutput {
+                        status: output.status.code(),
+                        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+                        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                    })
+                }
+                Err(_) => Err(SimctlError::Timeout(command)),
+            },
+            _ = cancel.cancelled() => Err(SimctlError::Cancelled(command)),
+        }
+    }
+    /**
+     * spawn `<program> <args>` and return the handle without waiting
+     * long running processes do not take a concurrency permit, they would starve the short ones
+     */
+    pub fn spawn(&self, program: &str, args: &[&str]) -> Result<ChildProcess, SimctlError> {
+        let command = format!("{} {}", program, args.join(" "));
+        debug_println!("spawn process: {}", command);
+        let child = tokio::process::Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+        Ok(ChildProcess { child, command })
+    }
+}
+
+/**
+ * A process spawned by `ProcessRunner::spawn`, killed on drop
+ */
+#[derive(Debug)]
+pub struct ChildProcess {
+    pub child: Child,
+    pub command: String,
+}
+
+#[async_trait]
+impl SimctlProcess for ChildProcess {
+    async fn stop(&mut self) -> Result<Option<i32>, SimctlError> {
+        if let Some(pid) = self.child.id() {
+            // SIGINT lets simctl finalize the output, e.g. write the trailer of the video file
+            unsafe {
+                libc::kill(pid as libc::pid_t, libc::SIGINT);
+            }
+        }
+        match tokio::time::timeout(STOP_TIMEOUT, self.child.wait()).await {
+            Ok(status) => Ok(status?.code()),
+            Err(_) => {
+                let _ = self.child.kill().await;
+                Err(SimctlError::Timeout(self.command.clone()))
+            }
+        }
+    }
+    fn take_stdout(&mut self) -> Option<Box<dyn LineSource>> {
+        let stdout = self.child.stdout.take()?;
+        Some(Box::new(ChildLines(BufReader::new(stdout).lines())))
+    }
+}
+
+/**
+ * stdout lines of a spawned process
+ */
+pub struct ChildLines(Lines<BufReader<ChildStdout>>);
+
+#[async_trait]
+impl LineSource for ChildLines {
+    async fn next_line(&mut self) -> Option<String> {
+        self.0.next_line().await.ok().flatten()
+    }
+}
+
+/**
+ * Cancellation tokens of the long running operations started from the webview
+ * key: operation id given by the webview
+ */
+#[derive(Default)]
+pub struct CancelRegistry {
+    tokens: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl CancelRegistry {
+    pub fn register(&self, operation_id: &str) -> CancellationToken {
+        let token = CancellationToken::new();
+        self.tokens
+            .lock()
+            .unwrap()
+            .insert(operation_id.to_string(), token.clone());
+        token
+    }
+    /** cancel the operation, return false if the operation is not running */
+    pub fn cancel(&self, operation_id: &str) -> bool {
+        match self.tokens.lock().unwrap().remove(operation_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+    pub fn remove(&self, operation_id: &str) {
+        self.tokens.lock().unwrap().remove(operation_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn run_options(timeout_ms: u64) -> RunOptions {
+        RunOptions::new(Duration::from_millis(timeout_ms))
+    }
+
+    #[tokio::test]
+    async fn writes_stdin_while_reading_the_output() {
+        let runner = ProcessRunner::default();
+        let output = runner
+            .run(
+                "cat",
+                &[],
+                run_options(5000).with_stdin("hello".to_string()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(output.status, Some(0));
+        assert_eq!(output.stdout, "hello");
+
+        // more than the pipe buffers, written before the process reads stdin
+        let input = "x".repeat(1024 * 1024);
+        let output = runner
+            .run(
+                "sh",
+                &["-c", "head -c 1048576 /dev/zero | tr '\\0' y; cat"],
+                run_options(10_000).with_stdin(input.clone()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(output.stdout.len(), 2 * input.len());
+        assert!(output.stdout.ends_with(&input));
+    }
+
+    #[tokio::test]
+    async fn times_out_a_process_not_reading_stdin() {
+        let started = Instant::now();
+        let res = ProcessRunner::default()
+            .run(
+                "sleep",
+                &["30"],
+                run_options(300).with_stdin("x".repeat(1024 * 1024)),
+            )
+            .await;
+        assert!(matches!(res, Err(SimctlError::Timeout(_))), "{:?}", res);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn timeout_kills_the_process() {
+        let dir = TempDir::new("runner-timeout");
+        let marker = dir.join("finished");
+        let script = format!("sleep 1; touch '{}'", marker.to_string_lossy());
+        let res = ProcessRunner::default()
+            .run("sh", &["-c", &script], run_options(200))
+            .await;
+        assert!(matches!(res, Err(SimctlError::Timeout(_))), "{:?}", res);
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!marker.exists());
+    }
+
+    #[tokio::test]
+    async fn cancel_stops_the_process() {
+        let cancel = CancellationToken::new();
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            canceller.cancel();
+        });
+        let started = Instant::now();
+        let res = ProcessRunner::default()
+            .run("sleep", &["30"], run_options(30_000).with_cancel(cancel))
+            .await;
+        assert!(matches!(res, Err(SimctlError::Cancelled(_))), "{:?}", res);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // a cancelled token does not even take a permit
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let runner = ProcessRunner::new(0);
+        let res = runner
+            .run("true", &[], run_options(1000).with_cancel(cancel))
+            .await;
+        assert!(matches!(res, Err(SimctlError::Cancelled(_))), "{:?}", res);
+    }
+
+    #[tokio::test]
+    async fn limits_the_concurrent_processes() {
+        let dir = TempDir::new("runner-concurrency");
+        // each process counts the processes running with it
+        let script = format!(
+            "cd '{}' && touch $$ && sleep 0.2 && ls | wc -l && rm $$",
+            dir.path().to_string_lossy()
+        );
+        let runner = ProcessRunner::default();
+        let runs = (0..MAX_CONCURRENT_PROCESSES * 3).map(|_| {
+            let runner = runner.clone();
+            let script = script.clone();
+            tokio::spawn(async move {
+                let output = runner
+                    .run("sh", &["-c", &script], run_options(10_000))
+                    .await
+                    .unwrap();
+                output.stdout.trim().parse::<usize>().unwrap()
+            })
+        });
+        let mut running = vec![];
+        for run in runs.collect::<Vec<_>>() {
+            running.push(run.await.unwrap());
+        }
+        let max = running.into_iter().max().unwrap();
+        assert!(max <= MAX_CONCURRENT_PROCESSES, "{} running", max);
+        assert!(max > 1, "the processes run one by one");
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use chrono::{DateTime, Utc};
 use debug_print::debug_println;
 use serde::{Deserialize, Serialize};
 
@@ -51,3 +52,59 @@ pub struct Runtime {
     #[serde(rename = "unusableErrorMessage")]
     pub unusable_error_message: Option<String>,
 }
+
+/** state of a runtime which can boot devices */
+pub const RUNTIME_STATE_READY: &str = "Ready";
+
+impl Runtime {
+    /**
+     * the reason the runtime cannot be used, None if it is ready
+     */
+    pub fn unusable_reason(&self) -> Option<String> {
+        match self.unusable_error_message.as_ref() {
+            Some(message) => Some(message.clone()),
+            None if self.state != RUNTIME_STATE_READY => Some(format!("state: {}", self.state)),
+            None => None,
+        }
+    }
+    pub fn last_used(&self) -> Option<DateTime<Utc>> {
+        self.last_used_at
+            .as_ref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&Utc))
+    }
+    /**
+     * days since the runtime was last used, None if it was never used
+     */
+    pub fn unused_days(&self, now: DateTime<Utc>) -> Option<i64> {
+        self.last_used().map(|t| (now - t).num_days())
+    }
+}
+
+// {
+//   "iphoneos17.5" : {
+//     "chosenRuntimeBuild" : "21F79",
+//     "defaultBuild" : "21F79",
+//     "platform" : "com.apple.platform.iphoneos",
+//     "preferredBuild" : "21F79",
+//     "sdkBuild" : "21F77",
+//     "sdkDirectory" : "\/Applications\/Xcode.app\/Contents\/Developer\/Platforms\/iPhoneOS.platform\/Developer\/SDKs\/iPhoneOS17.5.sdk",
+//     "sdkVersion" : "17.5"
+//   }
+// }
+
+/**
+ * The runtime chosen for an SDK of Xcode, an entry of `xcrun simctl runtime match list --json`
+ */
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RuntimeMatch {
+    pub platform: String,
+    pub sdk_version: String,
+    pub sdk_build: String,
+    pub sdk_directory: Option<String>,
+    pub default_build: Option<String>,
+    pub preferred_build: Option<String>,
+    /** None if no installed runtime matches the SDK */
+    pub chosen_runtime_build: Option<String>,
+}
@@ -0,0 +1,435 @@
+/**
+ * This module summarizes the installed simulator runtimes and plans their cleanup.
+ * A prune plan is always computed first (dry run), only the runtimes still in a fresh plan are deleted.
+ */
+use std::{collections::HashMap, path::PathBuf};
+
+use chrono::Utc;
+use debug_print::debug_println;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use crate::path::get_user_home;
+
+use super::{
+    backend::SimctlBackend,
+    command,
+    error::SimctlError,
+    install::compare_versions,
+    runtime::{Runtime, RuntimeMatch},
+};
+
+/** runtimes not used for this many days are flagged as unused by default */
+pub const DEFAULT_RUNTIME_UNUSED_DAYS: i64 = 90;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DyldCacheStatus {
+    /** the shared cache of the runtime is built */
+    Built,
+    /** not built yet, the first boot of a device will be slow */
+    Missing,
+    /** the cache folder cannot be read */
+    Unknown,
+}
+
+/**
+ * The runtime with its usage
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RuntimeStatus {
+    pub runtime: Runtime,
+    /** number of devices created with the runtime */
+    pub devices: usize,
+    /** why the runtime cannot be used, None if it is ready */
+    pub unusable: Option<String>,
+    /** days since the last use, None if never used */
+    pub unused_days: Option<i64>,
+    /** the runtime is not used for the threshold days and has no device */
+    pub unused: bool,
+    /** sdks of Xcode this runtime is chosen for, e.g. "iphoneos17.5" */
+    pub matched_sdks: Vec<String>,
+    pub dyld_cache: DyldCacheStatus,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RuntimeSummary {
+    pub total_bytes: u64,
+    /** bytes of the runtimes which can be deleted */
+    pub deletable_bytes: u64,
+    /** bytes of the unusable & unused runtimes */
+    pub reclaimable_bytes: u64,
+    pub runtimes: Vec<RuntimeStatus>,
+    /** sdks without an installed runtime */
+    pub unmatched_sdks: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PruneCandidate {
+    pub identifier: String,
+    pub version: String,
+    pub build: String,
+    pub size_bytes: u64,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PrunePlan {
+    pub unused_days: i64,
+    pub candidates: Vec<PruneCandidate>,
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PruneResult {
+    pub identifier: String,
+    pub error: Option<String>,
+}
+
+/**
+ * the dyld caches are built per host OS build, `<host build>/<runtime identifier>.<runtime build>`
+ */
+fn get_dyld_cache_root() -> PathBuf {
+    get_user_home().join("Library/Developer/CoreSimulator/Caches/dyld")
+}
+
+pub fn get_dyld_cache_status(runtime: &Runtime) -> DyldCacheStatus {
+    let name = format!("{}.{}", runtime.runtime_identifier, runtime.build);
+    let host_builds = match std::fs::read_dir(get_dyld_cache_root()) {
+        Ok(entries) => entries,
+        // no cache has ever been built
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return DyldCacheStatus::Missing,
+        Err(_) => return DyldCacheStatus::Unknown,
+    };
+    let built = host_builds
+        .filter_map(|e| e.ok())
+        .any(|host_build| host_build.path().join(&name).is_dir());
+    if built {
+        DyldCacheStatus::Built
+    } else {
+        DyldCacheStatus::Missing
+    }
+}
+
+/**
+ * build the status of each runtime, pure except for the dyld cache lookup
+ */
+pub fn build_runtime_statuses(
+    runtimes: &HashMap<String, Runtime>,
+    device_counts: &HashMap<String, usize>,
+    matches: &HashMap<String, RuntimeMatch>,
+    unused_threshold: i64,
+) -> Vec<RuntimeStatus> {
+    let now = Utc::now();
+    let mut statuses = runtimes
+        .values()
+        .map(|runtime| {
+            let devices = device_counts
+                .get(&runtime.runtime_identifier)
+                .copied()
+                .unwrap_or(0);
+            let unused_days = runtime.unused_days(now);
+            let mut matched_sdks = matches
+                .iter()
+                .filter(|(_, m)| m.chosen_runtime_build.as_deref() == Some(runtime.build.as_str()))
+                .map(|(sdk, _)| sdk.clone())
+                .collect::<Vec<_>>();
+            matched_sdks.sort();
+            RuntimeStatus {
+                devices,
+                unusable: runtime.unusable_reason(),
+                unused: devices == 0 && unused_days.unwrap_or(i64::MAX) >= unused_threshold,
+                unused_days,
+                matched_sdks,
+                dyld_cache: get_dyld_cache_status(runtime),
+                runtime: runtime.clone(),
+            }
+        })
+        .collect::<Vec<_>>();
+    statuses.sort_by(|a, b| compare_versions(&b.runtime.version, &a.runtime.version));
+    statuses
+}
+
+/**
+ * the runtimes to delete, deletable & (unusable or unused), runtimes chosen for an SDK are kept
+ */
+pub fn plan_prune(statuses: &[RuntimeStatus], unused_days: i64) -> PrunePlan {
+    let candidates = statuses
+        .iter()
+        .filter(|s| s.runtime.deletable && s.matched_sdks.is_empty())
+        .filter_map(|s| {
+            let reason = match (&s.unusable, s.unused) {
+                (Some(reason), _) => format!("unusable: {}", reason),
+                (None, true) => match s.unused_days {
+                    Some(days) => format!("not used for {} days", days),
+                    None => "never used".to_string(),
+                },
+                (None, false) => return None,
+            };
+            Some(PruneCandidate {
+                identifier: s.runtime.identifier.clone(),
+                version: s.runtime.version.clone(),
+                build: s.runtime.build.clone(),
+                size_bytes: s.runtime.size_bytes,
+                reason,
+            })
+        })
+        .collect::<Vec<_>>();
+    PrunePlan {
+        unused_days,
+        reclaimable_bytes: candidates.iter().map(|c| c.size_bytes).sum(),
+        candidates,
+    }
+}
+
+/**
+ * list the runtimes, devices & sdk matches and build the statuses
+ */
+pub async fn get_runtime_statuses(
+    backend: &dyn SimctlBackend,
+    unused_days: i64,
+) -> Result<(Vec<RuntimeStatus>, HashMap<String, RuntimeMatch>), SimctlError> {
+    let runtimes = command::get_all_runtimes(backend).await?;
+    let device_counts = command::get_all_devices(backend)
+        .await?
+        .devices
+        .iter()
+        .map(|(runtime, devices)| (runtime.clone(), devices.len()))
+        .collect::<HashMap<_, _>>();
+    // `runtime match` needs Xcode 15+, the runtimes are still listed without it
+    let matches = command::get_runtime_matches(backend)
+        .await
+        .unwrap_or_else(|e| {
+            debug_println!("runtime match error: {}", e);
+            HashMap::new()
+        });
+    let statuses = build_runtime_statuses(&runtimes, &device_counts, &matches, unused_days);
+    Ok((statuses, matches))
+}
+
+pub async fn get_runtime_summary(
+    backend: &dyn SimctlBackend,
+    unused_days: i64,
+) -> Result<RuntimeSummary, SimctlError> {
+    let (runtimes, matches) = get_runtime_statuses(backend, unused_days).await?;
+    let mut unmatched_sdks = matches
+        .iter()
+        .filter(|(_, m)| m.chosen_runtime_build.is_none())
+        .map(|(sdk, _)| sdk.clone())
+        .collect::<Vec<_>>();
+    unmatched_sdks.sort();
+    Ok(RuntimeSummary {
+        total_bytes: runtimes.iter().map(|s| s.runtime.size_bytes).sum(),
+        deletable_bytes: runtimes
+            .iter()
+            .filter(|s| s.runtime.deletable)
+            .map(|s| s.runtime.size_bytes)
+            .sum(),
+        reclaimable_bytes: plan_prune(&runtimes, unused_days).reclaimable_bytes,
+        runtimes,
+        unmatched_sdks,
+    })
+}
+
+pub async fn get_prune_plan(
+    backend: &dyn SimctlBackend,
+    unused_days: i64,
+) -> Result<PrunePlan, SimctlError> {
+    let (statuses, _) = get_runtime_statuses(backend, unused_days).await?;
+    Ok(plan_prune(&statuses, unused_days))
+}
+
+/**
+ * delete the confirmed runtimes of the plan one by one
+ * the plan is computed again, a runtime which is no longer a candidate is skipped
+ */
+pub async fn prune_runtimes(
+    backend: &dyn SimctlBackend,
+    identifiers: &[String],
+    unused_days: i64,
+    cancel: Option<CancellationToken>,
+) -> Result<Vec<PruneResult>, SimctlError> {
+    let plan = get_prune_plan(backend, unused_days).await?;
+    let mut results = vec![];
+    for identifier in identifiers {
+        if !plan.candidates.iter().any(|c| &c.identifier == identifier) {
+            results.push(PruneResult {
+                identifier: identifier.clone(),
+                error: Some("no longer a prune candidate".to_string()),
+            });
+            continue;
+        }
+        debug_println!("prune runtime: {}", identifier);
+        let error = match command::delete_runtime(backend, identifier.clone(), cancel.clone()).await
+        {
+            Ok(_) => None,
+            // stop the remaining deletes too
+            Err(e @ SimctlError::Cancelled(_)) => return Err(e),
+            Err(e) => Some(e.to_string()),
+        };
+        results.push(PruneResult {
+            identifier: identifier.clone(),
+            error,
+        });
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::backend::RecordedBackend;
+
+    /** a deletable & ready runtime, used `unused_days` ago */
+    fn status(identifier: &str, unused_days: Option<i64>) -> RuntimeStatus {
+        RuntimeStatus {
+            runtime: Runtime {
+                identifier: identifier.to_string(),
+                version: "17.5".to_string(),
+                build: "21F79".to_string(),
+                state: "Ready".to_string(),
+                deletable: true,
+                size_bytes: 100,
+                ..Default::default()
+            },
+            devices: 0,
+            unusable: None,
+            unused_days,
+            unused: unused_days.unwrap_or(i64::MAX) >= DEFAULT_RUNTIME_UNUSED_DAYS,
+            matched_sdks: vec![],
+            dyld_cache: DyldCacheStatus::Built,
+        }
+    }
+
+    fn candidate_reasons(plan: &PrunePlan) -> Vec<(&str, &str)> {
+        plan.candidates
+            .iter()
+            .map(|c| (c.identifier.as_str(), c.reason.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn statuses_are_sorted_by_version_components() {
+        let runtimes = ["9.3", "17.5", "18.0", "17.0.1"]
+            .iter()
+            .map(|version| {
+                let runtime = Runtime {
+                    identifier: version.to_string(),
+                    version: version.to_string(),
+                    ..Default::default()
+                };
+                (version.to_string(), runtime)
+            })
+            .collect::<HashMap<_, _>>();
+        let statuses = build_runtime_statuses(
+            &runtimes,
+            &HashMap::new(),
+            &HashMap::new(),
+            DEFAULT_RUNTIME_UNUSED_DAYS,
+        );
+        let versions = statuses
+            .iter()
+            .map(|status| status.runtime.version.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(versions, vec!["18.0", "17.5", "17.0.1", "9.3"]);
+    }
+
+    #[test]
+    fn plans_the_unused_and_unusable_runtimes() {
+        let mut broken = status("broken", Some(1));
+        broken.unusable = Some("state: Unusable".to_string());
+        let statuses = vec![
+            status("stale", Some(120)),
+            status("never", None),
+            status("recent", Some(3)),
+            broken,
+        ];
+        let plan = plan_prune(&statuses, DEFAULT_RUNTIME_UNUSED_DAYS);
+        assert_eq!(
+            candidate_reasons(&plan),
+            vec![
+                ("stale", "not used for 120 days"),
+                ("never", "never used"),
+                ("broken", "unusable: state: Unusable"),
+            ]
+        );
+        assert_eq!(plan.reclaimable_bytes, 300);
+    }
+
+    #[test]
+    fn keeps_the_runtimes_which_cannot_or_should_not_be_deleted() {
+        let mut system = status("system", None);
+        system.runtime.deletable = false;
+        let mut chosen = status("chosen", None);
+        chosen.unusable = Some("state: Unusable".to_string());
+        chosen.matched_sdks = vec!["iphoneos17.5".to_string()];
+        let plan = plan_prune(&[system, chosen], DEFAULT_RUNTIME_UNUSED_DAYS);
+        assert!(plan.candidates.is_empty());
+        assert_eq!(plan.reclaimable_bytes, 0);
+    }
+
+    #[test]
+    fn a_runtime_with_devices_is_not_unused() {
+        let runtimes = ["with-devices", "without-devices"]
+            .iter()
+            .map(|identifier| {
+                let runtime = Runtime {
+                    identifier: identifier.to_string(),
+                    runtime_identifier: identifier.to_string(),
+                    last_used_at: Some("2020-01-01T00:00:00Z".to_string()),
+                    ..Default::default()
+                };
+                (identifier.to_string(), runtime)
+            })
+            .collect::<HashMap<_, _>>();
+        let device_counts = HashMap::from([("with-devices".to_string(), 2)]);
+        let statuses = build_runtime_statuses(
+            &runtimes,
+            &device_counts,
+            &HashMap::new(),
+            DEFAULT_RUNTIME_UNUSED_DAYS,
+        );
+        let unused = statuses
+            .iter()
+            .map(|s| (s.runtime.identifier.as_str(), s.unused))
+            .collect::<HashMap<_, _>>();
+        assert!(!unused["with-devices"]);
+        assert!(unused["without-devices"]);
+    }
+
+    #[tokio::test]
+    async fn prunes_only_the_runtimes_still_in_a_fresh_plan() {
+        let runtimes_json = format!(
+            r#"{{
+              "STALE" : {{ "identifier" : "STALE", "runtimeIdentifier" : "com.apple.CoreSimulator.SimRuntime.iOS-16-4", "version" : "16.4", "build" : "20E247", "state" : "Ready", "deletable" : true, "sizeBytes" : 100, "lastUsedAt" : "2020-01-01T00:00:00Z" }},
+              "RECENT" : {{ "identifier" : "RECENT", "runtimeIdentifier" : "com.apple.CoreSimulator.SimRuntime.iOS-17-5", "version" : "17.5", "build" : "21F79", "state" : "Ready", "deletable" : true, "sizeBytes" : 100, "lastUsedAt" : "{}" }}
+            }}"#,
+            Utc::now().to_rfc3339()
+        );
+        let backend = RecordedBackend::new()
+            .with_runtimes_json(&runtimes_json)
+            .with_devices_json(r#"{ "devices" : {} }"#);
+
+        let results = prune_runtimes(
+            &backend,
+            &["STALE".to_string(), "RECENT".to_string()],
+            DEFAULT_RUNTIME_UNUSED_DAYS,
+            None,
+        )
+        .await
+        .unwrap();
+        let errors = results
+            .iter()
+            .map(|r| (r.identifier.as_str(), r.error.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                ("STALE", None),
+                ("RECENT", Some("no longer a prune candidate")),
+            ]
+        );
+        assert!(backend.has_command(&["runtime", "delete", "STALE"]));
+        assert!(!backend.has_command(&["runtime", "delete", "RECENT"]));
+    }
+}
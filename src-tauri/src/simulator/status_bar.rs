@@ -0,0 +1,301 @@
+/**
+ * This module overrides the simulator status bar by `xcrun simctl status_bar`.
+ * Named presets are kept in the store, and a preset can be applied automatically before screenshots.
+ */
+use std::{collections::HashMap, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use strum_macros::{Display, EnumString};
+use tauri::AppHandle;
+
+use crate::sotre::{get_tauri_store, update_tauri_store, StoreKey};
+
+/** the preset applied before screenshots by default */
+pub const DEFAULT_STATUS_BAR_PRESET: &str = "Clean";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, EnumString, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum DataNetwork {
+    Hide,
+    Wifi,
+    #[serde(rename = "3g")]
+    #[strum(serialize = "3g")]
+    ThreeG,
+    #[serde(rename = "4g")]
+    #[strum(serialize = "4g")]
+    FourG,
+    Lte,
+    #[serde(rename = "lte-a")]
+    #[strum(serialize = "lte-a")]
+    LteA,
+    #[serde(rename = "lte+")]
+    #[strum(serialize = "lte+")]
+    LtePlus,
+    #[serde(rename = "5g")]
+    #[strum(serialize = "5g")]
+    FiveG,
+    #[serde(rename = "5g+")]
+    #[strum(serialize = "5g+")]
+    FiveGPlus,
+    #[serde(rename = "5g-uwb")]
+    #[strum(serialize = "5g-uwb")]
+    FiveGUwb,
+    #[serde(rename = "5g-uc")]
+    #[strum(serialize = "5g-uc")]
+    FiveGUc,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, EnumString, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum WifiMode {
+    Searching,
+    Failed,
+    Active,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, EnumString, Display)]
+#[serde(rename_all = "camelCase")]
+#[strum(serialize_all = "camelCase")]
+pub enum CellularMode {
+    NotSupported,
+    Searching,
+    Failed,
+    Active,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, EnumString, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum BatteryState {
+    Charging,
+    Charged,
+    Discharging,
+}
+
+/**
+ * Options of `simctl status_bar override`, fields which are None are left unchanged
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct StatusBarOverride {
+    /** any text, e.g. "9:41" or an ISO date "2007-01-09T09:41:00+00:00" */
+    pub time: Option<String>,
+    pub data_network: Option<DataNetwork>,
+    pub wifi_mode: Option<WifiMode>,
+    /** 0 ~ 3 */
+    pub wifi_bars: Option<u8>,
+    pub cellular_mode: Option<CellularMode>,
+    /** 0 ~ 4 */
+    pub cellular_bars: Option<u8>,
+    pub operator_name: Option<String>,
+    pub battery_state: Option<BatteryState>,
+    /** 0 ~ 100 */
+    pub battery_level: Option<u8>,
+}
+
+impl StatusBarOverride {
+    /**
+     * the 9:41 status bar of Apple marketing screenshots
+     */
+    pub fn clean() -> Self {
+        Self {
+            time: Some("9:41".to_string()),
+            data_network: Some(DataNetwork::Wifi),
+            wifi_mode: Some(WifiMode::Active),
+            wifi_bars: Some(3),
+            cellular_mode: Some(CellularMode::Active),
+            cellular_bars: Some(4),
+            operator_name: Some("".to_string()),
+            battery_state: Some(BatteryState::Charged),
+            battery_level: Some(100),
+        }
+    }
+    pub fn validate(&self) -> Result<(), String> {
+        let ranges = [
+            ("wifiBars", self.wifi_bars, 3),
+            ("cellularBars", self.cellular_bars, 4),
+            ("batteryLevel", self.battery_level, 100),
+        ];
+        for (name, value, max) in ranges.iter() {
+            if let Some(value) = value {
+                if value > max {
+                    return Err(format!("{} must be between 0 and {}", name, max));
+                }
+            }
+        }
+        if self.override_args().is_empty() {
+            return Err("no status bar field is set".to_string());
+        }
+        Ok(())
+    }
+    /**
+     * the options after `simctl status_bar <udid> override`
+     */
+    pub fn override_args(&self) -> Vec<String> {
+        let mut args = vec![];
+        let mut push = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                args.push(format!("--{}", name));
+                args.push(value);
+            }
+        };
+        push("time", self.time.clone());
+        push("dataNetwork", self.data_network.map(|v| v.to_string()));
+        push("wifiMode", self.wifi_mode.map(|v| v.to_string()));
+        push("wifiBars", self.wifi_bars.map(|v| v.to_string()));
+        push("cellularMode", self.cellular_mode.map(|v| v.to_string()));
+        push("cellularBars", self.cellular_bars.map(|v| v.to_string()));
+        push("operatorName", self.operator_name.clone());
+        push("batteryState", self.battery_state.map(|v| v.to_string()));
+        push("batteryLevel", self.battery_level.map(|v| v.to_string()));
+        args
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StatusBarPreset {
+    pub name: String,
+    pub status_bar: StatusBarOverride,
+}
+
+pub fn default_status_bar_presets() -> Vec<StatusBarPreset> {
+    vec![StatusBarPreset {
+        name: DEFAULT_STATUS_BAR_PRESET.to_string(),
+        status_bar: StatusBarOverride::clean(),
+    }]
+}
+
+pub fn get_status_bar_presets(handle: &AppHandle) -> Vec<StatusBarPreset> {
+    get_tauri_store(handle.clone())
+        .and_then(|store| store.status_bar_presets)
+        .unwrap_or_else(default_status_bar_presets)
+}
+
+pub fn set_status_bar_presets(
+    handle: &AppHandle,
+    presets: Vec<StatusBarPreset>,
+) -> Result<(), String> {
+    update_tauri_store(handle.clone(), StoreKey::StatusBarPresets, json!(presets))
+        .map_err(|e| e.to_string())
+}
+
+pub fn get_status_bar_preset(handle: &AppHandle, name: &str) -> Option<StatusBarPreset> {
+    get_status_bar_presets(handle)
+        .into_iter()
+        .find(|p| p.name == name)
+}
+
+/**
+ * Presets applied from the webview managed by tauri, screenshots keep them instead of the automatic preset
+ * key: device udid
+ * value: preset name
+ */
+#[derive(Default)]
+pub struct StatusBarOverrides(Mutex<HashMap<String, String>>);
+
+impl StatusBarOverrides {
+    pub fn get(&self, udid: &str) -> Option<String> {
+        self.0.lock().unwrap().get(udid).cloned()
+    }
+    pub fn insert(&self, udid: &str, name: &str) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(udid.to_string(), name.to_string());
+    }
+    pub fn remove(&self, udid: &str) {
+        self.0.lock().unwrap().remove(udid);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::{backend::RecordedBackend, command::override_status_bar};
+
+    #[tokio::test]
+    async fn overrides_by_the_clean_preset() {
+        let backend = RecordedBackend::new();
+        let preset = &default_status_bar_presets()[0];
+        assert_eq!(preset.name, DEFAULT_STATUS_BAR_PRESET);
+        override_status_bar(&backend, "UDID", &preset.status_bar)
+            .await
+            .unwrap();
+        assert_eq!(
+            backend.commands(),
+            vec![[
+                "status_bar",
+                "UDID",
+                "override",
+                "--time",
+                "9:41",
+                "--dataNetwork",
+                "wifi",
+                "--wifiMode",
+                "active",
+                "--wifiBars",
+                "3",
+                "--cellularMode",
+                "active",
+                "--cellularBars",
+                "4",
+                "--operatorName",
+                "",
+                "--batteryState",
+                "charged",
+                "--batteryLevel",
+                "100",
+            ]
+            .map(|arg| arg.to_string())
+            .to_vec()]
+        );
+    }
+
+    #[test]
+    fn only_the_set_fields_are_overridden() {
+        let status_bar = StatusBarOverride {
+            data_network: Some(DataNetwork::LtePlus),
+            cellular_mode: Some(CellularMode::NotSupported),
+            ..Default::default()
+        };
+        assert_eq!(
+            status_bar.override_args(),
+            vec!["--dataNetwork", "lte+", "--cellularMode", "notSupported"]
+        );
+    }
+
+    #[test]
+    fn validates_the_ranges() {
+        assert_eq!(StatusBarOverride::clean().validate(), Ok(()));
+        let cases = [
+            (
+                StatusBarOverride {
+                    wifi_bars: Some(4),
+                    ..Default::default()
+                },
+                "wifiBars must be between 0 and 3",
+            ),
+            (
+                StatusBarOverride {
+                    cellular_bars: Some(5),
+                    ..Default::default()
+                },
+                "cellularBars must be between 0 and 4",
+            ),
+            (
+                StatusBarOverride {
+                    battery_level: Some(101),
+                    ..StatusBarOverride::clean()
+                },
+                "batteryLevel must be between 0 and 100",
+            ),
+            (StatusBarOverride::default(), "no status bar field is set"),
+        ];
+        for (status_bar, error) in cases {
+            assert_eq!(status_bar.validate(), Err(error.to_string()));
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::{borrow::Borrow, ops::Deref, sync::Mutex};
+use std::{borrow::Borrow, collections::HashMap, ops::Deref, sync::Mutex};
 
 use debug_print::debug_println;
 use serde::{de::Error, Deserialize, Serialize};
@@ -10,9 +10,15 @@ use crate::{
     clipboard::ClipboardContent,
     constant::APP_NAME,
     file::check_file_if_exists,
-    host::host::Host,
+    host::{account::Account, host::Host},
+    library::package::PackageIndex,
     path::{get_app_data_dir, get_sotre_path},
-    simulator::{command::get_all_devices, device::DeviceMap},
+    preference::Preferences,
+    simulator::{
+        app::InstalledApp, backend::get_simctl_backend, capture::CaptureRecord,
+        command::get_all_devices, device::DeviceMap, location::LocationStore,
+        privacy::PermissionProfile, push::PushStore, status_bar::StatusBarPreset,
+    },
     tray::menu::TrayMenu,
 };
 use tauri::Wry;
@@ -31,6 +37,24 @@ pub enum StoreKey {
     RecentUrls,
     /** debug app host */
     DebugHosts,
+    /** screenshot & screen recording index */
+    Captures,
+    /** user preferences */
+    Preferences,
+    /** push payload templates & the last sent push */
+    Push,
+    /** saved places & routes of the location simulation */
+    Locations,
+    /** privacy permissions re-applied after an app is installed */
+    PermissionProfiles,
+    /** named status bar overrides */
+    StatusBarPresets,
+    /** installed apps listed by simctl, key: device udid */
+    InstalledApps,
+    /** index of the package library */
+    PackageLibrary,
+    /** test accounts the app is logged in with by the debug host */
+    Accounts,
 }
 
 impl StoreKey {
@@ -42,6 +66,15 @@ impl StoreKey {
             &StoreKey::ClipboardContent => "clipboard_content".to_owned(),
             &StoreKey::RecentUrls => "recent_urls".to_owned(),
             &StoreKey::DebugHosts => "debug_hosts".to_owned(),
+            &StoreKey::Captures => "captures".to_owned(),
+            &StoreKey::Preferences => "preferences".to_owned(),
+            &StoreKey::Push => "push".to_owned(),
+            &StoreKey::Locations => "locations".to_owned(),
+            &StoreKey::PermissionProfiles => "permission_profiles".to_owned(),
+            &StoreKey::StatusBarPresets => "status_bar_presets".to_owned(),
+            &StoreKey::InstalledApps => "installed_apps".to_owned(),
+            &StoreKey::PackageLibrary => "package_library".to_owned(),
+            &StoreKey::Accounts => "accounts".to_owned(),
         }
     }
 }
@@ -55,6 +88,15 @@ pub struct CostaStoreWrapper {
     pub clipboard_content: Option<ClipboardContent>,
     pub recent_urls: Option<Vec<String>>,
     pub debug_hosts: Option<Host>,
+    pub captures: Option<Vec<CaptureRecord>>,
+    pub preferences: Option<Preferences>,
+    pub push: Option<PushStore>,
+    pub locations: Option<LocationStore>,
+    pub permission_profiles: Option<Vec<PermissionProfile>>,
+    pub status_bar_presets: Option<Vec<StatusBarPreset>>,
+    pub installed_apps: Option<HashMap<String, Vec<InstalledApp>>>,
+    pub package_library: Option<PackageIndex>,
+    pub accounts: Option<Vec<Account>>,
 }
 
 impl CostaStoreWrapper {
@@ -67,6 +109,15 @@ impl CostaStoreWrapper {
             StoreKey::ClipboardContent => Some(json!(&self.clipboard_content)),
             StoreKey::RecentUrls => self.recent_urls.as_ref().map(|v| json!(v)),
             StoreKey::DebugHosts => Some(json!(&self.debug_hosts)),
+            StoreKey::Captures => Some(json!(&self.captures)),
+            StoreKey::Preferences => Some(json!(&self.preferences)),
+            StoreKey::Push => Some(json!(&self.push)),
+            StoreKey::Locations => Some(json!(&self.locations)),
+            StoreKey::PermissionProfiles => Some(json!(&self.permission_profiles)),
+            StoreKey::StatusBarPresets => Some(json!(&self.status_bar_presets)),
+            StoreKey::InstalledApps => Some(json!(&self.installed_apps)),
+            StoreKey::PackageLibrary => Some(json!(&self.package_library)),
+            StoreKey::Accounts => Some(json!(&self.accounts)),
             _ => None,
         }
     }
@@ -101,6 +152,42 @@ impl CostaStoreWrapper {
                 self.debug_hosts = serde_json::from_value(value)?;
                 Ok(())
             }
+            StoreKey::Captures => {
+                self.captures = serde_json::from_value(value)?;
+                Ok(())
+            }
+            StoreKey::Preferences => {
+                self.preferences = serde_json::from_value(value)?;
+                Ok(())
+            }
+            StoreKey::Push => {
+                self.push = serde_json::from_value(value)?;
+                Ok(())
+            }
+            StoreKey::Locations => {
+                self.locations = serde_json::from_value(value)?;
+                Ok(())
+            }
+            StoreKey::PermissionProfiles => {
+                self.permission_profiles = serde_json::from_value(value)?;
+                Ok(())
+            }
+            StoreKey::StatusBarPresets => {
+                self.status_bar_presets = serde_json::from_value(value)?;
+                Ok(())
+            }
+            StoreKey::InstalledApps => {
+                self.installed_apps = serde_json::from_value(value)?;
+                Ok(())
+            }
+            StoreKey::PackageLibrary => {
+                self.package_library = serde_json::from_value(value)?;
+                Ok(())
+            }
+            StoreKey::Accounts => {
+                self.accounts = serde_json::from_value(value)?;
+                Ok(())
+            }
             _ => Err(Box::new(std::io::Error::new(
                 std::io::ErrorKind::Other,
                 "Invalid key",
@@ -159,15 +246,29 @@ pub fn init_tauri_store<T: Into<AppHandleRef>>(app: T) {
         AppHandleRef::AppHandle(app_handle) => app_handle,
     };
     let mut store = StoreBuilder::new(app_handle.clone(), get_sotre_path()).build();
+    let backend = get_simctl_backend(&app_handle);
+    let devices = tauri::async_runtime::block_on(get_all_devices(backend.as_ref())).unwrap_or_else(|e| {
+        log::error!("get all devices error: {}", e);
+        DeviceMap::default()
+    });
     let store_content = CostaStoreWrapper {
-        simulator: get_all_devices(),
+        simulator: devices.clone(),
         tray: TrayMenu {
-            simulator: get_all_devices(),
+            simulator: devices,
         },
         recent_devices: vec![],
         clipboard_content: None,
         recent_urls: None,
         debug_hosts: None,
+        captures: None,
+        preferences: None,
+        push: None,
+        locations: None,
+        permission_profiles: None,
+        status_bar_presets: None,
+        installed_apps: None,
+        package_library: None,
+        accounts: None,
     };
     store
         .insert(APP_NAME.to_string(), json!(store_content))
@@ -6,11 +6,15 @@
  * @Description:
  */
 
+use std::collections::HashMap;
+
 use debug_print::debug_println;
-use tauri::AppHandle;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
 
 use crate::{
-    host::{host::Host, scanner::scan_local_debug_host},
+    host::{host::Host, info::HostInfo, mdns::MdnsBrowser, scanner::HostScanner},
+    preference::get_preferences,
     sotre::{get_tauri_store, update_tauri_store},
     tray::tray::init_system_tray_menu,
 };
@@ -18,33 +22,70 @@ use crate::{
 pub async fn tick(handle: AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     let mut interval = tokio::time::interval(std::time::Duration::from_millis(500));
     loop {
-        let meun_update_result = process_tray_menu_update(handle.clone());
+        let meun_update_result = process_tray_menu_update(handle.clone()).await;
         interval.tick().await;
         let host_scanner_result = process_host_scanner(handle.clone()).await;
     }
 }
 
-pub fn process_tray_menu_update(handle: AppHandle) {
-    let menu = init_system_tray_menu(None, Some(handle.clone()));
+pub async fn process_tray_menu_update(handle: AppHandle) {
+    let menu = init_system_tray_menu(handle.clone()).await;
     let result = handle.tray_handle().set_menu(menu);
 }
 
+/**
+ * The payload of the `debug_host_added` & `debug_host_removed` events
+ */
+#[derive(Serialize, Clone, Debug)]
+pub struct DebugHostEvent {
+    pub url: String,
+    pub info: HostInfo,
+}
+
 pub async fn process_host_scanner(handle: AppHandle) -> Result<(), Box<dyn std::error::Error>> {
-    let result = scan_local_debug_host().await?;
+    let settings = get_preferences(&handle).host_discovery;
+    let mut result = handle.state::<HostScanner>().scan(&settings).await;
+    // the heartbeat of a host found by both is fresher than its TXT records
+    let mdns = handle.state::<MdnsBrowser>();
+    mdns.set_enabled(settings.mdns);
+    for (url, info) in mdns.hosts() {
+        result.entry(url).or_insert(info);
+    }
     let store = get_tauri_store(handle.clone());
     if let Some(store) = store {
         let mut selected_host = None;
+        let mut previous_hosts = HashMap::new();
         if let Some(host) = store.debug_hosts.clone() {
             selected_host = host.selected_host;
+            previous_hosts = host.hosts;
+        }
+        if result == previous_hosts {
+            return Ok(());
         }
         update_tauri_store(
             handle.clone(),
             crate::sotre::StoreKey::DebugHosts,
             serde_json::to_value(Host {
                 selected_host,
-                host_map: result,
+                hosts: result.clone(),
             })?,
         )?;
+        for (url, info) in result.iter() {
+            if !previous_hosts.contains_key(url) {
+                debug_println!("debug host added: {}", url);
+                let event = DebugHostEvent {
+                    url: url.clone(),
+                    info: info.clone(),
+                };
+                let _ = handle.emit_all("debug_host_added", event);
+            }
+        }
+        for (url, info) in previous_hosts {
+            if !result.contains_key(&url) {
+                debug_println!("debug host removed: {}", url);
+                let _ = handle.emit_all("debug_host_removed", DebugHostEvent { url, info });
+            }
+        }
     }
     Ok(())
 }
@@ -10,12 +10,22 @@ use tauri::{AppHandle, Manager, SystemTrayEvent};
 use tauri_plugin_clipboard::ClipboardManager;
 
 use crate::{
+    catalog,
     clipboard::{ClipboardContent, ClipboardType},
     constant::{DEFAULT_HOST, DEFAULT_PATH},
-    host::host::HostOperation,
-    simulator::command::{
-        boot_device, find_all_web_view_windows_in_simultor, get_all_devices, open_safari_dev_tool,
-        open_simulator_app,
+    host::{account::get_account, client::HostError, host::HostOperation},
+    library::download::{self, DownloadRequest},
+    command::util::get_target_device,
+    simulator::{
+        appearance::{self, Appearance, ContentSize, DisplaySettings},
+        backend::get_simctl_backend,
+        capture::{self, RecordingSessions, ScreenshotFormat},
+        pasteboard::{self, PasteboardSync, SyncDirection},
+        push,
+        command::{
+            boot_device, find_all_web_view_windows_in_simultor, get_all_devices,
+            open_safari_dev_tool, open_simulator_app,
+        },
     },
     sotre::{get_tauri_store, set_tauri_store, update_tauri_store},
     tray::operation::OperationId,
@@ -57,17 +67,19 @@ pub fn on_system_tray_event(app_handle: &AppHandle, event: SystemTrayEvent) {
                 // open Safari developer tools
                 OperationId::Safari => {
                     // send system notification to tell user this is WIP
-                    Notification::new("WIP")
+                    if let Err(e) = Notification::new("WIP")
                         .title("This feature is under development")
                         .body("Please wait for the next release")
                         .show()
-                        .unwrap();
+                    {
+                        error!("Notification Error: {:?}", e);
+                    }
                     let app_handle = app_handle.clone();
                     // get devicemap from the store
                     let devicemap = get_tauri_store(app_handle.clone()).unwrap().simulator;
                     if let Some(device) = devicemap.get_first_booted_device() {
                         // let all_webview_windows = find_all_web_view_windows_in_simultor(&devicemap);
-                        // open_safari_dev_tool(device.udid.as_str(), None);
+                        // open_safari_dev_tool(get_simctl_backend(&app_handle).as_ref(), device.udid.as_str(), None);
                     }
                 }
                 // scan QR code in clipboard & open the url edit window
@@ -93,16 +105,16 @@ pub fn on_system_tray_event(app_handle: &AppHandle, event: SystemTrayEvent) {
                 }
                 // send route back operation to the simulator
                 OperationId::RouteBack => {
-                    let host_agent = get_tauri_store(app_handle.clone())
-                        .unwrap()
-                        .debug_hosts
-                        .unwrap();
-                    host_agent.exec_operation(OperationId::RouteBack, json!({"value": id.clone()}));
+                    exec_host_operation(app_handle, OperationId::RouteBack, json!({}));
                 }
                 // send route forward operation to the simulator
-                OperationId::RouteForward => {}
+                OperationId::RouteForward => {
+                    exec_host_operation(app_handle, OperationId::RouteForward, json!({}));
+                }
                 // send route refresh operation to the simulator
-                OperationId::RouteRefresh => {}
+                OperationId::RouteRefresh => {
+                    exec_host_operation(app_handle, OperationId::RouteRefresh, json!({}));
+                }
                 // setEnv
                 OperationId::SetEnv => {
                     let clipboard_result = read_clipboard(app_handle);
@@ -118,17 +130,27 @@ pub fn on_system_tray_event(app_handle: &AppHandle, event: SystemTrayEvent) {
                     );
                     costa_window::create_env_edit_window(app_handle);
                 }
-                // login to the host
-                OperationId::Login => {}
-                // logout from the host
-                OperationId::Logout => {}
+                // login the app with the saved account
+                OperationId::Login => {
+                    let name = id
+                        .strip_prefix(OperationId::Login.to_string().as_str())
+                        .unwrap_or_default();
+                    match get_account(app_handle, name) {
+                        Ok(account) => exec_host_operation(
+                            app_handle,
+                            OperationId::Login,
+                            json!({ "value": account }),
+                        ),
+                        Err(e) => error!("Login Error: {}", e),
+                    }
+                }
+                // logout the app
+                OperationId::Logout => {
+                    exec_host_operation(app_handle, OperationId::Logout, json!({}));
+                }
                 // open the debug menu
                 OperationId::DebugMenu => {
-                    let host_agent = get_tauri_store(app_handle.clone())
-                        .unwrap()
-                        .debug_hosts
-                        .unwrap();
-                    host_agent.exec_operation(OperationId::DebugMenu, json!({}));
+                    exec_host_operation(app_handle, OperationId::DebugMenu, json!({}));
                 }
                 // open the simulator app
                 OperationId::OpenSimulator => {
@@ -162,9 +184,17 @@ pub fn on_system_tray_event(app_handle: &AppHandle, event: SystemTrayEvent) {
                                 }
                             }
                         }
-                        let devices = get_all_devices();
-                        boot_device(&dev_id);
-                        open_simulator_app();
+                        let backend = get_simctl_backend(&app_handle);
+                        match boot_device(backend.as_ref(), &dev_id).await {
+                            Ok(_) => {
+                                appearance::apply_saved_display_settings(&app_handle, dev_id)
+                                    .await
+                            }
+                            Err(e) => error!("Boot Device Error: {}", e),
+                        }
+                        if let Err(e) = open_simulator_app(backend.as_ref()).await {
+                            error!("Open Simulator Error: {}", e);
+                        }
                     });
                 }
                 OperationId::SelectHost => {
@@ -184,6 +214,160 @@ pub fn on_system_tray_event(app_handle: &AppHandle, event: SystemTrayEvent) {
                         hosts.into(),
                     );
                 }
+                // take a screenshot of the booted device
+                OperationId::Screenshot => {
+                    let app_handle = app_handle.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let device = match get_target_device(&app_handle, None) {
+                            Ok(device) => device,
+                            Err(e) => {
+                                error!("Screenshot Error: {}", e);
+                                return;
+                            }
+                        };
+                        match capture::take_screenshot(
+                            &app_handle,
+                            &device,
+                            ScreenshotFormat::default(),
+                            None,
+                        )
+                        .await
+                        {
+                            Ok(record) => info!("Screenshot saved: {}", record.path),
+                            Err(e) => error!("Screenshot Error: {}", e),
+                        }
+                    });
+                }
+                // start or stop recording the booted device
+                OperationId::Recording => {
+                    let app_handle = app_handle.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let device = match get_target_device(&app_handle, None) {
+                            Ok(device) => device,
+                            Err(e) => {
+                                error!("Recording Error: {}", e);
+                                return;
+                            }
+                        };
+                        let recording = app_handle
+                            .state::<RecordingSessions>()
+                            .is_recording(&device.udid);
+                        if recording {
+                            match capture::stop_recording(&app_handle, &device.udid).await {
+                                Ok(record) => info!("Recording saved: {}", record.path),
+                                Err(e) => error!("Recording Error: {}", e),
+                            }
+                        } else if let Err(e) =
+                            capture::start_recording(&app_handle, &device, None).await
+                        {
+                            error!("Recording Error: {}", e);
+                        }
+                    });
+                }
+                // resend the last push payload
+                OperationId::ResendPush => {
+                    let app_handle = app_handle.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = push::resend_last_push(&app_handle).await {
+                            error!("Resend Push Error: {}", e);
+                        }
+                    });
+                }
+                // toggle dark mode of the booted device
+                OperationId::DarkMode => {
+                    let app_handle = app_handle.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let changes = |current: DisplaySettings| DisplaySettings {
+                            appearance: Some(match current.appearance {
+                                Some(Appearance::Dark) => Appearance::Light,
+                                _ => Appearance::Dark,
+                            }),
+                            ..Default::default()
+                        };
+                        if let Err(e) = toggle_display_settings(&app_handle, changes).await {
+                            error!("Dark Mode Error: {}", e);
+                        }
+                    });
+                }
+                // toggle increase contrast of the booted device
+                OperationId::IncreaseContrast => {
+                    let app_handle = app_handle.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let changes = |current: DisplaySettings| DisplaySettings {
+                            increase_contrast: Some(!current.increase_contrast.unwrap_or(false)),
+                            ..Default::default()
+                        };
+                        if let Err(e) = toggle_display_settings(&app_handle, changes).await {
+                            error!("Increase Contrast Error: {}", e);
+                        }
+                    });
+                }
+                // set the text size of the booted device
+                OperationId::ContentSize => {
+                    let content_size = id
+                        .split(OperationId::ContentSize.to_string().as_str())
+                        .last()
+                        .unwrap()
+                        .parse::<ContentSize>();
+                    let content_size = match content_size {
+                        Ok(content_size) => content_size,
+                        Err(e) => {
+                            error!("Content Size Error: {}", e);
+                            return;
+                        }
+                    };
+                    let app_handle = app_handle.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let changes = |_: DisplaySettings| DisplaySettings {
+                            content_size: Some(content_size),
+                            ..Default::default()
+                        };
+                        if let Err(e) = toggle_display_settings(&app_handle, changes).await {
+                            error!("Content Size Error: {}", e);
+                        }
+                    });
+                }
+                // download the app of the catalog and install it into the booted device
+                OperationId::DownloadCatalogApp => {
+                    let item = id
+                        .split(OperationId::DownloadCatalogApp.to_string().as_str())
+                        .last()
+                        .unwrap();
+                    let (app_id, channel) = item.split_once('/').unwrap_or((item, ""));
+                    let request = catalog::get_catalog_app(app_handle, app_id).and_then(|app| {
+                        app.get_channel(Some(channel))
+                            .map(|channel| DownloadRequest {
+                                url: channel.url.clone(),
+                                sha256: channel.sha256.clone(),
+                                install: true,
+                                ..Default::default()
+                            })
+                            .ok_or_else(|| format!("channel not found: {}", item))
+                    });
+                    let res = request.and_then(|request| {
+                        download::start_download(app_handle, request).map_err(String::from)
+                    });
+                    if let Err(e) = res {
+                        error!("Download App Error: {}", e);
+                    }
+                }
+                // toggle the clipboard sync, selecting the running direction stops it
+                OperationId::PasteboardSync | OperationId::PasteboardSyncTwoWay => {
+                    let direction = match OperationId::from(id.to_owned()) {
+                        OperationId::PasteboardSyncTwoWay => SyncDirection::TwoWay,
+                        _ => SyncDirection::HostToDevice,
+                    };
+                    let sync = app_handle.state::<PasteboardSync>();
+                    if sync.direction() == Some(direction) {
+                        sync.stop();
+                    } else {
+                        pasteboard::start_pasteboard_sync(app_handle, direction);
+                    }
+                    let app_handle = app_handle.clone();
+                    tauri::async_runtime::spawn(async move {
+                        crate::tick::process_tray_menu_update(app_handle).await;
+                    });
+                }
                 OperationId::None => {}
             }
         }
@@ -191,6 +375,39 @@ pub fn on_system_tray_event(app_handle: &AppHandle, event: SystemTrayEvent) {
     }
 }
 
+/**
+ * send the operation to the selected debug host in the background, errors are logged
+ */
+fn exec_host_operation(app_handle: &AppHandle, operation: OperationId, params: serde_json::Value) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let name = operation.to_string();
+        let res = match get_tauri_store(app_handle).and_then(|store| store.debug_hosts) {
+            Some(host) => host.exec_operation(operation, params).await,
+            None => Err(HostError::NoSelectedHost),
+        };
+        match res {
+            Ok(resp) => debug_println!("{} response: {:?}", name, resp),
+            Err(e) => error!("Host Operation {} Error: {}", name, e),
+        }
+    });
+}
+
+/**
+ * read the settings of the booted device, apply the changes built from them, then refresh the tray
+ */
+async fn toggle_display_settings(
+    app_handle: &AppHandle,
+    changes: impl FnOnce(DisplaySettings) -> DisplaySettings,
+) -> Result<(), String> {
+    let device = get_target_device(app_handle, None)?;
+    let backend = get_simctl_backend(app_handle);
+    let current = appearance::read_display_settings(backend.as_ref(), &device.udid).await?;
+    appearance::update_display_settings(app_handle, &device, &changes(current)).await?;
+    crate::tick::process_tray_menu_update(app_handle.clone()).await;
+    Ok(())
+}
+
 fn read_clipboard(app_handle: &AppHandle) -> Result<ClipboardContent, DeQRError> {
     let handle = app_handle.clone();
     let clipboard = handle.state::<ClipboardManager>();
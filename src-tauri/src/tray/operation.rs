@@ -24,12 +24,30 @@ pub enum OperationId {
     RouteRefresh,
     #[strum(to_string = "op_set_env")]
     SetEnv,
-    #[strum(to_string = "op_login")]
+    #[strum(to_string = "op_login_")]
     Login,
     #[strum(to_string = "op_logout")]
     Logout,
     #[strum(to_string = "op_debug_menu")]
     DebugMenu,
+    #[strum(to_string = "capture_screenshot")]
+    Screenshot,
+    #[strum(to_string = "capture_recording")]
+    Recording,
+    #[strum(to_string = "push_resend")]
+    ResendPush,
+    #[strum(to_string = "ui_dark_mode")]
+    DarkMode,
+    #[strum(to_string = "ui_increase_contrast")]
+    IncreaseContrast,
+    #[strum(to_string = "ui_content_size_")]
+    ContentSize,
+    #[strum(to_string = "pasteboard_sync")]
+    PasteboardSync,
+    #[strum(to_string = "pasteboard_sync_two_way")]
+    PasteboardSyncTwoWay,
+    #[strum(to_string = "download_app_")]
+    DownloadCatalogApp,
 }
 
 impl From<OperationId> for String {
@@ -64,9 +82,24 @@ impl From<String> for OperationId {
             "op_route_forward" => OperationId::RouteForward,
             "op_route_refresh" => OperationId::RouteRefresh,
             "op_set_env" => OperationId::SetEnv,
-            "op_login" => OperationId::Login,
             "op_logout" => OperationId::Logout,
+            // "op_login_<account name>"
+            _ if id.starts_with(OperationId::Login.to_string().as_str()) => OperationId::Login,
             "op_debug_menu" => OperationId::DebugMenu,
+            "capture_screenshot" => OperationId::Screenshot,
+            "capture_recording" => OperationId::Recording,
+            "push_resend" => OperationId::ResendPush,
+            "ui_dark_mode" => OperationId::DarkMode,
+            "ui_increase_contrast" => OperationId::IncreaseContrast,
+            "pasteboard_sync" => OperationId::PasteboardSync,
+            "pasteboard_sync_two_way" => OperationId::PasteboardSyncTwoWay,
+            _ if id.starts_with(OperationId::ContentSize.to_string().as_str()) => {
+                OperationId::ContentSize
+            }
+            // "download_app_<app id>/<channel>"
+            _ if id.starts_with(OperationId::DownloadCatalogApp.to_string().as_str()) => {
+                OperationId::DownloadCatalogApp
+            }
             _ => OperationId::None,
         }
     }
@@ -1,17 +1,27 @@
 use debug_print::debug_println;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use strum::IntoEnumIterator;
 use tauri::{
     App, AppHandle, CustomMenuItem, Manager, MenuEntry, State, SystemTray, SystemTrayEvent,
     SystemTrayMenu, SystemTrayMenuItem, SystemTraySubmenu,
 };
 
 use crate::{
-    host::host::Host,
+    catalog::{get_app_catalog, AppCatalog},
+    host::{
+        account::{get_accounts, Account},
+        host::Host,
+        info::HostCapability,
+    },
     simulator::{
         self,
+        appearance::{get_display_settings, ContentSize, DisplaySettings},
+        backend::get_simctl_backend,
+        capture::RecordingSessions,
+        pasteboard::{PasteboardSync, SyncDirection},
         command::{boot_device, get_all_devices, open_simulator_app},
-        device::Device,
+        device::{Device, DeviceMap},
     },
     sotre::{get_tauri_store, set_tauri_store, update_tauri_store, AppHandleRef, StoreKey},
     tray::menu::{self, TrayMenu},
@@ -30,23 +40,24 @@ pub fn init_system_tray() -> SystemTray {
     SystemTray::new().with_menu(init_menu)
 }
 
-pub fn init_system_tray_menu(app: Option<&App>, handle: Option<AppHandle>) -> SystemTrayMenu {
-    if (app.is_none() && handle.is_none()) {
-        return SystemTrayMenu::new();
-    }
+pub async fn init_system_tray_menu(handle: AppHandle) -> SystemTrayMenu {
+    let backend = get_simctl_backend(&handle);
     let simulators = TrayMenu {
-        simulator: get_all_devices(),
+        simulator: get_all_devices(backend.as_ref()).await.unwrap_or_else(|e| {
+            log::error!("get all devices error: {}", e);
+            DeviceMap::default()
+        }),
     };
-    let store = get_tauri_store(handle.clone().unwrap());
+    let store = get_tauri_store(handle.clone());
 
     // update device state in store
     let _ = update_tauri_store(
-        handle.clone().unwrap(),
+        handle.clone(),
         StoreKey::Tray,
         Some(json!(simulators)).into(),
     );
     let _ = update_tauri_store(
-        handle.clone().unwrap(),
+        handle.clone(),
         StoreKey::Simulator,
         Some(json!(simulators.simulator)).into(),
     );
@@ -66,13 +77,36 @@ pub fn init_system_tray_menu(app: Option<&App>, handle: Option<AppHandle>) -> Sy
         .filter_map(|device| device)
         .collect();
     let hosts = menu_state.debug_hosts;
+    let has_last_push = menu_state
+        .push
+        .map(|push| push.last_push.is_some())
+        .unwrap_or(false);
     // debug_println!("Hosts: {:?}", hosts);
+    let recording = simulators
+        .simulator
+        .get_first_booted_device()
+        .map(|d| handle.state::<RecordingSessions>().is_recording(&d.udid))
+        .unwrap_or(false);
+    let pasteboard_sync = handle.state::<PasteboardSync>().direction();
+    // the real appearance settings of the booted device, None if no device is booted
+    let display_settings = match simulators.simulator.get_first_booted_device() {
+        Some(device) => Some(get_display_settings(&handle, &device.udid).await),
+        None => None,
+    };
+    let catalog = get_app_catalog(&handle);
+    let accounts = get_accounts(&handle);
 
     SystemTrayMenu::new()
         .set_devices(&simulators.simulator)
         .set_recent_devices(&recent_devices)
         .set_debug_hosts(&hosts)
-        .set_operation_menu()
+        .set_operation_menu(&hosts)
+        .set_account_menu(&hosts, &accounts)
+        .set_capture_menu(recording)
+        .set_push_menu(has_last_push)
+        .set_display_menu(&display_settings)
+        .set_pasteboard_menu(pasteboard_sync)
+        .set_catalog_menu(&catalog)
         .set_basic_menu()
 }
 
@@ -80,8 +114,14 @@ pub trait CostaTray {
     fn set_devices(&self, devices: &simulator::device::DeviceMap) -> SystemTrayMenu;
     fn set_recent_devices(self, devices: &Vec<&simulator::device::Device>) -> SystemTrayMenu;
     fn set_basic_menu(&self) -> SystemTrayMenu;
-    fn set_operation_menu(&self) -> SystemTrayMenu;
+    fn set_operation_menu(&self, hosts: &Option<Host>) -> SystemTrayMenu;
+    fn set_account_menu(&self, hosts: &Option<Host>, accounts: &[Account]) -> SystemTrayMenu;
     fn set_debug_hosts(self, hosts: &Option<Host>) -> SystemTrayMenu;
+    fn set_capture_menu(&self, recording: bool) -> SystemTrayMenu;
+    fn set_push_menu(&self, has_last_push: bool) -> SystemTrayMenu;
+    fn set_display_menu(&self, settings: &Option<DisplaySettings>) -> SystemTrayMenu;
+    fn set_pasteboard_menu(&self, sync: Option<SyncDirection>) -> SystemTrayMenu;
+    fn set_catalog_menu(&self, catalog: &AppCatalog) -> SystemTrayMenu;
 }
 
 impl CostaTray for SystemTrayMenu {
@@ -128,7 +168,7 @@ impl CostaTray for SystemTrayMenu {
     fn set_debug_hosts(mut self, hosts: &Option<Host>) -> SystemTrayMenu {
         if let Some(hosts) = hosts {
             // if hosts map is empty, return a prompt
-            if hosts.get_host_map().is_empty() {
+            if hosts.hosts.is_empty() {
                 return self
                     .clone()
                     .add_item(
@@ -140,10 +180,12 @@ impl CostaTray for SystemTrayMenu {
             // else if hosts map is not empty, return a submenu
             let sub_menu_hosts = {
                 let mut menu = SystemTrayMenu::new();
-                for (title, url) in hosts.host_map.iter() {
+                let mut hosts_by_name = hosts.hosts.iter().collect::<Vec<_>>();
+                hosts_by_name.sort_by_key(|(url, info)| (info.display_name(), url.to_string()));
+                for (url, info) in hosts_by_name {
                     let mut menu_item = CustomMenuItem::new(
                         OperationId::SelectHost.to_string() + url,
-                        title.clone(),
+                        info.display_name(),
                     );
                     if hosts.selected_host != None {
                         if url == hosts.selected_host.as_ref().unwrap().as_str() {
@@ -182,23 +224,183 @@ impl CostaTray for SystemTrayMenu {
             .add_item(CustomMenuItem::new(OperationId::InstallApp, "Install App"))
             .add_item(CustomMenuItem::new(OperationId::Quit, "Quit"))
     }
-    fn set_operation_menu(&self) -> SystemTrayMenu {
+    /**
+     * the operations declared by the selected host, nothing if no host is selected
+     */
+    fn set_operation_menu(&self, hosts: &Option<Host>) -> SystemTrayMenu {
+        let supports = |operation: &OperationId| {
+            hosts
+                .as_ref()
+                .is_some_and(|hosts| hosts.supports_operation(operation))
+        };
+        let items = [
+            (OperationId::RouteBack, "Route Back"),
+            (OperationId::RouteForward, "Route Forward"),
+            (OperationId::RouteRefresh, "Route Refresh"),
+            (OperationId::SetEnv, "Set Env"),
+            (OperationId::DebugMenu, "Open Debug Menu"),
+        ];
+        let items = items
+            .into_iter()
+            .filter(|(operation, _)| supports(operation))
+            .collect::<Vec<_>>();
+        if items.is_empty() {
+            return self.clone();
+        }
+        items
+            .into_iter()
+            .fold(self.clone(), |menu, (operation, title)| {
+                menu.add_item(CustomMenuItem::new(operation, title))
+            })
+            .add_native_item(SystemTrayMenuItem::Separator)
+    }
+    fn set_account_menu(&self, hosts: &Option<Host>, accounts: &[Account]) -> SystemTrayMenu {
+        let info = hosts
+            .as_ref()
+            .and_then(|hosts| hosts.get_selected_host_info());
+        let can_login = info.is_some_and(|info| info.supports(HostCapability::Login));
+        let can_logout = info.is_some_and(|info| info.supports(HostCapability::Logout));
+        if !can_login && !can_logout {
+            return self.clone();
+        }
+        let mut login = SystemTrayMenu::new();
+        for account in accounts.iter() {
+            login = login.add_item(CustomMenuItem::new(
+                OperationId::Login.to_string() + account.name.as_str(),
+                account.name.clone(),
+            ));
+        }
+        if accounts.is_empty() {
+            let item = CustomMenuItem::new("login_no_account".to_string(), "No Saved Account");
+            login = login.add_item(item.disabled());
+        }
+        let mut menu = self.clone();
+        if can_login {
+            menu = menu.add_submenu(SystemTraySubmenu::new("Login", login));
+        }
+        if can_logout {
+            menu = menu.add_item(CustomMenuItem::new(OperationId::Logout, "Logout"));
+        }
+        menu.add_native_item(SystemTrayMenuItem::Separator)
+    }
+    fn set_push_menu(&self, has_last_push: bool) -> SystemTrayMenu {
+        let mut menu_item = CustomMenuItem::new(OperationId::ResendPush, "Resend Last Push");
+        if !has_last_push {
+            menu_item = menu_item.disabled();
+        }
+        self.clone()
+            .add_item(menu_item)
+            .add_native_item(SystemTrayMenuItem::Separator)
+    }
+    fn set_display_menu(&self, settings: &Option<DisplaySettings>) -> SystemTrayMenu {
+        // no booted device, nothing to toggle
+        let settings = match settings {
+            Some(settings) => settings,
+            None => return self.clone(),
+        };
+        let mut dark_mode = CustomMenuItem::new(OperationId::DarkMode, "Dark Mode");
+        if settings.appearance == Some(simulator::appearance::Appearance::Dark) {
+            dark_mode = dark_mode.selected();
+        }
+        if settings.appearance.is_none() {
+            dark_mode = dark_mode.disabled();
+        }
+        let mut increase_contrast =
+            CustomMenuItem::new(OperationId::IncreaseContrast, "Increase Contrast");
+        if settings.increase_contrast == Some(true) {
+            increase_contrast = increase_contrast.selected();
+        }
+        if settings.increase_contrast.is_none() {
+            increase_contrast = increase_contrast.disabled();
+        }
+        let sub_menu_content_size = {
+            let mut menu = SystemTrayMenu::new();
+            for content_size in ContentSize::iter() {
+                let mut menu_item = CustomMenuItem::new(
+                    OperationId::ContentSize.to_string() + content_size.to_string().as_str(),
+                    content_size.to_string(),
+                );
+                if settings.content_size == Some(content_size) {
+                    menu_item = menu_item.selected();
+                }
+                menu = menu.add_item(menu_item);
+            }
+            SystemTraySubmenu::new("Text Size", menu)
+        };
+        let sub_menu_display = SystemTraySubmenu::new(
+            "Appearance",
+            SystemTrayMenu::new()
+                .add_item(dark_mode)
+                .add_item(increase_contrast)
+                .add_submenu(sub_menu_content_size),
+        );
+        self.clone()
+            .add_submenu(sub_menu_display)
+            .add_native_item(SystemTrayMenuItem::Separator)
+    }
+    fn set_pasteboard_menu(&self, sync: Option<SyncDirection>) -> SystemTrayMenu {
+        let mut host_to_device =
+            CustomMenuItem::new(OperationId::PasteboardSync, "Sync Clipboard to Device");
+        if sync == Some(SyncDirection::HostToDevice) {
+            host_to_device = host_to_device.selected();
+        }
+        let mut two_way = CustomMenuItem::new(
+            OperationId::PasteboardSyncTwoWay,
+            "Sync Clipboard Both Ways",
+        );
+        if sync == Some(SyncDirection::TwoWay) {
+            two_way = two_way.selected();
+        }
+        self.clone()
+            .add_item(host_to_device)
+            .add_item(two_way)
+            .add_native_item(SystemTrayMenuItem::Separator)
+    }
+    fn set_catalog_menu(&self, catalog: &AppCatalog) -> SystemTrayMenu {
+        if catalog.apps.is_empty() {
+            return self.clone();
+        }
+        let sub_menu_apps = {
+            let mut menu = SystemTrayMenu::new();
+            for app in catalog.apps.iter() {
+                let item_id = |channel: &str| {
+                    OperationId::DownloadCatalogApp.to_string() + app.id.as_str() + "/" + channel
+                };
+                // one item for the default channel, a submenu if the app has more
+                if app.channels.len() == 1 {
+                    menu = menu.add_item(CustomMenuItem::new(
+                        item_id(&app.channels[0].name),
+                        app.name.clone(),
+                    ));
+                } else if !app.channels.is_empty() {
+                    let mut channels = SystemTrayMenu::new();
+                    for channel in app.channels.iter() {
+                        channels = channels.add_item(CustomMenuItem::new(
+                            item_id(&channel.name),
+                            channel.name.clone(),
+                        ));
+                    }
+                    menu = menu.add_submenu(SystemTraySubmenu::new(app.name.clone(), channels));
+                }
+            }
+            SystemTraySubmenu::new("Download App", menu)
+        };
+        self.clone().add_submenu(sub_menu_apps)
+    }
+    fn set_capture_menu(&self, recording: bool) -> SystemTrayMenu {
+        let recording_label = if recording {
+            "Stop Recording"
+        } else {
+            "Start Recording"
+        };
         self.clone()
-            .add_item(CustomMenuItem::new(OperationId::RouteBack, "Route Back"))
-            .add_item(CustomMenuItem::new(
-                OperationId::RouteForward,
-                "Route Forward",
-            ))
             .add_item(CustomMenuItem::new(
-                OperationId::RouteRefresh,
-                "Route Refresh",
+                OperationId::Screenshot,
+                "Take Screenshot",
             ))
-            .add_item(CustomMenuItem::new(OperationId::SetEnv, "Set Env"))
-            // .add_item(CustomMenuItem::new(OperationId::Login, "Login"))
-            // .add_item(CustomMenuItem::new(OperationId::Logout, "Logout"))
             .add_item(CustomMenuItem::new(
-                OperationId::DebugMenu,
-                "Open Debug Menu",
+                OperationId::Recording,
+                recording_label,
             ))
             .add_native_item(SystemTrayMenuItem::Separator)
     }
@@ -0,0 +1,36 @@
+use debug_print::debug_println;
+use log::error;
+use tauri::{FileDropEvent, GlobalWindowEvent, Manager, WindowEvent};
+
+use crate::{
+    command::util::get_target_device,
+    simulator::{backend::get_simctl_backend, media::MediaResult},
+};
+
+pub fn on_window_event(event: GlobalWindowEvent) {
+    match event.event() {
+        // add the files dropped on a window to the booted device
+        WindowEvent::FileDrop(FileDropEvent::Dropped(paths)) => {
+            let window = event.window().clone();
+            let paths = paths
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect::<Vec<_>>();
+            debug_println!("file dropped: {:?}", paths);
+            tauri::async_runtime::spawn(async move {
+                let app_handle = window.app_handle();
+                let device = match get_target_device(&app_handle, None) {
+                    Ok(device) => device,
+                    Err(e) => {
+                        error!("Add Media Error: {}", e);
+                        return;
+                    }
+                };
+                let backend = get_simctl_backend(&app_handle);
+                let results: Vec<MediaResult> = device.add_media(backend.as_ref(), &paths).await;
+                let _ = window.emit("media_added", results);
+            });
+        }
+        _ => {}
+    }
+}
@@ -1,2 +1,3 @@
 pub mod costa_window;
-pub mod costa_router;
\ No newline at end of file
+pub mod costa_router;
+pub mod event;
\ No newline at end of file
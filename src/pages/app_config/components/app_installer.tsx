@@ -1,9 +1,10 @@
 import { Button, List, Message, Tag } from "@arco-design/web-react";
 import { invoke } from "@tauri-apps/api/tauri";
 import { useState } from "react";
+import { PackageRecord } from "../types";
 
 interface AppInstallerProps {
-  appList: string[];
+  appList: PackageRecord[];
 }
 
 const AppInstaller: React.FC<AppInstallerProps> = ({ appList }) => {
@@ -29,9 +30,10 @@ const AppInstaller: React.FC<AppInstallerProps> = ({ appList }) => {
       return (
         <List.Item key={index}>
           <div style={{ display: 'flex', justifyContent: 'space-between' }}>
-            <div style={{ display: 'inline-block' }}>{item}
+            <div style={{ display: 'inline-block' }}>
+              {item.name} {item.version && <Tag>{item.version}</Tag>} {item.bundle_id}
             </div>
-            <Button type="primary" onClick={() => handleAppInstall(item)} loading={appInstalling}>
+            <Button type="primary" onClick={() => handleAppInstall(item.path)} loading={appInstalling}>
               Install
             </Button>
           </div>
@@ -1,6 +1,6 @@
 import React, { useEffect, useState } from 'react';
 import { List, Button } from '@arco-design/web-react';
-import { Runtime } from '../types';
+import { PackageRecord, Runtime } from '../types';
 import { getAvailableSimulatorRuntimeList } from '../../../utils';
 import { invoke } from '@tauri-apps/api/tauri';
 
@@ -9,12 +9,12 @@ interface SimInstallerProps {
 }
 
 const SimInstaller: React.FC<SimInstallerProps> = ({ installedRuntimes }) => {
-  const [availableRuntimes, setAvailableRuntimes] = useState<string[]>([]);
+  const [availableRuntimes, setAvailableRuntimes] = useState<PackageRecord[]>([]);
   const [loading, setLoading] = useState(false);
 
-  const onInstall = async (runtime: string) => {
+  const onInstall = async (runtime: PackageRecord) => {
     setLoading(true);
-    await invoke('install_simulator', { path: runtime });
+    await invoke('install_simulator', { path: runtime.path });
     setLoading(false);
   }
 
@@ -23,15 +23,15 @@ const SimInstaller: React.FC<SimInstallerProps> = ({ installedRuntimes }) => {
     const interval = setInterval(() => {
       getAvailableSimulatorRuntimeList().then((res) => {
         // console.log('res: ', res);
-        setAvailableRuntimes(res as string[]);
+        setAvailableRuntimes(res as PackageRecord[]);
       });
     }, 1000);
     return () => clearInterval(interval);
   }, []);
 
-  const isInstalled = (runtime: string) => {
+  const isInstalled = (runtime: PackageRecord) => {
     return installedRuntimes.some(
-      (installed) => runtime.includes(installed.version)
+      (installed) => runtime.version === installed.version || runtime.name.includes(installed.version)
     );
   };
 
@@ -54,7 +54,7 @@ const SimInstaller: React.FC<SimInstallerProps> = ({ installedRuntimes }) => {
               </Button>
             ]}
           >
-            {item}
+            {item.name}
           </List.Item>
         )}
       />
@@ -2,7 +2,7 @@ import React, { useEffect, useState } from 'react';
 import { Button, Typography, Space, Message, List, Modal } from '@arco-design/web-react';
 import { invoke } from '@tauri-apps/api/tauri';
 import { getInstalledSimulatorRuntimeList } from '../../utils';
-import { RuntimeMap } from './types';
+import { AppCatalog, PackageRecord, RuntimeMap } from './types';
 import SimInstaller from './components/sim_installer';
 import AppInstaller from './components/app_installer';
 
@@ -57,7 +57,14 @@ const AppConfig: React.FC = () => {
     })
   };
 
-  const [appList, setAppList] = useState<string[]>([]);
+  const [appList, setAppList] = useState<PackageRecord[]>([]);
+  const [catalog, setCatalog] = useState<AppCatalog>({ apps: [] });
+
+  useEffect(() => {
+    invoke('get_app_catalog').then((res) => {
+      setCatalog(res as AppCatalog);
+    });
+  }, []);
 
   const handleAppDownload = async (app: string) => {
     try {
@@ -77,7 +84,7 @@ const AppConfig: React.FC = () => {
     // get app list as interval
     const interval = setInterval(() => {
       invoke('get_app_pkg_list').then((res) => {
-        setAppList(res as string[]);
+        setAppList(res as PackageRecord[]);
       });
     }, 1000);
     return () => clearInterval(interval);
@@ -130,12 +137,11 @@ const AppConfig: React.FC = () => {
       <div>
         <Title heading={3}>Install App to Simulator</Title>
         <div style={{ display: 'flex', gap: 10 }}>
-          <Button type="primary" onClick={() => handleAppDownload('app1')}>
-            Download App1 Latest
-          </Button>
-          <Button type="primary" onClick={() => handleAppDownload('app2')} status='warning'>
-            Download App2 Latest
-          </Button>
+          {catalog.apps.map((app) => (
+            <Button key={app.id} type="primary" onClick={() => handleAppDownload(app.id)}>
+              Download {app.name} Latest
+            </Button>
+          ))}
         </div>
         <div style={{ marginTop: 10 }}>
           <AppInstaller appList={appList} />
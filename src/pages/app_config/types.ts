@@ -18,3 +18,38 @@ export interface Runtime {
   signatureState: string;
   sizeBytes: number;
 }
+
+export interface PackageRecord {
+  sha256: string;
+  kind: 'app' | 'runtime';
+  name: string;
+  bundle_id?: string;
+  version?: string;
+  build?: string;
+  min_os_version?: string;
+  source_url?: string;
+  source_paths: string[];
+  path: string;
+  size: number;
+  added_at: number;
+}
+
+export interface AppChannel {
+  name: string;
+  url: string;
+  sha256?: string;
+}
+
+export interface CatalogApp {
+  id: string;
+  name: string;
+  bundle_id?: string;
+  channels: AppChannel[];
+  scheme_rewrites: { from: string; to: string }[];
+  envs: string[];
+}
+
+export interface AppCatalog {
+  apps: CatalogApp[];
+  simulator_runtime_url?: string;
+}
@@ -5,6 +5,7 @@ import { listen } from '@tauri-apps/api/event';
 import { Button, Card, Divider, Empty, Input, List, Message, Switch } from '@arco-design/web-react';
 import { message } from '@tauri-apps/api/dialog';
 import { useLocalStorageState } from 'ahooks';
+import { getSelectedHostInfo, hostSupports, HostInfo } from '../utils';
 
 interface EnvConfig {
   name: string;
@@ -22,6 +23,7 @@ const EnvEditor: React.FC = () => {
     defaultValue: [],
   });
   const [geckoOnline, setGeckoOnline] = useState(true);
+  const [hostInfo, setHostInfo] = useState<HostInfo | null>(null);
 
   // if the system is in dark mode by prefers-color-scheme: dark
   // listen to the system's dark mode change
@@ -36,6 +38,19 @@ const EnvEditor: React.FC = () => {
     };
   }, []);
 
+  useEffect(() => {
+    // only enable the operations the selected debug host supports
+    const refreshHostInfo = () => getSelectedHostInfo().then(setHostInfo).catch(() => setHostInfo(null));
+    refreshHostInfo();
+    const unlisten = Promise.all([
+      listen('debug_host_added', refreshHostInfo),
+      listen('debug_host_removed', refreshHostInfo),
+    ]);
+    return () => {
+      unlisten.then(fns => fns.forEach(fn => fn()));
+    };
+  }, []);
+
   useEffect(() => {
     // Get clipboard value on component mount
     getClipboardValue();
@@ -50,6 +65,8 @@ const EnvEditor: React.FC = () => {
     }
   };
 
+  const canSetEnv = hostSupports(hostInfo, 'setEnv');
+
   const handleSetEnv = async (env: 'boe' | 'ppe', isOn: boolean) => {
     try {
       console.log(`set_${env}`, { params: { name: editorValue.name.trim(), envType: env, isOn: isOn, geckoOnline: geckoOnline } });
@@ -102,10 +119,10 @@ const EnvEditor: React.FC = () => {
         <Card title='Env Editor' extra={
           <div style={{ display: 'flex', gap: '8px' }}>
             <Switch checked={geckoOnline} onChange={(value) => setGeckoOnline(value)} checkedText='Gecko Online' uncheckedText='Gecko Offline' />
-            <Button type='primary' onClick={async () => await handleSetEnv('boe', true)}>Set as Boe</Button>
-            <Button type='primary' onClick={async () => await handleSetEnv('ppe', true)}>Set as PPE</Button>
-            <Button type='primary' onClick={async () => await handleSetEnv('boe', false)}>Set as Boe Off</Button>
-            <Button type='primary' onClick={async () => await handleSetEnv('ppe', false)}>Set as PPE Off</Button>
+            <Button type='primary' disabled={!canSetEnv} onClick={async () => await handleSetEnv('boe', true)}>Set as Boe</Button>
+            <Button type='primary' disabled={!canSetEnv} onClick={async () => await handleSetEnv('ppe', true)}>Set as PPE</Button>
+            <Button type='primary' disabled={!canSetEnv} onClick={async () => await handleSetEnv('boe', false)}>Set as Boe Off</Button>
+            <Button type='primary' disabled={!canSetEnv} onClick={async () => await handleSetEnv('ppe', false)}>Set as PPE Off</Button>
           </div>
         }
         >
@@ -4,6 +4,7 @@ import { Editor } from '@monaco-editor/react';
 import { listen } from '@tauri-apps/api/event';
 import { Button, Card, Divider, Empty, Input, List } from '@arco-design/web-react';
 import { useLocalStorageState } from 'ahooks';
+import { getSelectedHostInfo, hostSupports, HostInfo } from '../utils';
 
 interface SchemaRecord {
   schema: string;
@@ -17,6 +18,7 @@ const SchemaRouter: React.FC = () => {
     defaultValue: [],
   });
   const [isDarkMode, setIsDarkMode] = useState(false);
+  const [hostInfo, setHostInfo] = useState<HostInfo | null>(null);
   const [favorateSchemas, setfavorateSchemas] = useLocalStorageState<SchemaRecord[]>('favorateSchemas', {
     defaultValue: [],
   });
@@ -34,6 +36,19 @@ const SchemaRouter: React.FC = () => {
     };
   }, []);
 
+  useEffect(() => {
+    // only enable the operations the selected debug host supports
+    const refreshHostInfo = () => getSelectedHostInfo().then(setHostInfo).catch(() => setHostInfo(null));
+    refreshHostInfo();
+    const unlisten = Promise.all([
+      listen('debug_host_added', refreshHostInfo),
+      listen('debug_host_removed', refreshHostInfo),
+    ]);
+    return () => {
+      unlisten.then(fns => fns.forEach(fn => fn()));
+    };
+  }, []);
+
   useEffect(() => {
     // Get clipboard value on component mount
     getClipboardValue();
@@ -90,7 +105,7 @@ const SchemaRouter: React.FC = () => {
   return (
     <div style={{ display: 'flex', height: '100vh', padding: 0, margin: 0, overflow: 'hidden' }}>
       <div style={{ flex: 1, marginRight: '16px', display: 'flex', flexDirection: 'column', justifyContent: 'space-between', height: '100%' }}>
-        <Card title='Schema Editor' extra={<Button type='primary' onClick={handleGotoSchema}>Go to Schema</Button>}>
+        <Card title='Schema Editor' extra={<Button type='primary' disabled={!hostSupports(hostInfo, 'routeSwitch')} onClick={handleGotoSchema}>Go to Schema</Button>}>
           <Editor
             width="70vw"
             // height="50vh"
@@ -9,3 +9,33 @@ export const getAvailableSimulatorRuntimeList = async () => {
   const runtimes = await invoke('get_available_simulator_runtime_list');
   return runtimes;
 };
+
+export type HostCapability =
+  | 'routeSwitch'
+  | 'routeBack'
+  | 'routeForward'
+  | 'routeRefresh'
+  | 'setEnv'
+  | 'login'
+  | 'logout'
+  | 'debugMenu'
+  | 'currentRoute';
+
+export interface HostInfo {
+  protocolVersion: number;
+  appName: string;
+  bundleId?: string;
+  appVersion?: string;
+  deviceName?: string;
+  deviceUdid?: string;
+  operations: HostCapability[];
+}
+
+// null if no debug host is selected or the selected host stopped answering
+export const getSelectedHostInfo = async () => {
+  const info = await invoke('get_selected_host_info');
+  return info as HostInfo | null;
+};
+
+export const hostSupports = (info: HostInfo | null, operation: HostCapability) =>
+  !!info && info.operations.includes(operation);